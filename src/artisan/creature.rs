@@ -0,0 +1,160 @@
+// artisan/creature.rs
+// Describes the class of animate objects throughout the game, ie the ship's crew and anyone else who moves around
+
+/* The format of the creature defns as of Oct 16 2026:
+ *  [
+ *    {
+ *      "name": "crewmember",      - string, the name used to request this creature
+ *      "desc": "...",             - string, optional, the long description
+ *      "body": ["0,0 @ yellow"],  - array of strings, optional, in the same format as the item bodies
+ *      "view_range": 8,           - integer, optional, the range of their Viewshed
+ *      "speed": 100,              - integer, optional, as a percentage of normal speed
+ *      "role": "engineer"         - string, optional, the name of a CrewRole if they're one of the crew
+ *    }
+ *  ]
+ * Every creature gets the same bundle of actor components (Mobile, Obstructive, Memory, etc) on top of these
+ * There are no Health or Dialogue components yet; when there are, they'll belong here too
+ */
+
+// ###: EXTERNAL LIBRARIES
+use simplelog::*;
+use serde::{Deserialize, Serialize};
+use bevy::prelude::{
+	Entity,
+	World,
+};
+
+// ###: INTERNAL LIBRARIES
+use crate::components::*;
+use crate::components::{
+	Color,
+	Direction,
+};
+use crate::artisan::read_json_entries;
+
+// ###: CONSTANTS
+pub const CREATURE_DEFNS_FILE: &str = "resources/creatures_v1.json";
+/// The Viewshed range of a creature whose defn doesn't specify one
+pub const DEFAULT_VIEW_RANGE: i32 = 8;
+
+//  ###: COMPLEX TYPES
+//   ##: THE CREATURE BUILDER
+//    #: CreatureBuilder
+/// Provides a facility for creating NPCs and other actors, in the same fashion as the ItemBuilder
+#[derive(Clone, Debug, Default)]
+pub struct CreatureBuilder {
+	pub spawn_count: i32,
+	desc:     Option<Description>,
+	body:     Option<Body>,
+	viewshed: Option<Viewshed>,
+	crew:     Option<Crew>,
+	speed:    Option<Speed>,
+	creature_dict: Vec<RawCreature>,
+}
+impl CreatureBuilder {
+	/// CreatureBuilder constructor
+	pub fn new() -> CreatureBuilder {
+		CreatureBuilder {
+			creature_dict: load_creature_defns(CREATURE_DEFNS_FILE),
+			..CreatureBuilder::default()
+		}
+	}
+	/// Starting incantation in the chain to create new creatures
+	/// If there's no defn for the requested kind of creature, a plain unnamed figure will be made instead
+	pub fn create(&mut self, kind: &str) -> &mut CreatureBuilder {
+		let creature_data = if let Some(value) = self.creature_dict.iter().find(|x| x.name == kind).cloned() { value } else {
+			warn!("* no creature defn for '{}', using a generic one", kind); // DEBUG: report missing creature defn
+			RawCreature::default()
+		};
+		self.desc = Some(Description::new().name(&creature_data.name).desc(&creature_data.desc));
+		self.body = if creature_data.body.is_empty() {
+			Some(Body::small(Position::default(), ScreenCell::new().glyph("@").fg(Color::Yellow).bg(Color::Black)))
+		} else {
+			Some(Body::new_from_str(creature_data.body.clone()))
+		};
+		self.viewshed = Some(Viewshed::new(creature_data.view_range.unwrap_or(DEFAULT_VIEW_RANGE)));
+		self.speed = creature_data.speed.map(Speed::new);
+		self.crew = None;
+		if let Some(role_name) = creature_data.role.as_ref() {
+			self.role(role_name);
+		}
+		self
+	}
+	/// Gives the creature its own name, in place of the one from its defn
+	pub fn name(&mut self, new_name: &str) -> &mut CreatureBuilder {
+		if let Some(desc) = self.desc.as_mut() {
+			desc.name = new_name.to_string();
+		}
+		self
+	}
+	/// Makes the creature a member of the crew, using the name of a CrewRole; an unknown role is reported and ignored
+	pub fn role(&mut self, role_name: &str) -> &mut CreatureBuilder {
+		match role_name.parse::<CrewRole>() {
+			Ok(role) => { self.crew = Some(Crew::new(role)); }
+			Err(_) => { warn!("* unknown crew role '{}' for creature, skipping it", role_name); } // DEBUG: report bad crew role
+		}
+		self
+	}
+	/// Sets the creature's position in the game world
+	pub fn at(&mut self, posn: Position) -> &mut CreatureBuilder {
+		if let Some(body) = self.body.as_mut() {
+			body.move_to(posn);
+		}
+		self
+	}
+	/// Constructs the creature in the specified World, and returns its Entity ID along with the Positions it occupies
+	pub fn build(&mut self, world: &mut World) -> (Entity, Vec<Position>) {
+		self.spawn_count += 1;
+		let body = self.body.take().unwrap_or_default();
+		let shape = body.posns();
+		let mut new_creature = world.spawn((
+			ActionSet::new(),
+			Facing::new(Direction::S),
+			self.desc.take().unwrap_or_default(),
+			body.ref_posn,
+			body,
+			self.viewshed.take().unwrap_or(Viewshed::new(DEFAULT_VIEW_RANGE)),
+			Mobile::default(),
+			Obstructive::default(),
+			Container::default(),
+			Memory::new(),
+		));
+		if let Some(crew)  = self.crew.take() { new_creature.insert(crew); }
+		if let Some(speed) = self.speed.take() { new_creature.insert(speed); }
+		(new_creature.id(), shape)
+	}
+}
+//   ##: THE CREATURE DICTIONARY
+//    #: RawCreature
+/// Contains the creature's definition as it was imported from external storage
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RawCreature {
+	pub name: String,
+	#[serde(default)]
+	pub desc: String,
+	#[serde(default)]
+	pub body: Vec<String>,
+	#[serde(default)]
+	pub view_range: Option<i32>,
+	#[serde(default)]
+	pub speed: Option<u32>,
+	#[serde(default)]
+	pub role: Option<String>,
+}
+
+//  ###: SIMPLE TYPES AND HELPERS
+/// Loads the creature definitions from external storage, reporting any entries that could not be used
+pub fn load_creature_defns(filename: &str) -> Vec<RawCreature> {
+	let mut errors = Vec::new();
+	let mut creatures: Vec<RawCreature> = read_json_entries(filename, &mut errors);
+	creatures.retain(|x| {
+		if x.name.is_empty() { errors.push(format!("! {}: a creature was defined without a name", filename)); }
+		!x.name.is_empty()
+	});
+	for error in errors.iter() {
+		error!("{}", error);
+	}
+	creatures
+}
+
+// EOF
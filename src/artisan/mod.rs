@@ -36,18 +36,24 @@
  *   AccessPort
  *   ActionSet
  *   Container
+ *   Critical
+ *   Friendly
  *   IsCarried
  *   Memory
  *   Mobile
  *   Networkable
  *   Obstructive
  * COMPLEX:
+ *   Crew(role as a CrewRole name, ie "role:engineer")
  *   Device(discharge rate in volts/turn as i32)
  *   Key(key id as i32)
  *   Lockable(initial state as bool, matching key id as i32)
+ *   Multilevel(number of decks spanned as i32)
  *   Opaque(current state as bool)
  *   Openable(initial state as bool, open/closed glyphs)
  *   Portable(carrier of item as Entity)
+ *   RenderLayer(draw priority as i32)
+ *   Speed(percentage of normal speed as u32)
  *   Viewshed(range in tiles as i32)
  */
 
@@ -68,12 +74,20 @@ use bevy::prelude::{
 	World,
 };
 use bevy::ecs::world::EntityMut;
+use bevy::utils::HashMap;
 use bevy_turborand::*;
 
 // ###: INTERNAL LIBRARIES
 use crate::components::*;
 use crate::planq::*;
-use crate::mason::logical_map::SpawnTemplate;
+use crate::mason::logical_map::{PlacementMode, SpawnTemplate};
+pub mod creature;
+
+// ###: CONSTANTS
+pub const ITEM_DEFNS_FILE: &str = "resources/furniture_items_v3.json";
+pub const ITEM_SETS_FILE: &str = "resources/furniture_sets_v2.json";
+/// How many times to retry placing a Critical item that was spawned somewhere unreachable
+pub const CRITICAL_SPAWN_ATTEMPTS: usize = 5;
 
 //  ###: COMPLEX TYPES
 //   ##: THE ITEM BUILDER
@@ -84,22 +98,29 @@ use crate::mason::logical_map::SpawnTemplate;
 pub struct ItemBuilder {
 	request_list: Vec<ItemRequest>, // The template ID, the item name, ...
 	pub spawn_count: i32,
+	pub deck_count: usize, // How many decks the ship has, so that Multilevel items don't reach up past the top one
 	body:     Option<Body>,
 	desc:     Option<Description>,
 	actions:  Option<ActionSet>,
 	// Optional/auxiliary components
 	access:   Option<AccessPort>,
 	contain:  Option<Container>,
+	critical: Option<Critical>,
+	crew:     Option<Crew>,
 	device:   Option<Device>,
+	friendly: Option<Friendly>,
 	is_carried: Option<IsCarried>,
 	key:      Option<Key>,
 	lock:     Option<Lockable>,
 	mobile:   Option<Mobile>,
+	multilevel: Option<Multilevel>,
 	network:  Option<Networkable>,
 	obstruct: Option<Obstructive>,
 	opaque:   Option<Opaque>,
 	open:     Option<Openable>,
 	portable: Option<Portable>,
+	render:   Option<RenderLayer>,
+	speed:    Option<Speed>,
 	planq:    Option<Planq>,
 	#[reflect(ignore)]
 	item_dict:     ItemDict,
@@ -113,122 +134,68 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 		// Return the new object instance
 		// -- OLD METHOD
 		ItemBuilder {
-			item_dict: load_furniture_defns("resources/furniture_items_v3.json", "resources/furniture_sets_v2.json"),
+			item_dict: load_furniture_defns(ITEM_DEFNS_FILE, ITEM_SETS_FILE),
 			..ItemBuilder::default()
 		}
 	}
+	/// Re-reads the item definition files and swaps them in, so that content edits can be tested without a restart
+	/// Only newly-built items will use the new defns; if anything fails to parse, the old defns are kept
+	pub fn reload_defns(&mut self) -> Result<usize, Vec<String>> {
+		let (new_dict, errors) = read_furniture_defns(ITEM_DEFNS_FILE, ITEM_SETS_FILE);
+		if !errors.is_empty() { return Err(errors); }
+		self.item_dict = new_dict;
+		Ok(self.item_dict.furniture.len())
+	}
+	/// Returns true if the named item is defined with the Critical tag
+	pub fn is_critical(&self, item_name: &str) -> bool {
+		if let Some(item_data) = self.item_dict.furniture.iter().find(|x| x.name == item_name) {
+			return item_data.extra.iter().any(|x| x.split(' ').next() == Some("critical"));
+		}
+		false
+	}
+	/// Produces the full set of Positions that the named item would cover if it was spawned at the given Position,
+	/// including the copies of its footprint on the decks above if it's a Multilevel item
+	pub fn footprint(&self, item_name: &str, posn: Position) -> Vec<Position> {
+		let item_data = if let Some(value) = self.item_dict.furniture.iter().find(|x| x.name == item_name) { value } else {
+			return vec![posn];
+		};
+		let mut body = Body::new_from_str(item_data.body.clone());
+		body.move_to(posn);
+		let decks = item_data.extra.iter()
+			.find(|x| x.split(' ').next() == Some("multilevel"))
+			.and_then(|x| x.split(' ').find_map(|y| y.strip_prefix("decks:")))
+			.and_then(|x| x.parse::<i32>().ok());
+		if let Some(decks) = decks { body.span_decks(decks, self.deck_count); }
+		body.posns()
+	}
 	/// Starting incantation in the chain to create new items
 	pub fn create(&mut self, new_item: &str) -> &mut ItemBuilder {
 		//debug!("* ItemBuilder create() request: {}", new_item); // DEBUG: log item builder request
-		if let Some(item_data) = self.item_dict.furniture.iter().find(|x| x.name == new_item) {
+		// The dict is taken out while the parsers run, so that they are free to modify the rest of the builder
+		let dict = std::mem::take(&mut self.item_dict);
+		if let Some(item_data) = dict.furniture.iter().find(|x| x.name == new_item) {
 			self.desc = Some(Description::new().name(&item_data.name).desc(&item_data.desc));
 			debug!("* recvd item_data.body: {:?}", item_data.body.clone()); // DEBUG: log new Body component
 			self.body = Some(Body::new_from_str(item_data.body.clone()));
-			if !item_data.extra.is_empty() {
-				// Parse and add any additional components that are in the item's definition
-				//debug!("* recvd item_data.extra: {:?}", item_data.extra); // DEBUG: log any extra components
-				for component in item_data.extra.iter() {
-					//debug!("* raw component value: {}", component); // DEBUG: log raw component values
-					// HINT: This will in fact return the entire string if the string consists of only a single word
-					//    let new_string: Vec<&str> = component.split(' ').collect();
-					let mut new_cmpnt = component.split(' ');
-					let part = new_cmpnt.next().unwrap_or(""); // This is a closure that returns an empty string
-					let details: Vec<&str> = new_cmpnt.collect();
-					let error_msg = "! ERR: Could not parse key:value for ";
-					match part {
-						"accessport"  => { self.access = Some(AccessPort::default()); } // tag component
-						"actionset"   => { self.actions = Some(ActionSet::default()); } // tag component
-						"container"   => { self.contain = Some(Container::default()); } // tag component for now
-						"description" => {
-							let mut new_desc = Description::new();
-							for string in details.iter() {
-								if let Some((key, value)) = string.split_once(':') {
-									match key {
-										"name" => { new_desc.name = value.to_string(); }
-										"desc" => { new_desc.desc = value.to_string(); }
-										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
-									}
-								} else { warn!("* could not split key:value on component {}", part); }
-							}
-							self.desc = Some(new_desc);
-						}
-						"device"      => {
-							let mut new_device = Device::new(0);
-							for string in details.iter() {
-								if let Some((key, value)) = string.split_once(':') {
-									match key {
-										"state" => { new_device.pw_switch = value.parse().expect(&(error_msg.to_owned() + "device:state")); }
-										"voltage" => { new_device.batt_voltage = value.parse().expect(&(error_msg.to_owned() + "device:voltage")); }
-										"rate" => { new_device.batt_discharge = value.parse().expect(&(error_msg.to_owned() + "device:rate")); }
-										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
-									}
-								} else { warn!("* could not split key:value on component {}", part); }
-							}
-							self.device = Some(new_device);
-						}
-						"key"         => {
-							let mut new_key = Key::default();
-							for string in details.iter() {
-								if let Some((key, value)) = string.split_once(':') {
-									if key == "id" { new_key.key_id = value.parse().expect(&(error_msg.to_owned() + "key:id")); }
-									else { warn!("* component key:value {}:{} was not recognized", key, value); }
-								} else { warn!("* could not split key:value on component {}", part); }
-							}
-							self.key = Some(new_key);
-						}
-						"lockable"    => {
-							let mut new_lock = Lockable::default();
-							for string in details.iter() {
-								if let Some((key, value)) = string.split_once(':') {
-									match key {
-										"state" => { new_lock.is_locked = value.parse().expect(&(error_msg.to_owned() + "lockable:state")); }
-										"key_id" => { new_lock.key_id = value.parse().expect(&(error_msg.to_owned() + "lockable:key_id")); }
-										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
-									}
-								} else { warn!("* could not split key:value on component {}", part); }
-							}
-							self.lock = Some(new_lock);
-						}
-						"mobile"      => { self.mobile = Some(Mobile::default()); } // tag component
-						"networkable" => { self.network = Some(Networkable::default()); } // tag component
-						"obstructs"   => { self.obstruct = Some(Obstructive::default()); } // tag component
-						"opaque"      => {
-							let mut new_opaque = Opaque::default();
-							if details.is_empty() {
-								// The default for a boolean in Rust is 'false', which means that the Opaque::default()
-								// is an Opaque component with component.opaque = false, meaning transparent
-								new_opaque.opaque = true;
-							} else {
-								for string in details.iter() {
-									if let Some((key, value)) = string.split_once(':') {
-										if key == "state" { new_opaque.opaque = value.parse().expect(&(error_msg.to_owned() + "opaque:state")); }
-										else { warn!("* component key:value {}:{} was not recognized", key, value); }
-									}
-								}
-							}
-							self.opaque = Some(new_opaque);
-						}
-						"openable"    => {
-							let mut new_open = Openable::default();
-							for string in details.iter() {
-								if let Some((key, value)) = string.split_once(':') {
-									match key {
-										"state" => { new_open.is_open = value.parse().expect(&(error_msg.to_owned() + "openable:state")); }
-										"stuck" => { new_open.is_stuck = value.parse().expect(&(error_msg.to_owned() + "openable:stuck")); }
-										"open" => { new_open.open_glyph = value.to_string(); }
-										"closed" => { new_open.closed_glyph = value.to_string(); }
-										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
-									}
-								} else { warn!("* could not split key:value on component {}", part); }
-							}
-							self.open = Some(new_open);
-						}
-						"portable"    => { self.portable = Some(Portable::empty()); } // the Entity field cannot be specified before runtime
-						_ => { error!("! ERR: requested component {} was not recognized", component); }
-					}
+			// Parse and add any additional components that are in the item's definition
+			let mut errors = Vec::new();
+			for component in item_data.extra.iter() {
+				//debug!("* raw component value: {}", component); // DEBUG: log raw component values
+				// HINT: This will in fact return the entire string if the string consists of only a single word
+				let mut new_cmpnt = component.split(' ');
+				let part = new_cmpnt.next().unwrap_or(""); // This is a closure that returns an empty string
+				let details: Vec<&str> = new_cmpnt.collect();
+				if let Some((_, parser)) = COMPONENT_PARSERS.iter().find(|x| x.0 == part) {
+					errors.append(&mut parser(self, &details));
+				} else {
+					errors.push(format!("requested component {} was not recognized", component));
 				}
 			}
+			for error in errors.iter() {
+				error!("! {}: {}", item_data.name, error);
+			}
 		}
+		self.item_dict = dict;
 		/*
 		 * else if let Some(set_data) = self.item_dict.sets.iter().find(|x| x.name == new_item) {
 		 * 	// There's no way to store the values for multiple items to be generated, so instead we'll make this
@@ -278,43 +245,97 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 		let mut new_item = world.spawn_empty();
 		// Add all of the populated components to the new entity
 		if let Some(desc)     = &self.desc { new_item.insert(desc.clone()); self.desc = None; }
-		if let Some(body)     = &self.body {
+		if let Some(body)     = &mut self.body {
+			// A multilevel item is defined by its footprint on one deck, and only gets copied up once its spot is known
+			if let Some(span) = self.multilevel { body.span_decks(span.decks, self.deck_count); }
 			//debug!("* creating new item with shape {:?}", body.posns());
 			item_shape = body.posns();
 			new_item.insert(body.clone()); self.body = None;
 		}
-		if let Some(actions)  = &self.actions { new_item.insert(actions.clone()); self.actions = None; }
+		// Every item gets an ActionSet whether it asked for one or not, since the menus use it to decide what to offer
+		new_item.insert(self.actions.take().unwrap_or_default());
 		if let Some(contain)  = &self.contain { new_item.insert(*contain); self.contain = None; }
+		if let Some(critical) = self.critical { new_item.insert(critical); self.critical = None; }
+		if let Some(crew)     = self.crew { new_item.insert(crew); self.crew = None; }
 		if let Some(device)   = self.device { new_item.insert(device); self.device = None; }
+		if let Some(friendly) = self.friendly { new_item.insert(friendly); self.friendly = None; }
 		if let Some(is_carried) = self.is_carried { new_item.insert(is_carried); self.is_carried = None; }
 		if let Some(key)      = self.key { new_item.insert(key); self.key = None; }
 		if let Some(lock)     = self.lock { new_item.insert(lock); self.lock = None; }
 		if let Some(mobile)   = self.mobile { new_item.insert(mobile); self.mobile = None; }
+		if let Some(multilevel) = self.multilevel { new_item.insert(multilevel); self.multilevel = None; }
 		if let Some(obstruct) = self.obstruct { new_item.insert(obstruct); self.obstruct = None; }
 		if let Some(opaque)   = self.opaque { new_item.insert(opaque); self.opaque = None; }
 		if let Some(open)     = &self.open { new_item.insert(open.clone()); self.open = None; }
 		if let Some(planq)    = self.planq { new_item.insert(planq); self.planq = None; }
 		if let Some(portable) = self.portable { new_item.insert(portable); self.portable = None; }
+		if let Some(render)   = self.render { new_item.insert(render); self.render = None; }
+		if let Some(speed)    = self.speed { new_item.insert(speed); self.speed = None; }
 		vec![(new_item, item_shape)]
 	}
+	/// Constructs a whole list of (name, position) item requests in one pass, returning each new Entity and its shape
+	/// Each item name is only looked up and parsed once; every later request for that name reuses the parsed components
+	pub fn build_batch(&mut self, requests: &[(String, Position)], world: &mut World) -> Vec<(Entity, Vec<Position>)> {
+		let mut parsed: HashMap<String, ItemBuilder> = HashMap::new();
+		let mut output = Vec::new();
+		for (i_name, i_posn) in requests.iter() {
+			if !parsed.contains_key(i_name) {
+				self.create(i_name);
+				parsed.insert(i_name.clone(), self.take_components());
+			}
+			if let Some(template) = parsed.get(i_name) {
+				self.load_components(template);
+			}
+			for (i_enty, i_shape) in self.at(*i_posn).build(world).iter() {
+				output.push((i_enty.id(), i_shape.clone()));
+			}
+		}
+		output
+	}
+	/// Moves the pending components out of this builder, without making a copy of the item dictionary
+	fn take_components(&mut self) -> ItemBuilder {
+		let dict = std::mem::take(&mut self.item_dict);
+		let (spawn_count, deck_count) = (self.spawn_count, self.deck_count);
+		let parts = std::mem::take(self);
+		self.item_dict = dict;
+		self.spawn_count = spawn_count;
+		self.deck_count = deck_count;
+		parts
+	}
+	/// Copies a set of previously-parsed components into this builder, ready for a call to build()
+	fn load_components(&mut self, parts: &ItemBuilder) {
+		let dict = std::mem::take(&mut self.item_dict);
+		let (spawn_count, deck_count) = (self.spawn_count, self.deck_count);
+		*self = parts.clone();
+		self.item_dict = dict;
+		self.spawn_count = spawn_count;
+		self.deck_count = deck_count;
+	}
 	/// Retrieves a random template from the set defined for a specified item
-	pub fn get_random_shape(&self, item_name: &str, rng: &mut GlobalRng) -> Option<SpawnTemplate> {
+	/// In the Deterministic placement mode, the first template in the set is always used instead
+	pub fn get_random_shape(&self, item_name: &str, rng: &mut GlobalRng, mode: PlacementMode) -> Option<SpawnTemplate> {
+		let mut pick_shape = |shapes: &Vec<Vec<String>>| -> Option<Vec<String>> {
+			match mode {
+				PlacementMode::Random        => { rng.sample(shapes).cloned() }
+				PlacementMode::Deterministic => { shapes.first().cloned() }
+			}
+		};
 		//debug!("* get_random_shape: {}", item_name); // DEBUG: log get_random_shape invocation
 		// If this item name was found in the ItemDict,
 		if let Some(item_data) = self.item_dict.furniture.iter().find(|x| x.name == item_name) {
 			// Return a SpawnTemplate that is made from the 'furniture' list of RawItems in the ItemDict
 			// item_data should be a RawItem object, representing a single item, so it's okay to return wholesale
 			//debug!("* Obtained item_data: {:?}", item_data); // DEBUG: log obtained item_data
-			let mut new_template: SpawnTemplate = (*rng.sample(&item_data.shapes)?).clone().into();
+			let mut new_template: SpawnTemplate = pick_shape(&item_data.shapes)?.into();
 			new_template.assign_name(&item_data.name);
 			return Some(new_template);
 		} else if let Some(set_data) = self.item_dict.sets.iter().find(|x| x.name == item_name) {
 			// As above, but for the 'sets' list of RawItemSets in the ItemDict
 			// Make a base template using the item set defn
-			let mut new_template: SpawnTemplate = (*rng.sample(&set_data.shapes)?).clone().into();
+			let mut new_template: SpawnTemplate = pick_shape(&set_data.shapes)?.into();
 			// Use the room's contents list from the item defn, to populate the names in the spawn template's output
 			//debug!("* RNG: Now calling assign_names with {:?}", set_data.contents); // DEBUG: log obtained item_data
-			new_template.assign_names(set_data.contents.clone());
+			new_template.assign_names(&set_data.name, set_data.contents.clone());
 			return Some(new_template);
 		} else {
 			// Couldn't find the requested item, make sure someone knows
@@ -392,12 +413,32 @@ pub struct ItemDict {
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct RawItem {
 	pub name: String,
+	pub base: Option<String>, // Name of another RawItem to inherit any unspecified fields from
+	#[serde(default)]
 	pub desc: String,
+	#[serde(default)]
 	pub body: Vec<String>,
+	#[serde(default)]
 	pub shapes: Vec<Vec<String>>,
+	#[serde(default)]
 	pub extra: Vec<String>,
 	pub constraints: Option<Vec<(String, String)>>
 }
+impl RawItem {
+	/// Fills in any fields that this item left blank using the parent's values
+	/// The extra components are merged: the child's component overrides any of the parent's with the same keyword
+	fn inherit_from(&mut self, parent: &RawItem) {
+		if self.desc.is_empty() { self.desc = parent.desc.clone(); }
+		if self.body.is_empty() { self.body = parent.body.clone(); }
+		if self.shapes.is_empty() { self.shapes = parent.shapes.clone(); }
+		if self.constraints.is_none() { self.constraints = parent.constraints.clone(); }
+		let keyword = |x: &String| x.split(' ').next().unwrap_or("").to_string();
+		let own_keys: Vec<String> = self.extra.iter().map(keyword).collect();
+		let mut new_extra: Vec<String> = parent.extra.iter().filter(|x| !own_keys.contains(&keyword(x))).cloned().collect();
+		new_extra.append(&mut self.extra);
+		self.extra = new_extra;
+	}
+}
 //    #: RawItemSet
 /// Contains a definition for a set of items, such as a set of lockers, to facilitate spawning
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -407,38 +448,407 @@ pub struct RawItemSet {
 	pub shapes: Vec<Vec<String>>, // Works same as the RawItem.shapes
 }
 
+//   ##: THE COMPONENT PARSERS
+/// Reads the key:value details of one component from an item defn and stores the result on the ItemBuilder
+/// Returns a description of each detail that could not be used; the component is still added with its defaults
+pub type ComponentParser = fn(&mut ItemBuilder, &[&str]) -> Vec<String>;
+/// Maps each component keyword that may appear in an item defn's 'extra' list to its parser
+/// To support a new component: write a parser for it below, then add it to this list
+pub const COMPONENT_PARSERS: &[(&str, ComponentParser)] = &[
+	("accessport",  parse_accessport),
+	("actionset",   parse_actionset),
+	("container",   parse_container),
+	("critical",    parse_critical),
+	("crew",        parse_crew),
+	("description", parse_description),
+	("device",      parse_device),
+	("friendly",    parse_friendly),
+	("key",         parse_key),
+	("lockable",    parse_lockable),
+	("mobile",      parse_mobile),
+	("multilevel",  parse_multilevel),
+	("networkable", parse_networkable),
+	("obstructs",   parse_obstructs),
+	("opaque",      parse_opaque),
+	("openable",    parse_openable),
+	("portable",    parse_portable),
+	("renderlayer", parse_renderlayer),
+	("speed",       parse_speed),
+];
+/// Splits each of the details into its key:value pair and hands them to the handler, which returns Ok(false) if it did
+/// not recognize the key; any detail that is malformed, unrecognized, or unparseable is collected into the result
+fn for_each_detail<F>(cmpnt: &str, details: &[&str], mut handler: F) -> Vec<String>
+where F: FnMut(&str, &str) -> Result<bool, String> {
+	let mut errors = Vec::new();
+	for string in details.iter() {
+		if let Some((key, value)) = string.split_once(':') {
+			match handler(key, value) {
+				Ok(true) => { }
+				Ok(false) => { errors.push(format!("component key:value {}:{} was not recognized", key, value)); }
+				Err(error) => { errors.push(error); }
+			}
+		} else { errors.push(format!("could not split key:value '{}' on component {}", string, cmpnt)); }
+	}
+	errors
+}
+/// Parses a single value from an item defn, describing the problem if the value won't parse
+fn parse_detail<T: std::str::FromStr>(cmpnt: &str, key: &str, value: &str) -> Result<T, String> {
+	value.parse().map_err(|_| format!("could not parse key:value {}:{} for {}", key, value, cmpnt))
+}
+// Tag components take no details
+fn parse_accessport(builder: &mut ItemBuilder, _details: &[&str])  -> Vec<String> { builder.access = Some(AccessPort::default()); Vec::new() }
+fn parse_actionset(builder: &mut ItemBuilder, _details: &[&str])   -> Vec<String> { builder.actions = Some(ActionSet::default()); Vec::new() }
+fn parse_container(builder: &mut ItemBuilder, _details: &[&str])   -> Vec<String> { builder.contain = Some(Container::default()); Vec::new() } // tag component for now
+fn parse_critical(builder: &mut ItemBuilder, _details: &[&str])    -> Vec<String> { builder.critical = Some(Critical::default()); Vec::new() }
+fn parse_friendly(builder: &mut ItemBuilder, _details: &[&str])    -> Vec<String> { builder.friendly = Some(Friendly::default()); Vec::new() }
+fn parse_mobile(builder: &mut ItemBuilder, _details: &[&str])      -> Vec<String> { builder.mobile = Some(Mobile::default()); Vec::new() }
+fn parse_networkable(builder: &mut ItemBuilder, _details: &[&str]) -> Vec<String> { builder.network = Some(Networkable::default()); Vec::new() }
+fn parse_obstructs(builder: &mut ItemBuilder, _details: &[&str])   -> Vec<String> { builder.obstruct = Some(Obstructive::default()); Vec::new() }
+fn parse_portable(builder: &mut ItemBuilder, _details: &[&str])    -> Vec<String> { builder.portable = Some(Portable::empty()); Vec::new() } // the Entity field cannot be specified before runtime
+// Complex components
+fn parse_description(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_desc = Description::new();
+	let errors = for_each_detail("description", details, |key, value| {
+		match key {
+			"name" => { new_desc.name = value.to_string(); }
+			"desc" => { new_desc.desc = value.to_string(); }
+			_ => { return Ok(false); }
+		}
+		Ok(true)
+	});
+	builder.desc = Some(new_desc);
+	errors
+}
+fn parse_device(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_device = Device::new(0);
+	let errors = for_each_detail("device", details, |key, value| {
+		match key {
+			"state" => { new_device.pw_switch = parse_detail("device", key, value)?; }
+			"voltage" => { new_device.batt_voltage = parse_detail("device", key, value)?; }
+			"rate" => { new_device.batt_discharge = parse_detail("device", key, value)?; }
+			_ => { return Ok(false); }
+		}
+		Ok(true)
+	});
+	builder.device = Some(new_device);
+	errors
+}
+fn parse_crew(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_crew = Crew::default();
+	let errors = for_each_detail("crew", details, |key, value| {
+		if key != "role" { return Ok(false); }
+		new_crew.role = parse_detail("crew", key, value)?;
+		Ok(true)
+	});
+	builder.crew = Some(new_crew);
+	errors
+}
+fn parse_key(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_key = Key::default();
+	let errors = for_each_detail("key", details, |key, value| {
+		if key != "id" { return Ok(false); }
+		new_key.key_id = parse_detail("key", key, value)?;
+		Ok(true)
+	});
+	builder.key = Some(new_key);
+	errors
+}
+fn parse_lockable(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_lock = Lockable::default();
+	let errors = for_each_detail("lockable", details, |key, value| {
+		match key {
+			"state" => { new_lock.is_locked = parse_detail("lockable", key, value)?; }
+			"key_id" => { new_lock.key_id = parse_detail("lockable", key, value)?; }
+			_ => { return Ok(false); }
+		}
+		Ok(true)
+	});
+	builder.lock = Some(new_lock);
+	errors
+}
+fn parse_multilevel(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_span = Multilevel::new(1);
+	let errors = for_each_detail("multilevel", details, |key, value| {
+		if key != "decks" { return Ok(false); }
+		new_span.decks = parse_detail("multilevel", key, value)?;
+		Ok(true)
+	});
+	builder.multilevel = Some(new_span);
+	errors
+}
+fn parse_opaque(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	// The default for a boolean in Rust is 'false', which means that the Opaque::default()
+	// is an Opaque component with component.opaque = false, meaning transparent
+	// An opaque tag without any details is taken to mean that it blocks vision
+	let mut new_opaque = Opaque::new(details.is_empty());
+	let errors = for_each_detail("opaque", details, |key, value| {
+		if key != "state" { return Ok(false); }
+		new_opaque.opaque = parse_detail("opaque", key, value)?;
+		Ok(true)
+	});
+	builder.opaque = Some(new_opaque);
+	errors
+}
+fn parse_openable(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_open = Openable::default();
+	let errors = for_each_detail("openable", details, |key, value| {
+		match key {
+			"state" => { new_open.is_open = parse_detail("openable", key, value)?; }
+			"stuck" => { new_open.is_stuck = parse_detail("openable", key, value)?; }
+			"open" => { new_open.open_glyph = value.to_string(); }
+			"closed" => { new_open.closed_glyph = value.to_string(); }
+			_ => { return Ok(false); }
+		}
+		Ok(true)
+	});
+	builder.open = Some(new_open);
+	errors
+}
+fn parse_renderlayer(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_layer = RenderLayer::default();
+	let errors = for_each_detail("renderlayer", details, |key, value| {
+		if key != "priority" { return Ok(false); }
+		new_layer.priority = parse_detail("renderlayer", key, value)?;
+		Ok(true)
+	});
+	builder.render = Some(new_layer);
+	errors
+}
+fn parse_speed(builder: &mut ItemBuilder, details: &[&str]) -> Vec<String> {
+	let mut new_speed = Speed::default();
+	let errors = for_each_detail("speed", details, |key, value| {
+		if key != "percent" { return Ok(false); }
+		new_speed = Speed::new(parse_detail("speed", key, value)?);
+		Ok(true)
+	});
+	builder.speed = Some(new_speed);
+	errors
+}
+
 //  ###: SIMPLE TYPES AND HELPERS
 /// Loads the various furniture generation definitions from the external storage
 pub fn load_furniture_defns(items_filename: &str, sets_filename: &str) -> ItemDict {
-	// Make an empty ItemDict
+	let (new_dict, errors) = read_furniture_defns(items_filename, sets_filename);
+	for error in errors.iter() {
+		error!("{}", error);
+	}
+	new_dict
+}
+/// Reads the furniture definitions, returning whatever could be parsed along with a list of any errors
+/// Each entry is parsed on its own, so that one malformed item only drops that item instead of the whole file
+pub fn read_furniture_defns(items_filename: &str, sets_filename: &str) -> (ItemDict, Vec<String>) {
 	let mut new_dict = ItemDict::default();
-	// Get a handle on the file to be loaded
+	let mut errors = Vec::new();
 	// Construct the furniture item dictionary
-	if let Ok(item_file) = File::open(items_filename) {
-		// Open a reader object for the file handle
-		let item_reader = BufReader::new(item_file);
-		// If reading any of the lines failed, return a default dict
-		new_dict.furniture = match serde_json::from_reader(item_reader) {
-			//Ok(output) => {debug!("* recvd output: {:?}", output); output}, // DEBUG: log the successful output
-			Ok(output) => {output},
-			Err(e) => {error!("! could not create ItemDict.furniture: {}", e); Vec::new()},
-		};
-	} else {
-		error!("! could not access the furniture items file at {}", items_filename);
-	}
+	let mut raw_items: Vec<RawItem> = read_json_entries(items_filename, &mut errors);
+	raw_items.retain(|x| {
+		if x.name.is_empty() { errors.push(format!("! {}: an item was defined without a name", items_filename)); }
+		!x.name.is_empty()
+	});
+	new_dict.furniture = resolve_item_bases(raw_items);
+	// The body is only checked after resolving the bases, since an item may inherit its body from its base
+	new_dict.furniture.retain(|x| {
+		if x.body.is_empty() { errors.push(format!("! {}: item '{}' has no body defined", items_filename, x.name)); }
+		!x.body.is_empty()
+	});
 	// Construct the furniture set dictionary in the same way
-	if let Ok(sets_file) = File::open(sets_filename) {
-		let sets_reader = BufReader::new(sets_file);
-		new_dict.sets = match serde_json::from_reader(sets_reader) {
-			//Ok(output) => {debug!("* new sets: {:?}", output); output}, // DEBUG: log the successful output
-			Ok(output) => {output},
-			Err(e) => {error!("! could not create ItemDict.sets: {}", e); Vec::new()}
-		};
-	} else {
-		error!("! could not access the furniture sets file at {}", sets_filename);
+	new_dict.sets = read_json_entries(sets_filename, &mut errors);
+	errors.append(&mut validate_item_sets(&new_dict));
+	(new_dict, errors)
+}
+/// Checks that every item named in an item set is actually defined, so that typos are caught at startup
+/// instead of turning into missing furniture later on
+pub fn validate_item_sets(dict: &ItemDict) -> Vec<String> {
+	let mut errors = Vec::new();
+	for set in dict.sets.iter() {
+		for (id, item_name) in set.contents.iter() {
+			if !dict.furniture.iter().any(|x| x.name == *item_name) {
+				errors.push(format!("! item set '{}' refers to undefined item '{}' (id '{}')", set.name, item_name, id));
+			}
+		}
+	}
+	errors
+}
+/// Reads a JSON file that holds an array of definitions, and parses each of the entries individually
+/// Any problems are described in the errors list, identified by the file and the entry's index and name
+fn read_json_entries<T: serde::de::DeserializeOwned>(filename: &str, errors: &mut Vec<String>) -> Vec<T> {
+	let file = if let Ok(file) = File::open(filename) { file } else {
+		errors.push(format!("! could not access the definitions file at {}", filename));
+		return Vec::new();
+	};
+	// serde's syntax errors already include the line and column where they occurred
+	let entries: Vec<serde_json::Value> = match serde_json::from_reader(BufReader::new(file)) {
+		Ok(output) => { output }
+		Err(e) => {
+			errors.push(format!("! could not parse {}: {}", filename, e));
+			return Vec::new();
+		}
+	};
+	let mut output = Vec::new();
+	for (index, entry) in entries.into_iter().enumerate() {
+		let name = entry.get("name").and_then(|x| x.as_str()).unwrap_or("(unnamed)").to_string();
+		match serde_json::from_value(entry) {
+			Ok(new_entry) => { output.push(new_entry); }
+			Err(e) => { errors.push(format!("! {}: skipped entry #{} '{}': {}", filename, index, name, e)); }
+		}
+	}
+	output
+}
+/// Resolves the 'base' field on each of the RawItems, so that every item comes out fully-specified
+/// Items that are part of an inheritance cycle, or that name a base that doesn't exist, are reported and dropped
+pub fn resolve_item_bases(raw_items: Vec<RawItem>) -> Vec<RawItem> {
+	let lookup: HashMap<String, RawItem> = raw_items.iter().map(|x| (x.name.clone(), x.clone())).collect();
+	let mut resolved: HashMap<String, RawItem> = HashMap::new();
+	let mut output = Vec::new();
+	for item in raw_items.iter() {
+		let mut chain: Vec<String> = Vec::new();
+		if let Some(new_item) = resolve_item(&item.name, &lookup, &mut resolved, &mut chain) {
+			output.push(new_item);
+		}
+	}
+	output
+}
+/// Recursive helper for resolve_item_bases(); the chain holds the names of the items currently being resolved
+fn resolve_item(name: &str, lookup: &HashMap<String, RawItem>, resolved: &mut HashMap<String, RawItem>, chain: &mut Vec<String>) -> Option<RawItem> {
+	if let Some(item) = resolved.get(name) { return Some(item.clone()); }
+	if chain.iter().any(|x| x == name) {
+		chain.push(name.to_string());
+		error!("! ERR: inheritance cycle in item defns: {}", chain.join(" -> "));
+		return None;
+	}
+	let mut new_item = if let Some(item) = lookup.get(name) { item.clone() } else {
+		error!("! ERR: item '{}' was named as a base but is not defined", name);
+		return None;
+	};
+	if let Some(base_name) = new_item.base.clone() {
+		chain.push(name.to_string());
+		let parent = resolve_item(&base_name, lookup, resolved, chain);
+		chain.pop();
+		if let Some(parent) = parent {
+			new_item.inherit_from(&parent);
+		} else {
+			error!("! ERR: could not resolve base '{}' for item '{}'", base_name, name);
+			return None;
+		}
+	}
+	resolved.insert(name.to_string(), new_item.clone());
+	Some(new_item)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn multilevel_footprint_stops_at_the_top_deck() {
+		let mut artisan = ItemBuilder { deck_count: 2, ..ItemBuilder::default() };
+		artisan.item_dict.furniture.push(RawItem {
+			name: "reactor".to_string(),
+			body: vec!["0,0 O".to_string(), "1,0".to_string()],
+			extra: vec!["multilevel decks:3".to_string()],
+			..RawItem::default()
+		});
+		let lower = artisan.footprint("reactor", Position::new(4, 4, 0));
+		assert_eq!(lower.len(), 4);
+		assert!(lower.contains(&Position::new(5, 4, 1)));
+		let upper = artisan.footprint("reactor", Position::new(4, 4, 1));
+		assert_eq!(upper, vec![Position::new(4, 4, 1), Position::new(5, 4, 1)]);
+	}
+	#[test]
+	fn device_details_are_parsed() {
+		let mut artisan = ItemBuilder::default();
+		let errors = parse_device(&mut artisan, &["state:true", "voltage:50", "rate:2"]);
+		assert!(errors.is_empty());
+		let device = artisan.device.expect("device component was not added");
+		assert!(device.pw_switch);
+		assert_eq!(device.batt_voltage, 50);
+		assert_eq!(device.batt_discharge, 2);
+	}
+	#[test]
+	fn openable_details_are_parsed() {
+		let mut artisan = ItemBuilder::default();
+		let errors = parse_openable(&mut artisan, &["state:true", "stuck:false", "open:'", "closed:+"]);
+		assert!(errors.is_empty());
+		let open = artisan.open.expect("openable component was not added");
+		assert!(open.is_open);
+		assert!(!open.is_stuck);
+		assert_eq!(open.open_glyph, "'");
+		assert_eq!(open.closed_glyph, "+");
+	}
+	#[test]
+	fn malformed_details_are_reported_instead_of_halting() {
+		let mut artisan = ItemBuilder::default();
+		let errors = parse_device(&mut artisan, &["voltage:lots", "state", "colour:red"]);
+		assert_eq!(errors.len(), 3);
+		// The component is still added, using the defaults for the details that could not be used
+		assert_eq!(artisan.device.expect("device component was not added").batt_voltage, 0);
+		// A malformed component in a defn should not stop the rest of the item from being created
+		artisan.item_dict.furniture.push(RawItem {
+			name: "lamp".to_string(),
+			body: vec!["0,0 *".to_string()],
+			extra: vec!["openable state:maybe".to_string(), "portable".to_string()],
+			..RawItem::default()
+		});
+		artisan.create("lamp");
+		assert!(artisan.open.is_some());
+		assert!(artisan.portable.is_some());
+		assert_eq!(artisan.item_dict.furniture.len(), 1);
+	}
+	#[test]
+	fn broken_item_defns_only_drop_the_broken_entries() {
+		let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+		let items_file = format!("{}/broken_items.json", fixtures);
+		let sets_file = format!("{}/no_such_sets.json", fixtures);
+		let (dict, errors) = read_furniture_defns(&items_file, &sets_file);
+		assert_eq!(dict.furniture.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), vec!["crate"]);
+		// Each of the errors names the file, and the entry where it can
+		assert_eq!(errors.len(), 4);
+		assert!(errors.iter().any(|x| x.contains("broken_items.json") && x.contains("#1 'lamp'")));
+		assert!(errors.iter().any(|x| x.contains("without a name")));
+		assert!(errors.iter().any(|x| x.contains("'ghost' has no body")));
+		assert!(errors.iter().any(|x| x.contains("no_such_sets.json")));
+		// A file that isn't valid JSON at all can't be split up, so the error says where it went wrong instead
+		let (dict, errors) = read_furniture_defns(&format!("{}/truncated_items.json", fixtures), &sets_file);
+		assert!(dict.furniture.is_empty());
+		assert!(errors.iter().any(|x| x.contains("truncated_items.json") && x.contains("line")));
+	}
+	#[test]
+	fn batch_spawns_match_the_one_at_a_time_spawns() {
+		let mut artisan = ItemBuilder::default();
+		artisan.item_dict.furniture.push(RawItem {
+			name: "locker".to_string(),
+			body: vec!["0,0 L".to_string(), "0,1".to_string()],
+			extra: vec!["openable state:false".to_string(), "obstructs".to_string()],
+			..RawItem::default()
+		});
+		artisan.item_dict.furniture.push(RawItem {
+			name: "wrench".to_string(),
+			body: vec!["0,0 w".to_string()],
+			extra: vec!["portable".to_string()],
+			..RawItem::default()
+		});
+		let requests: Vec<(String, Position)> = (0..100)
+			.map(|x| (if x % 3 == 0 { "locker" } else { "wrench" }.to_string(), Position::new(x % 10, x / 10, 0)))
+			.collect();
+		let mut batch_world = World::new();
+		let batch = artisan.build_batch(&requests, &mut batch_world);
+		let mut single_world = World::new();
+		let mut single = Vec::new();
+		for (i_name, i_posn) in requests.iter() {
+			for (i_enty, i_shape) in artisan.create(i_name).at(*i_posn).build(&mut single_world).iter() {
+				single.push((i_enty.id(), i_shape.clone()));
+			}
+		}
+		assert_eq!(batch.len(), 100);
+		assert_eq!(batch.len(), single.len());
+		for ((b_enty, b_shape), (s_enty, s_shape)) in batch.iter().zip(single.iter()) {
+			assert_eq!(b_shape, s_shape);
+			assert_eq!(batch_world.get::<Body>(*b_enty), single_world.get::<Body>(*s_enty));
+			assert_eq!(batch_world.get::<Description>(*b_enty), single_world.get::<Description>(*s_enty));
+			assert_eq!(batch_world.get::<Openable>(*b_enty).map(|x| x.is_open), single_world.get::<Openable>(*s_enty).map(|x| x.is_open));
+			assert_eq!(batch_world.get::<Portable>(*b_enty).is_some(), single_world.get::<Portable>(*s_enty).is_some());
+		}
 	}
-	// Now return the dict from this function (or put it where it needs to go)
-	new_dict
 }
 
 // EOF
@@ -21,13 +21,21 @@ use bracket_geometry::prelude::*;
 use ratatui::style::Color as RatatuiColor;
 use ratatui::buffer::Cell;
 use ratatui::style::Modifier;
+use serde::{Deserialize, Serialize};
 use simplelog::*;
 
 // ###: INTERNAL LIBS
 use crate::components::*;
+use crate::engine::config::Config;
 use crate::worldmap::*;
 use crate::components::Color;
 
+//  ###: CONSTANTS
+/// The background tints for the reach overlay, as indices into the 256-color palette; these are a couple shades
+/// off of black so that they don't drown out the glyphs on top of them
+const REACH_TINT: u8 = 237;
+const EXAMINE_TINT: u8 = 235;
+
 //  ###: MAIN CLASSES
 //   ##: CameraView
 /// Represents a 'flattened' view of the Map's layers, with all entities and effects painted in,
@@ -41,6 +49,9 @@ pub struct CameraView {
 	pub height: i32,
 	pub reticle: Position,
 	pub reticle_glyphs: String,
+	pub show_reach: bool, // If true, the tiles within the player's reach get a background tint
+	pub origin: Position, // The map position that's drawn in the camera's upper-left corner
+	pub focus: Position, // If valid, the camera is panned to center on this position instead of the player
 }
 impl CameraView {
 	pub fn new(new_width: i32, new_height: i32) -> Self {
@@ -50,6 +61,9 @@ impl CameraView {
 			height: new_height,
 			reticle: Position::INVALID,
 			reticle_glyphs: "⌟⌞⌝⌜".to_string(), // Corner frame
+			show_reach: false,
+			origin: Position::default(),
+			focus: Position::INVALID,
 		}
 		// Other options for reticles might include: (not all tested)
 		// The reticle glyph order is UL, UR, DL, DR
@@ -74,6 +88,15 @@ impl CameraView {
 			self.output = vec![ScreenCell::default(); new_size];
 		}
 	}
+	/// Returns true if the camera has been detached from the player to look around the deck
+	pub fn is_panning(&self) -> bool {
+		self.focus != Position::INVALID
+	}
+	/// Converts map coordinates to screen coordinates, using the map frame from the camera's last update
+	/// Like Position::to_camera_coords, this does NOT check whether the result actually falls on the screen
+	pub fn to_screen_coords(&self, map_posn: Position) -> Position {
+		Position::new(map_posn.x - self.origin.x, map_posn.y - self.origin.y, 0)
+	}
 }
 //   ##: ScreenCell
 /// Compatibility type for better integration with ratatui; converts directly to a ratatui::Buffer::Cell
@@ -89,25 +112,21 @@ pub struct ScreenCell {
 impl ScreenCell {
 	/// Creates a ScreenCell from an input string, formatted as "G f b m" where G is the display char,
 	/// f and b are the foreground and background colors,
-	/// and m is the set of text modifications to apply
+	/// and m is the set of text modifications to apply; anything left off the end uses the defaults
 	pub fn new_from_str(input: &str) -> ScreenCell {
 		debug!("* new_from_str input: {:?}", input); // DEBUG: log the input
-		let mut new_cell = ScreenCell::new();
-		let str_list: Vec<&str> = input.split(' ').collect();
-		new_cell.glyph = str_list[0].to_string();
-		new_cell.fg = COLOR_DICT[str_list[1]] as u8;
-		new_cell.bg = COLOR_DICT[str_list[2]] as u8;
-		new_cell.modifier = MODS_DICT[str_list[3]];
-		new_cell
+		ScreenCell::new_from_str_vec(input.split(' ').collect())
 	}
 	/// Creates a ScreenCell from an input Vec of strings, such as might be obtained by collect()ing an
 	/// input vector after doing some parsing to it
+	/// The colors may be given either by name, ie "green", or by their ANSI index; missing colors use the defaults
 	pub fn new_from_str_vec(input: Vec<&str>) -> ScreenCell {
 		debug!("* new_from_str_vec input: {:?}", input); // DEBUG: log the input
 		let mut new_cell = ScreenCell::new();
-		new_cell.glyph = input[0].to_string();
-		new_cell.fg = COLOR_DICT[input[1]] as u8;
-		new_cell.bg = COLOR_DICT[input[2]] as u8;
+		new_cell.glyph = input.first().map(|x| x.to_string()).unwrap_or_default();
+		new_cell.fg = input.get(1).map(|x| parse_color(x)).unwrap_or(Color::default() as u8);
+		new_cell.bg = input.get(2).map(|x| parse_color(x)).unwrap_or(Color::Black as u8);
+		if input.len() > 3 { new_cell.modifier = parse_mods(&input[3..].join(" ")); }
 		new_cell
 	}
 	pub fn create(new_glyph: &str, new_fg: Color, new_bg: Color, mods: u16) -> ScreenCell {
@@ -210,65 +229,118 @@ impl From<ScreenCell> for Cell { // Used for converting my custom ScreenCell obj
 }
 impl From<Vec<String>> for ScreenCell { // Input string should be formatted as "G f b m" where G is the display char and f,b,m are integers
 	fn from(input: Vec<String>) -> Self {
-		let fg_color = if let Ok(color) = input[1].parse::<u8>() {
-			color
-		} else { // try the color dict
-			COLOR_DICT[input[1].as_str()] as u8
-		};
-		let bg_color = if let Ok(color) = input[2].parse::<u8>() {
-			color
-		} else {
-			COLOR_DICT[input[2].as_str()] as u8
-		};
-		ScreenCell {
-			glyph: input[0].clone(),
-			fg: fg_color,
-			bg: bg_color,
-			modifier: input[3].parse::<u16>().unwrap_or(0)
-		}
+		ScreenCell::from(input.iter().map(|x| x.as_str()).collect::<Vec<&str>>())
 	}
 }
 impl From<Vec<&str>> for ScreenCell { // Input string should be formatted as "G f b m" where G is the display char and f,b,m are integers
 	fn from(input: Vec<&str>) -> Self {
-		let fg_color = if let Ok(color) = input[1].parse::<u8>() {
-			color
-		} else { // try the color dict
-			COLOR_DICT[input[1]] as u8
-		};
-		let bg_color = if let Ok(color) = input[2].parse::<u8>() {
-			color
-		} else {
-			COLOR_DICT[input[2]] as u8
-		};
+		// Any missing fields fall back to the same defaults as new_from_str_vec
 		ScreenCell {
-			glyph: input[0].to_string(),
-			fg: fg_color,
-			bg: bg_color,
-			modifier: input[3].parse::<u16>().unwrap_or(0)
+			glyph: input.first().map(|x| x.to_string()).unwrap_or_default(),
+			fg: input.get(1).map(|x| parse_color(x)).unwrap_or(Color::default() as u8),
+			bg: input.get(2).map(|x| parse_color(x)).unwrap_or(Color::Black as u8),
+			modifier: input.get(3).map(|x| parse_mod_token(x)).unwrap_or(0),
+		}
+	}
+}
+
+/// Looks up a color by its friendly name, ie "green", and returns it in the form that a ScreenCell uses
+pub fn color_from_name(name: &str) -> Option<u8> {
+	COLOR_DICT.get(name.to_lowercase().as_str()).map(|x| *x as u8)
+}
+/// Converts a color from a content file into a ScreenCell color, accepting either an ANSI index or a color name
+/// Anything unrecognizable is reported and replaced with the default color, rather than crashing the game
+pub fn parse_color(input: &str) -> u8 {
+	if let Ok(index) = input.parse::<u8>() { return index; }
+	if let Some(color) = color_from_name(input) { return color; }
+	warn!("* could not parse color '{}', using the default instead", input); // DEBUG: report a bad color name
+	Color::default() as u8
+}
+
+//   ##: DeviceTheme
+/// Sets the glyph colors that show each DeviceState on the map, so that the player can tell what a device is doing
+/// without examining it; the colors are given by name as in the content files, and a None leaves the glyph as-is
+#[derive(Clone, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceTheme {
+	pub offline: Option<String>,
+	pub idle: Option<String>,
+	pub working: Option<String>,
+	pub error: Option<String>,
+}
+impl DeviceTheme {
+	/// Provides the built-in palettes that the Options menu switches between, by name
+	/// The high contrast palette avoids telling the states apart by red and green alone
+	pub fn presets() -> Vec<(&'static str, DeviceTheme)> {
+		vec![
+			("standard", DeviceTheme::default()),
+			("high contrast", DeviceTheme {
+				offline: Some("gray".to_string()),
+				idle: Some("ltwhite".to_string()),
+				working: Some("ltblue".to_string()),
+				error: Some("yellow".to_string()),
+			}),
+		]
+	}
+	/// Converts the color names into ScreenCell colors, in DeviceState order: offline, idle, working, error
+	pub fn resolve(&self) -> [Option<u8>; 4] {
+		[&self.offline, &self.idle, &self.working, &self.error].map(|x| x.as_deref().map(parse_color))
+	}
+}
+impl Default for DeviceTheme {
+	fn default() -> DeviceTheme {
+		DeviceTheme {
+			offline: Some("gray".to_string()),
+			idle: None,
+			working: Some("ltcyan".to_string()),
+			error: Some("red".to_string()),
 		}
 	}
 }
+/// Picks the color for a Device's glyph out of a resolved DeviceTheme
+fn device_color(colors: &[Option<u8>; 4], state: DeviceState) -> Option<u8> {
+	match state {
+		DeviceState::Offline  => { colors[0] }
+		DeviceState::Idle     => { colors[1] }
+		DeviceState::Working  => { colors[2] }
+		DeviceState::Error(_) => { colors[3] }
+	}
+}
 
 // ###: BEVY SYSTEMS
 /// Populates and updates the CameraView's data structures so that the player can see what's going on
+/// Any Devices in view are tinted according to their state, using the DeviceTheme from the player's Config
 pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 	                              model:       Res<WorldModel>,
 	                              p_posn:      Res<Position>,
+	                              config:      Option<Res<Config>>,
 	                              mut p_query: Query<(Entity, &Body, &Viewshed, &Memory), With<Player>>,
-	                              e_query:     Query<(Entity, &Body), Without<Player>>,
+	                              e_query:     Query<(Entity, &Body, Option<&Device>), Without<Player>>,
 ) {
 	// Bail out of the method if we're missing any of the structure we need
-	if p_query.get_single_mut().is_err() { return; }
-	let (p_enty, p_body, p_viewshed, p_memory) = p_query.get_single_mut().unwrap(); // There's probably a better way to do this but the line above guards this one so it's okay for now b(> u * )
-	let world_map = &model.levels[p_posn.z as usize];
-	assert!(!camera.output.is_empty(), "camera_update_system: camera.output has length 0!");
-	assert!(!world_map.tiles.is_empty(), "camera_update_system: world_map.tiles has length 0!");
+	// This can happen for a few ticks while a new game is still being set up, so it's not an error
+	let (p_enty, p_body, p_viewshed, p_memory) = if let Ok(value) = p_query.get_single_mut() { value } else { return; };
+	let world_map = if let Some(value) = model.levels.get(p_posn.z as usize) { value } else {
+		warn!("* camera_update_system: no level at z-index {}, skipping update", p_posn.z); // DEBUG: report missing map level
+		return;
+	};
+	if camera.output.is_empty() || world_map.tiles.is_empty() {
+		debug!("* camera_update_system: camera or map has no tiles yet, skipping update"); // DEBUG: report missing map data
+		return;
+	}
 	// Proceed with the update
+	let device_colors = config.map(|x| x.device_theme.resolve()).unwrap_or_else(|| DeviceTheme::default().resolve());
 	let camera_width = camera.width as usize;
-	let screen_center = Position::new((camera_width / 2) as i32, camera.height / 2, 0);
 	// These map_frame values together define the area of the map that we'll be polling
-	let map_frame_ul = Position::new(p_posn.x - screen_center.x, p_posn.y - screen_center.y, 0);
-	let map_frame_dr = Position::new(p_posn.x + screen_center.x, p_posn.y + screen_center.y, 0);
+	// The frame is centered on the player (or on the camera's focus, if it's been panned away from them), but stops
+	// scrolling at the edges of the map so that the map always fills the view; near an edge, the player is drawn
+	// off-center instead
+	let center = if camera.is_panning() { camera.focus } else { *p_posn };
+	let map_frame_ul = Position::new(frame_origin(center.x, world_map.width as i32, camera.width),
+	                                 frame_origin(center.y, world_map.height as i32, camera.height),
+	                                 p_posn.z);
+	let map_frame_dr = Position::new(map_frame_ul.x + camera.width, map_frame_ul.y + camera.height, p_posn.z);
+	camera.origin = map_frame_ul;
 	// For every y-position in the map frame and its associated screen position, ...
 	for (scr_y, map_y) in (map_frame_ul.y..map_frame_dr.y).enumerate() {
 		// For every x-position in the map frame and its associated screen position, ...
@@ -276,19 +348,18 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 			trace!("- scr: {}, {}; map: {}, {}", scr_x, scr_y, map_x, map_y); // DEBUG: print the loop iteration values
 			// Get some indices for the various arrays we're going to use
 			let scr_index = xy_to_index(scr_x, scr_y, camera_width); // Indexes into the camera's map of the screen
-			let map_index = world_map.to_index(map_x, map_y); // Indexes into the worldmap's tilemap
 			let map_posn = Position::new(map_x, map_y, p_posn.z); // Shorthand container
+			// The frame can hang off the edges of a map that's smaller than the view, and to_index() would wrap any
+			// such point around onto the next row, so nothing may be looked up by map_index unless it's in bounds
+			let in_bounds = map_x >= 0 && map_x < world_map.width as i32 && map_y >= 0 && map_y < world_map.height as i32;
+			let map_index = if in_bounds { world_map.to_index(map_x, map_y) } else { usize::MAX }; // Indexes into the worldmap's tilemap
 			// Check if the map position is currently visible or at least has been seen before
-			let is_visible = p_viewshed.visible_points.contains(&Point::new(map_x, map_y));
-			let has_seen = if map_index < world_map.revealed_tiles.len() {
-				world_map.revealed_tiles[map_index]
-			} else {
-				false
-			};
+			let is_visible = in_bounds && p_viewshed.visible_points.contains(&Point::new(map_x, map_y));
+			let has_seen = world_map.revealed_tiles.get(map_index).copied().unwrap_or(false);
 			// If the map coordinates are valid, then we can go to the map to get a tile to draw on the screen
-			if map_x >= 0 && map_x < world_map.width as i32
-			&& map_y >= 0 && map_y < world_map.height as i32
-			{
+			// Each screen cell only asks for the part of an entity's Body that's at its own map position, so a
+			// multitile entity that's partly out of view just has its other cells left undrawn
+			if in_bounds {
 				// First, we must figure out what we're supposed to draw at this screen index:
 				camera.output[scr_index] =
 					// If this is the player's position, draw them
@@ -313,9 +384,14 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 									warn!("? Error retrieving player entity {:?} from the p_query during camera_update_system at posn {}", enty, map_posn);
 									ScreenCell::placeholder()
 								}
-							} else if let Ok((_enty, e_body)) = e_query.get(enty) { // It's a non-player entity
+							} else if let Ok((_enty, e_body, e_device)) = e_query.get(enty) { // It's a non-player entity
 								if let Some(e_glyph) = e_body.glyph_at(&map_posn) {
-									e_glyph.into()
+									let mut e_cell: ScreenCell = e_glyph.into();
+									// Show the state of any visible Devices by changing their color
+									if let Some(color) = e_device.and_then(|x| device_color(&device_colors, x.state)) {
+										e_cell.fg = color;
+									}
+									e_cell
 								} else {
 									warn!("? Error retrieving actor entity {:?} from the e_query during camera_update_system at posn {}", enty, map_posn);
 									ScreenCell::placeholder()
@@ -326,14 +402,14 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 								ScreenCell::placeholder()
 							}
 						} else { // There were no visible entities at the specified position, use a map tile instead
-							world_map.get_display_tile(map_posn).cell
+							world_map.get_tinted_cell(map_posn)
 						}
 					// Not the player, not visible, but has been seen by the player in the past: use the Memory component
 					} else if has_seen {
 						let mut new_cell: ScreenCell = {
 							if let Some(enty_list) = p_memory.visual.get(&map_posn) { // Try to get an entity list for that Position
 								if !enty_list.is_empty() {
-									if let Ok((_, remembered_body)) = e_query.get(enty_list[0]) {
+									if let Ok((_, remembered_body, _)) = e_query.get(enty_list[0]) {
 										if let Some(glyph) = remembered_body.glyph_at(&map_posn) {
 											glyph.into()
 										} else {
@@ -363,6 +439,14 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 				} else {
 					camera.output[scr_index] = ScreenCell::out_of_bounds(); // Painting this blank tile helps prevent artifacting
 			}
+			// Tint the tiles that the player can reach, using the same ranges as the action menus
+			if camera.show_reach && is_visible {
+				if p_posn.in_range_of(&map_posn, REACH_RANGE) {
+					camera.output[scr_index].bg = REACH_TINT;
+				} else if p_posn.in_range_of(&map_posn, EXAMINE_RANGE) {
+					camera.output[scr_index].bg = EXAMINE_TINT;
+				}
+			}
 			// Paint the targeting reticle onto the map if needed
 			/*
 			if camera.reticle != Position::INVALID {
@@ -397,6 +481,14 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 }
 
 //  ###: SIMPLE TYPES AND HELPERS
+/// Finds the first map coordinate along one axis of the camera's view, given the coordinate it should be centered on
+/// and the lengths of the map and the view along that axis; a map that's smaller than the view is centered in it
+fn frame_origin(focus: i32, map_len: i32, view_len: i32) -> i32 {
+	if map_len <= view_len {
+		return (map_len - view_len) / 2;
+	}
+	(focus - view_len / 2).clamp(0, map_len - view_len)
+}
 // I refuse to implement this as a match case on a string, don't @ me
 // This should REALLY be a feature in the stdlib bUt It BrEaKs ThE pArAdIgM (whateverrrrrr)
 extern crate lazy_static;
@@ -448,10 +540,24 @@ lazy_static::lazy_static! {
 }
 /// Parses a string of Modifier types into a single Modifier object
 pub fn parse_mods(input: &str) -> u16 {
-	let tokens: Vec<&str> = input.split(' ').collect();
 	let mut modifier: u16 = 0;
-	for string in tokens {
-		modifier |= MODS_DICT[string];
+	for string in input.split(' ').filter(|x| !x.is_empty()) {
+		modifier |= parse_mod_token(string);
+	}
+	modifier
+}
+/// Parses a single modifier token into its Modifier bits; the token may be the raw bits as an integer, a name,
+/// or written the same way as in the message markup, ie "bold", "+bold", "mod:+italic", or "mod:+bold,+reverse"
+pub fn parse_mod_token(input: &str) -> u16 {
+	if let Ok(bits) = input.parse::<u16>() { return bits; }
+	let mut modifier: u16 = 0;
+	for name in input.trim_start_matches("mod:").split(',') {
+		let name = name.trim_start_matches('+').to_lowercase();
+		if let Some(bits) = MODS_DICT.get(name.as_str()) {
+			modifier |= bits;
+		} else {
+			warn!("* could not parse text modifier '{}', ignoring it", name); // DEBUG: report a bad modifier
+		}
 	}
 	modifier
 }
@@ -480,4 +586,68 @@ impl VisualEffect { // TODO: add builders to this instead of lumping it into one
 }
 */
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bevy::app::{App, Update};
+	use bevy::MinimalPlugins;
+
+	/// Sets up a 10x10 camera over the given model, with a player at the given spot who can see all of their deck
+	fn camera_app(model: WorldModel, p_posn: Position) -> App {
+		let level = &model.levels[p_posn.z as usize];
+		let mut viewshed = Viewshed::new(99);
+		viewshed.visible_points = (0..level.height as i32).flat_map(|y| (0..level.width as i32).map(move |x| Point::new(x, y))).collect();
+		let mut app = App::new();
+		app.add_plugins(MinimalPlugins)
+			.insert_resource(CameraView::new(10, 10))
+			.insert_resource(model)
+			.insert_resource(p_posn)
+			.add_systems(Update, camera_update_system);
+		app.world.spawn((Player::default(), Body::small(p_posn, ScreenCell::new().glyph("@")), viewshed, Memory::default()));
+		app
+	}
+
+	#[test]
+	fn a_body_partly_off_camera_only_draws_the_cells_in_view() {
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(20, 20));
+		// The view stops at x = 9, so only the front of the shuttle is on screen
+		let posns = vec![Position::new(9, 5, 0), Position::new(10, 5, 0), Position::new(11, 5, 0)];
+		let cells = vec![ScreenCell::new().glyph("<"), ScreenCell::new().glyph("="), ScreenCell::new().glyph(">")];
+		let mut app = camera_app(model, Position::new(2, 2, 0));
+		let shuttle = app.world.spawn(Body::large(posns.clone(), cells)).id();
+		app.world.resource_mut::<WorldModel>().add_contents(&posns, 0, shuttle);
+		app.update();
+		let camera = app.world.resource::<CameraView>();
+		assert_eq!(camera.origin, Position::new(0, 0, 0));
+		assert_eq!(camera.output[xy_to_index(9, 5, 10)].glyph, "<");
+		// Nothing from the rest of the shuttle wraps around onto the next row, or anywhere else
+		assert_eq!(camera.output.iter().filter(|x| x.glyph == "=" || x.glyph == ">").count(), 0);
+		assert_eq!(camera.output[xy_to_index(0, 6, 10)], app.world.resource::<WorldModel>().levels[0].get_tinted_cell(Position::new(0, 6, 0)));
+	}
+	#[test]
+	fn a_player_in_the_corner_sees_no_blanks_past_the_map_edge() {
+		for (p_posn, origin, scr_posn) in [(Position::new(0, 0, 0), Position::new(0, 0, 0), (0, 0)),
+		                                   (Position::new(19, 19, 0), Position::new(10, 10, 0), (9, 9))] {
+			let mut model = WorldModel::default();
+			model.add_level(WorldMap::new(20, 20));
+			let mut app = camera_app(model, p_posn);
+			app.update();
+			let camera = app.world.resource::<CameraView>();
+			// The view stops scrolling at the edges, so the player is drawn in the corner instead of the middle
+			assert_eq!(camera.origin, origin);
+			assert_eq!(camera.output[xy_to_index(scr_posn.0, scr_posn.1, 10)].glyph, "@");
+			assert!(!camera.output.contains(&ScreenCell::out_of_bounds()));
+		}
+	}
+	#[test]
+	fn short_cell_strings_fall_back_to_the_defaults() {
+		let expected = ScreenCell::create("@", Color::White, Color::Black, 0);
+		assert_eq!(ScreenCell::new_from_str("@ white"), expected);
+		assert_eq!(ScreenCell::from(vec!["@", "white"]), expected);
+		assert_eq!(ScreenCell::from(vec!["@".to_string(), "white".to_string()]), expected);
+		assert_eq!(ScreenCell::from(Vec::<&str>::new()).glyph, "");
+	}
+}
+
 // EOF
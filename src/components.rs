@@ -10,6 +10,9 @@
  *     ref_posn: Position
  *     extent: Vec<Glyph>
  *   Container - "container"
+ *   Critical - "critical"
+ *   Crew - "crew role"
+ *     role: CrewRole
  *   Description - "description name desc"
  *     name: String
  *     desc: String
@@ -19,6 +22,9 @@
  *     batt_voltage: i32
  *     batt_discharge: i32
  *     state: DeviceState (gameplay property)
+ *   Facing - "facing"
+ *     dir: Direction
+ *   Friendly - "friendly"
  *   Glyph - use a Body component for this instead
  *     posn: Position
  *     cell: ScreenCell
@@ -31,7 +37,10 @@
  *     key_id: i32
  *   Memory - "memory"
  *     visual: HashMap<Position, Vec<Entity>>
+ *     seen_at: HashMap<Position, u64>
  *   Mobile - "mobile"
+ *   Multilevel - "multilevel decks"
+ *     decks: i32
  *   Networkable - "networkable"
  *   Obstructive - "obstructive"
  *   Opaque - "opaque state"
@@ -44,6 +53,13 @@
  *   Player - "player"
  *   Portable - "portable"
  *     carrier: Entity
+ *   RenderLayer - "renderlayer priority"
+ *     priority: i32
+ *   Speed - "speed percent"
+ *     percent: u32
+ *   Stamina - "stamina max"
+ *     current: u32
+ *     max: u32
  *   Viewshed - "viewshed range"
  *     visible_tiles: Vec<Point>
  *     range: i32
@@ -56,6 +72,9 @@
  *     height: i32
  *     reticle: Position
  *     reticle_glyphs: String
+ *     show_reach: bool
+ *     origin: Position
+ *     focus: Position
  *   ScreenCell
  *     glyph: String
  *     fg: u8
@@ -87,17 +106,24 @@ use bevy::prelude::{
 	World,
 };
 use bevy::ecs::entity::*;
+use bevy::ecs::reflect::ReflectMapEntities;
 use bevy::utils::hashbrown::{HashMap, HashSet};
 use bracket_pathfinding::prelude::*;
 use ratatui::layout::Rect;
 use serde::{Deserialize, Serialize};
-use strum_macros::AsRefStr;
+use strum_macros::{AsRefStr, EnumString};
 //use simplelog::*;
 
 // ###: INTERNAL LIBS
 use crate::engine::event::ActionType;
 use crate::camera::ScreenCell;
 
+// ###: CONSTANTS
+/// How far away something can be for the player to handle it, ie to open, lock, or use it
+pub const REACH_RANGE: i32 = 1;
+/// How far away something can be for the player to get a good look at it
+pub const EXAMINE_RANGE: i32 = 2;
+
 // Full-length derive macro examples
 //#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 //#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -119,6 +145,10 @@ impl ActionSet {
 	pub fn new() -> Self {
 		ActionSet::default()
 	}
+	/// Provides the set of actions that are supported by both ActionSets, ie what this entity may do to the other
+	pub fn intersect(&self, other: &ActionSet) -> HashSet<ActionType> {
+		self.actions.intersection(&other.actions).copied().collect()
+	}
 }
 impl Default for ActionSet {
 	fn default() -> ActionSet {
@@ -200,18 +230,29 @@ impl Body {
 	/// Creates a new Body component from a set of input strings, formatted as "x,y G F B M" where 'x,y' or 'x,y,z'
 	/// is the spawnpoint coordinates; 'G' is the display glyph, 'F' is the foreground color, 'B' is the background
 	/// color, and 'M' is the set of text modifications to apply to the display glyph
+	/// A line may give only the coordinates, in which case that part will reuse the glyph of the line before it
 	pub fn new_from_str(input: Vec<String>) -> Body {
 		//debug!("* recvd input: {:?}", input);
 		if input.is_empty() { return Body::default(); };
 		let mut posns = Vec::new();
-		let mut cells = Vec::new();
+		let mut cells: Vec<ScreenCell> = Vec::new();
 		for line in input.iter() {
-			let mut body_parts = line.split(' ');
+			let mut body_parts = line.split(' ').filter(|x| !x.is_empty());
 			if let Some(posn) = body_parts.next() {
 				posns.push(posn.into());
+			} else {
+				continue;
+			}
+			let cell_parts: Vec<&str> = body_parts.collect();
+			if !cell_parts.is_empty() {
+				//cells.push(ScreenCell::new_from_str(&body_parts.collect::<Vec<&str>>().join(" "))); // HINT: rejoins back into string
+				cells.push(ScreenCell::new_from_str_vec(cell_parts));
+			} else if let Some(last_cell) = cells.last() {
+				cells.push(last_cell.clone());
+			} else {
+				// The very first line has no glyph to reuse, but it still needs a cell to keep the rest in line
+				cells.push(ScreenCell::placeholder());
 			}
-			//cells.push(ScreenCell::new_from_str(&body_parts.collect::<Vec<&str>>().join(" "))); // HINT: rejoins back into string
-			cells.push(ScreenCell::new_from_str_vec(body_parts.collect()));
 		}
 		Body::large(posns, cells)
 	}
@@ -226,14 +267,15 @@ impl Body {
 	/// then the remaining Positions will be filled with copies of the last ScreenCell in the list;
 	/// If there are more ScreenCells than Positions, the remainder will be silently dropped
 	pub fn large(posns: Vec<Position>, mut glyphs: Vec<ScreenCell>) -> Body {
+		if posns.is_empty() { return Body::default(); }
+		// Without any glyphs at all there's nothing to repeat, so make the mistake obvious on the map instead
+		if glyphs.is_empty() {
+			glyphs.push(ScreenCell::placeholder());
+		}
 		// Pad out the list of glyphs if it's not long enough
-		loop {
-			if posns.len() <= glyphs.len() {
-				break;
-			}
-			if let Some(last_glyph) = glyphs.last() {
-				glyphs.push(last_glyph.clone());
-			}
+		while glyphs.len() < posns.len() {
+			let last_glyph = glyphs[glyphs.len() - 1].clone();
+			glyphs.push(last_glyph);
 		}
 		// Assign the first Position in the list as the reference position, and then make the full extent of the new Body
 		Body {
@@ -288,6 +330,27 @@ impl Body {
 	pub fn posns(&self) -> Vec<Position> {
 		self.extent.iter().map(|x| x.posn).collect()
 	}
+	/// Returns the list of z-levels that this Body has any parts on, lowest first
+	pub fn levels(&self) -> Vec<i32> {
+		let mut levels: Vec<i32> = self.extent.iter().map(|x| x.posn.z).collect();
+		levels.sort();
+		levels.dedup();
+		levels
+	}
+	/// Copies this Body's parts on its own deck onto each of the decks above it, so that it occupies the same
+	/// footprint on every deck in the span; any part that is already present on those decks is left alone
+	/// The span stops at the top of the ship, which has deck_count decks in it
+	pub fn span_decks(&mut self, decks: i32, deck_count: usize) {
+		let base: Vec<Glyph> = self.extent.iter().filter(|x| x.posn.z == self.ref_posn.z).cloned().collect();
+		let top_offset = decks.min(deck_count as i32 - self.ref_posn.z);
+		for offset in 1..top_offset {
+			for glyph in base.iter() {
+				let new_posn = Position::new(glyph.posn.x, glyph.posn.y, glyph.posn.z + offset);
+				if self.contains(&new_posn) { continue; }
+				self.extent.push((new_posn, glyph.cell.clone()).into());
+			}
+		}
+	}
 	/// Retrieves a particular glyph at a particular position; returns None if nothing found
 	pub fn glyph_at(&self, target: &Position) -> Option<Glyph> {
 		self.extent.iter().find(|x| x.posn == *target).cloned()
@@ -352,6 +415,21 @@ impl From<Glyph> for ScreenCell {
 		value.cell
 	}
 }
+//    #: Multilevel
+/// Marks an entity whose Body reaches through more than one deck, such as a reactor core or an open shaft
+/// The Body holds a copy of its footprint for each deck that it spans, so it shows up and blocks on every one of them
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Multilevel {
+	pub decks: i32, // How many decks the Body spans, counting upward from its ref_posn; cut short at the top deck
+}
+impl Multilevel {
+	pub fn new(new_decks: i32) -> Self {
+		Multilevel {
+			decks: new_decks,
+		}
+	}
+}
 //   ##: Viewshed
 /// Provides an object abstraction for the sensory range of a given entity
 //  INFO: This Viewshed type is NOT eligible for bevy_save because bracket_lib::Point doesn't impl Reflect/FromReflect
@@ -374,10 +452,12 @@ impl Viewshed {
 }
 //    ##: Memory
 /// Provides a memory of seen entities and other things to an entity with sentience
+/// The remembered Entity refs are remapped on load along with everything else, so the memory stays valid across saves
 #[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
-#[reflect(Component)]
+#[reflect(Component, MapEntities)]
 pub struct Memory {
 	pub visual: HashMap<Position, Vec<Entity>>,
+	pub seen_at: HashMap<Position, u64>, // The TurnCount at which each remembered Position was last seen
 }
 impl Memory {
 	pub fn new() -> Self {
@@ -385,12 +465,27 @@ impl Memory {
 	}
 	/// Updates the memorized positions for the specified entity; adds to memory if not already present; clears the memory
 	/// if there's nothing there any more
-	pub fn update(&mut self, targets: Vec<(Position, Option<Vec<Entity>>)>) {
+	pub fn update(&mut self, targets: Vec<(Position, Option<Vec<Entity>>)>, turn: u64) {
 		for (posn, entys) in targets.iter() {
 			if let Some(guys) = entys {
 				self.visual.insert(*posn, guys.clone());
+				self.seen_at.insert(*posn, turn);
 			} else {
 				self.visual.remove(posn);
+				self.seen_at.remove(posn);
+			}
+		}
+	}
+	/// Finds where the given entity was last seen, and on which turn, if it's remembered at all
+	pub fn recall(&self, target: Entity) -> Option<(Position, Option<u64>)> {
+		self.visual.iter().find(|x| x.1.contains(&target)).map(|x| (*x.0, self.seen_at.get(x.0).copied()))
+	}
+}
+impl MapEntities for Memory {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		for entys in self.visual.values_mut() {
+			for enty in entys.iter_mut() {
+				*enty = entity_mapper.get_or_reserve(*enty);
 			}
 		}
 	}
@@ -543,6 +638,203 @@ impl Device {
 		self.pw_switch
 	}
 }
+//   ##: RenderLayer
+/// Decides which Entity gets drawn when several of them share a tile: the highest priority is drawn on top
+/// Entities without this component are layered according to what kind of thing they are; see priority_of()
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct RenderLayer {
+	pub priority: i32,
+}
+impl RenderLayer {
+	pub const FIXTURE: i32 = 0;
+	pub const ITEM: i32 = 10;
+	pub const ACTOR: i32 = 20;
+	pub const PLAYER: i32 = 30;
+	pub fn new(new_priority: i32) -> RenderLayer {
+		RenderLayer { priority: new_priority }
+	}
+	/// Works out the draw priority of the given Entity, preferring an explicit RenderLayer if it has one
+	pub fn priority_of(world: &World, enty: Entity) -> i32 {
+		if let Some(layer) = world.get::<RenderLayer>(enty) { return layer.priority; }
+		if world.get::<Player>(enty).is_some() { return RenderLayer::PLAYER; }
+		if world.get::<Mobile>(enty).is_some() { return RenderLayer::ACTOR; }
+		if world.get::<Portable>(enty).is_some() { return RenderLayer::ITEM; }
+		RenderLayer::FIXTURE
+	}
+}
+//   ##: Speed
+/// Describes how quickly an entity performs its actions, as a percentage of normal speed
+/// Entities without this component are assumed to act at normal speed
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Speed {
+	pub percent: u32,
+}
+impl Speed {
+	pub fn new(new_percent: u32) -> Speed {
+		Speed { percent: new_percent.max(1) }
+	}
+	/// Scales an action's base cost by this entity's speed; any action that costs time will cost at least one tick
+	pub fn adjust(&self, cost: u32) -> u32 {
+		if cost == 0 { return 0; }
+		(cost * 100 / self.percent.max(1)).max(1)
+	}
+}
+impl Default for Speed {
+	fn default() -> Speed {
+		Speed { percent: 100 }
+	}
+}
+//   ##: Stamina
+/// Describes an entity's reserve of energy for strenuous actions, which can be recovered by resting
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Stamina {
+	pub current: u32,
+	pub max: u32,
+}
+impl Stamina {
+	/// Creates a new Stamina pool that starts out full
+	pub fn new(new_max: u32) -> Stamina {
+		Stamina { current: new_max, max: new_max }
+	}
+	/// Uses up the given amount of stamina; returns false and does nothing if there isn't enough left
+	pub fn spend(&mut self, amount: u32) -> bool {
+		if self.current < amount { return false; }
+		self.current -= amount;
+		true
+	}
+	/// Restores the given amount of stamina, up to the maximum
+	pub fn recover(&mut self, amount: u32) {
+		self.current = (self.current + amount).min(self.max);
+	}
+	/// Returns the remaining stamina as a percentage of the maximum
+	pub fn percent(&self) -> u32 {
+		if self.max == 0 { return 0; }
+		self.current * 100 / self.max
+	}
+}
+//   ##: GameClock
+/// Tracks the passage of game time, measured in ticks; every action advances the clock by its cost
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct GameClock {
+	pub ticks: u64,
+}
+impl GameClock {
+	/// Moves the clock forward by the given number of ticks
+	pub fn advance(&mut self, amount: u32) {
+		self.ticks += amount as u64;
+	}
+}
+//   ##: TurnCount
+/// Counts how many turns the player has taken, ie how many actions they've performed that took any time at all
+/// Each action's system counts the turn once it has carried the action out, so a failed attempt isn't counted
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct TurnCount {
+	pub turns: u64,
+}
+impl TurnCount {
+	pub fn increment(&mut self) {
+		self.turns += 1;
+	}
+}
+//   ##: NpcTurn
+/// Gates the NPC systems so that they take one turn for each turn that the player takes, instead of one per frame
+/// The turn_scheduler_system opens the gate once the player's action has been paid for, and the end_npc_turn_system
+/// closes it again after the NPCs have responded; see npc_turn_ready() for how an NPC system should be registered
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct NpcTurn {
+	pub pending: bool, // True if the player has acted and the NPCs have yet to take their turn
+	pub elapsed: u32, // How many ticks the player's action took, so that faster NPCs can do more in response
+}
+impl NpcTurn {
+	/// Lets the NPCs take a turn covering the given amount of game time
+	pub fn begin(&mut self, elapsed: u32) {
+		self.pending = true;
+		self.elapsed += elapsed;
+	}
+	/// Closes the gate until the player acts again
+	pub fn finish(&mut self) {
+		self.pending = false;
+		self.elapsed = 0;
+	}
+}
+//   ##: GameOutcome
+/// Records whether the game has been decided yet; the engine switches to the end screen once it has
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub enum GameOutcome {
+	#[default]
+	Ongoing,
+	Won,  // The player is carrying every one of the Critical items
+	Lost, // The player walked out into vacuum
+}
+//   ##: RunStats
+/// Accumulates the numbers that get reported in the summary at the end of a game
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct RunStats {
+	pub rooms_visited: Vec<String>,
+	pub items_collected: u32,
+}
+impl RunStats {
+	/// Records that the player has been in the named room; returns true if it's their first time there
+	pub fn visit(&mut self, room: &str) -> bool {
+		if self.rooms_visited.iter().any(|x| x == room) { return false; }
+		self.rooms_visited.push(room.to_string());
+		true
+	}
+}
+//    #: CrewRole
+/// Describes the jobs that a member of the crew might have; the names are also used in the item defns
+#[derive(AsRefStr, EnumString, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[strum(ascii_case_insensitive)]
+pub enum CrewRole {
+	Captain,
+	Engineer,
+	Medic,
+	Pilot,
+	Scientist,
+	Security,
+	#[default]
+	Technician,
+}
+impl CrewRole {
+	/// Names the kind of room where this crewmember does their job, for NPC systems to steer them towards
+	/// This is matched against the room names in the ship layout, so it should be a word that appears in them
+	pub fn duty_station(&self) -> &'static str {
+		match self {
+			CrewRole::Captain    => { "Bridge" }
+			CrewRole::Engineer   => { "Engineering" }
+			CrewRole::Medic      => { "Medbay" }
+			CrewRole::Pilot      => { "Bridge" }
+			CrewRole::Scientist  => { "Lab" }
+			CrewRole::Security   => { "Security" }
+			CrewRole::Technician => { "Maintenance" }
+		}
+	}
+	/// Provides something for this crewmember to say when spoken to, until there's a proper dialogue system
+	pub fn greeting(&self) -> &'static str {
+		match self {
+			CrewRole::Captain    => { "\"Report, crewman.\"" }
+			CrewRole::Engineer   => { "\"Don't touch anything that's humming.\"" }
+			CrewRole::Medic      => { "\"Are you hurt? No? Then keep it that way.\"" }
+			CrewRole::Pilot      => { "\"Steady as she goes.\"" }
+			CrewRole::Scientist  => { "\"Fascinating. Please don't interrupt.\"" }
+			CrewRole::Security   => { "\"Move along.\"" }
+			CrewRole::Technician => { "\"If it's broken, file a ticket.\"" }
+		}
+	}
+}
+impl std::fmt::Display for CrewRole {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.as_ref().to_lowercase())
+	}
+}
 //    #: DeviceState
 #[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
@@ -575,6 +867,11 @@ pub struct IsCarried { }
 #[derive(Component, Clone, Copy, Debug, Default, Reflect)]
 #[reflect(Component)]
 pub struct Container { } // TODO: this almost definitely needs a capacity field attached to it
+//   ##: Critical
+/// Marks an item that the game cannot be completed without: it may not be destroyed, and it must spawn within reach
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Critical { }
 //   ##: AccessPort
 /// Describes an entity with a PLANQ-compatible maintenance system
 #[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
@@ -585,6 +882,36 @@ pub struct AccessPort { }
 #[derive(Component, Copy, Clone, Debug, Default, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 pub struct Networkable { }
+//   ##: Crew
+/// Marks an NPC as a member of the ship's crew, which gives them a job to do and something to talk about
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Crew {
+	pub role: CrewRole,
+}
+impl Crew {
+	pub fn new(new_role: CrewRole) -> Crew {
+		Crew { role: new_role }
+	}
+}
+//   ##: Facing
+/// Describes which way an entity is pointed, ie the direction of the last step that it took
+/// Entities without this component simply don't have a front or back
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Facing {
+	pub dir: Direction,
+}
+impl Facing {
+	pub fn new(new_dir: Direction) -> Facing {
+		Facing { dir: new_dir }
+	}
+}
+//   ##: Friendly
+/// Describes an entity that is on the player's side, ie it will step aside instead of blocking the player's way
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Friendly { }
 //   ##: Mobile
 /// Describes an Entity that can move around under its own power
 #[derive(Component, Clone, Copy, Debug, Default, Reflect)]
@@ -641,6 +968,27 @@ pub enum Direction {
 	UP,
 	DOWN
 }
+impl Direction {
+	/// The eight compass directions that lead to a neighboring tile on the same level
+	pub const COMPASS: [Direction; 8] = [Direction::N, Direction::NE, Direction::E, Direction::SE,
+	                                     Direction::S, Direction::SW, Direction::W, Direction::NW];
+	/// Provides the (x, y, z) offset that moving one step in this direction would produce
+	pub fn offset(&self) -> (i32, i32, i32) {
+		match self {
+			Direction::X    => { ( 0,  0,  0) }
+			Direction::N    => { ( 0, -1,  0) }
+			Direction::NW   => { (-1, -1,  0) }
+			Direction::W    => { (-1,  0,  0) }
+			Direction::SW   => { (-1,  1,  0) }
+			Direction::S    => { ( 0,  1,  0) }
+			Direction::SE   => { ( 1,  1,  0) }
+			Direction::E    => { ( 1,  0,  0) }
+			Direction::NE   => { ( 1, -1,  0) }
+			Direction::UP   => { ( 0,  0,  1) }
+			Direction::DOWN => { ( 0,  0, -1) }
+		}
+	}
+}
 impl fmt::Display for Direction {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let text: String = match self {
@@ -698,6 +1046,25 @@ impl Position {
 		}
 		false
 	}
+	/// Gives the compass direction from this Position to the target, to the nearest eighth of a turn
+	/// A target on another deck is simply Up or Down from here, and a target at the same spot gives Direction::X
+	pub fn bearing_to(&self, target: &Position) -> Direction {
+		if target.z > self.z { return Direction::UP; }
+		if target.z < self.z { return Direction::DOWN; }
+		if target == self { return Direction::X; }
+		// The y-axis is flipped since the map's y-coordinates increase going south
+		let angle = ((self.y - target.y) as f32).atan2((target.x - self.x) as f32).to_degrees();
+		match ((angle + 360.0 + 22.5) / 45.0) as i32 % 8 {
+			0 => { Direction::E }
+			1 => { Direction::NE }
+			2 => { Direction::N }
+			3 => { Direction::NW }
+			4 => { Direction::W }
+			5 => { Direction::SW }
+			6 => { Direction::S }
+			_ => { Direction::SE }
+		}
+	}
 	/// Checks if two Positions are next to each other; shorthand for calling `self.in_range_of(target, 1)`
 	pub fn is_adjacent_to(&self, target: &Position) -> bool {
 		self.in_range_of(target, 1)
@@ -706,6 +1073,7 @@ impl Position {
 	/// WARN: this method does NOT guarantee or validate the coordinates it generates; if a given Position
 	/// would fall offscreen, then that is what will be returned!
 	/// The player's position is required as the second parameter in order to provide a reference point between the two maps
+	/// This assumes that the camera is centered on the player; CameraView::to_screen_coords accounts for the edges of the map
 	pub fn to_camera_coords(&self, screen: Rect, p_map: Position) -> Position {
 		// We can discard the z coordinate, since we can only see one level at a time anyway
 		// We can also assume the following relation/analogy: centerpoint : screen :: p_map : worldmap
@@ -867,4 +1235,43 @@ impl std::ops::Sub<Position> for Position {
  * but that isn't useful right now since I have no physics to worry about
 */
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn span_decks_stops_at_the_top_deck() {
+		let mut body = Body::small(Position::new(1, 1, 0), ScreenCell::placeholder());
+		body.span_decks(3, 2);
+		assert_eq!(body.levels(), vec![0, 1]);
+		let mut body = Body::small(Position::new(1, 1, 1), ScreenCell::placeholder());
+		body.span_decks(3, 2);
+		assert_eq!(body.levels(), vec![1]);
+	}
+	#[test]
+	fn remembered_entities_are_remapped_on_load() {
+		let mut world = World::new();
+		let (old_item, new_item) = (Entity::from_raw(40), world.spawn_empty().id());
+		let posn = Position::new(1, 1, 0);
+		let mut memory = Memory::default();
+		memory.visual.insert(posn, vec![old_item]);
+		// This is the same mapping from saved to loaded Entities that the snapshot applier uses
+		let mut entity_map = bevy::utils::HashMap::default();
+		entity_map.insert(old_item, new_item);
+		EntityMapper::world_scope(&mut entity_map, &mut world, |_, mapper| memory.map_entities(mapper));
+		assert_eq!(memory.visual[&posn], vec![new_item]);
+	}
+	#[test]
+	fn body_cells_stay_aligned_when_the_first_glyph_is_missing() {
+		let body = Body::new_from_str(vec!["0,0".to_string(), "1,0 X".to_string(), "2,0".to_string()]);
+		assert_eq!(body.extent.len(), 3);
+		assert_eq!(body.extent[0].posn, Position::new(0, 0, 0));
+		assert_eq!(body.extent[0].cell.glyph, ScreenCell::placeholder().glyph);
+		assert_eq!(body.extent[1].posn, Position::new(1, 0, 0));
+		assert_eq!(body.extent[1].cell.glyph, "X");
+		assert_eq!(body.extent[2].posn, Position::new(2, 0, 0));
+		assert_eq!(body.extent[2].cell.glyph, "X");
+	}
+}
+
 // EOF
@@ -0,0 +1,290 @@
+// engine/config.rs
+// Holds the player's settings: the options that apply to every game, and the choices for starting a new one
+
+//  ###: EXTERNAL LIBRARIES
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use simplelog::warn; // Shadows the bevy::prelude version, which has no LogPlugin to print it
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+//  ###: INTERNAL LIBRARIES
+use crate::camera::DeviceTheme;
+use crate::engine::keybinds::KeyBindings;
+use crate::engine::menu::MenuItem;
+use crate::engine::tui::DEFAULT_TICK_RATE;
+
+//  ###: CONSTANTS
+/// The set of ship layouts that a new game can be generated from
+pub const SHIP_LAYOUTS: &[&str] = &["resources/test_ship_v3.json", "procgen"];
+/// The item density settings to choose from, as a percentage of the ship layout's usual item count
+pub const ITEM_DENSITIES: &[u32] = &[50, 100, 150, 200];
+/// The tick intervals to choose from in the options menu, in milliseconds
+pub const TICK_RATES: &[u64] = &[100, 250, 500];
+/// The message priority filters to choose from in the options menu
+pub const MSG_PRIORITIES: &[i32] = &[0, 1, 2];
+
+//  ###: COMPLEX TYPES
+//   ##: Config
+/// Describes the player's preferences, which apply to every game and are changed from the Options menu
+/// The engine loads these at startup and writes them back out whenever they change; any setting that is missing
+/// from the file gets its default value, so the file can be trimmed down to just the settings that matter
+#[derive(Resource, Clone, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Config {
+	// Gameplay
+	pub tick_rate: u64, // The engine's tick interval in milliseconds; only takes effect at startup
+	// Display
+	pub min_msg_priority: i32, // Messages below this priority are left out of the message log pane
+	pub msg_log_height: u16, // The height of the message log pane, including its borders
+	pub sidebar_width: u16, // The width of the PLANQ sidebar
+	pub device_theme: DeviceTheme, // The colors that show the state of each Device on the map
+	// Controls
+	pub keys: Vec<(String, Vec<String>)>, // The names of each KeyAction and the keys bound to it
+}
+impl Default for Config {
+	fn default() -> Config {
+		Config {
+			tick_rate: DEFAULT_TICK_RATE,
+			min_msg_priority: 0,
+			msg_log_height: 12,
+			sidebar_width: 32,
+			device_theme: DeviceTheme::default(),
+			keys: KeyBindings::default().to_names(),
+		}
+	}
+}
+impl Config {
+	/// Reads the player's options from disk, falling back to the defaults if they're missing or unreadable
+	/// If there's no file yet then the defaults are written out, so that the player has something to edit
+	pub fn load() -> Config {
+		let filepath = config_filepath("options");
+		let config: Config = read_json_file(&filepath);
+		if !filepath.exists() { config.save(); }
+		config
+	}
+	/// Writes the player's options to disk
+	pub fn save(&self) {
+		write_json_file(&config_filepath("options"), self);
+	}
+	/// Switches to the next tick rate in the list
+	pub fn next_tick_rate(&mut self) {
+		let index = TICK_RATES.iter().position(|x| *x == self.tick_rate).map(|x| x + 1).unwrap_or(0);
+		self.tick_rate = TICK_RATES[index % TICK_RATES.len()];
+	}
+	/// Switches to the next message priority filter in the list
+	pub fn next_msg_priority(&mut self) {
+		let index = MSG_PRIORITIES.iter().position(|x| *x == self.min_msg_priority).map(|x| x + 1).unwrap_or(0);
+		self.min_msg_priority = MSG_PRIORITIES[index % MSG_PRIORITIES.len()];
+	}
+	/// Switches the Device colors to the next of the built-in palettes; a hand-edited palette starts over from the first
+	pub fn next_palette(&mut self) {
+		let presets = DeviceTheme::presets();
+		let index = presets.iter().position(|x| x.1 == self.device_theme).map(|x| x + 1).unwrap_or(0);
+		self.device_theme = presets[index % presets.len()].1.clone();
+	}
+	/// Provides the name of the Device palette in use, or "custom" if it was edited by hand
+	pub fn palette_name(&self) -> &'static str {
+		DeviceTheme::presets().iter().find(|x| x.1 == self.device_theme).map(|x| x.0).unwrap_or("custom")
+	}
+	/// Produces the entries for the "Options" submenu, showing the current value of each setting
+	/// The fast_boot setting belongs to the GameConfig, but it's offered here too since it's a matter of taste
+	pub fn menu_items(&self, game_config: &GameConfig) -> Vec<MenuItem<Cow<'static, str>>> {
+		let msg_filter = if self.min_msg_priority == 0 { "all".to_string() } else { format!("priority {}+", self.min_msg_priority) };
+		vec![
+			MenuItem::item(format!("Fast boot: {}", on_off(game_config.fast_boot)), "main.options.fast_boot".into(), None),
+			MenuItem::item(format!("Messages: {}", msg_filter), "main.options.msg_priority".into(), None),
+			MenuItem::item(format!("Tick rate: {}ms (on restart)", self.tick_rate), "main.options.tick_rate".into(), None),
+			MenuItem::item(format!("Palette: {}", self.palette_name()), "main.options.palette".into(), None),
+		]
+	}
+}
+//   ##: GameConfig
+/// Describes the rules and generation settings for a single game
+/// The engine keeps the player's latest choices and copies them into Bevy when a new game starts, so they also
+/// get saved along with the game
+#[derive(Resource, Clone, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct GameConfig {
+	pub ship_file: String, // The layout file to build the ship from, or "procgen" for a randomly-generated one
+	pub item_density: u32, // Scales the number of optional items, as a percentage
+	pub permadeath: bool, // If true, the savegame is deleted when the player loses
+	pub fast_boot: bool, // If true, the PLANQ skips the waits between its boot stages
+}
+impl Default for GameConfig {
+	fn default() -> GameConfig {
+		GameConfig {
+			ship_file: SHIP_LAYOUTS[0].to_string(),
+			item_density: 100,
+			permadeath: false,
+			fast_boot: false,
+		}
+	}
+}
+impl GameConfig {
+	/// Reads the last-used settings from disk, falling back to the defaults if they're missing or unreadable
+	pub fn load() -> GameConfig {
+		read_json_file(&config_filepath("game_config"))
+	}
+	/// Writes these settings to disk so that they can be reused for the next new game
+	pub fn save(&self) {
+		write_json_file(&config_filepath("game_config"), self);
+	}
+	/// Switches to the next ship layout in the list
+	pub fn next_ship(&mut self) {
+		let index = SHIP_LAYOUTS.iter().position(|x| *x == self.ship_file).map(|x| x + 1).unwrap_or(0);
+		self.ship_file = SHIP_LAYOUTS[index % SHIP_LAYOUTS.len()].to_string();
+	}
+	/// Switches to the next item density setting in the list
+	pub fn next_density(&mut self) {
+		let index = ITEM_DENSITIES.iter().position(|x| *x == self.item_density).map(|x| x + 1).unwrap_or(0);
+		self.item_density = ITEM_DENSITIES[index % ITEM_DENSITIES.len()];
+	}
+	/// Produces the entries for the "New Game" submenu, showing the current value of each setting
+	pub fn menu_items(&self) -> Vec<MenuItem<Cow<'static, str>>> {
+		let ship_name = self.ship_file.rsplit('/').next().unwrap_or(&self.ship_file).trim_end_matches(".json").to_string();
+		vec![
+			MenuItem::item("Start", "main.new_game".into(), None),
+			MenuItem::item(format!("Ship: {}", ship_name), "main.config.ship".into(), None),
+			MenuItem::item(format!("Items: {}%", self.item_density), "main.config.density".into(), None),
+			MenuItem::item(format!("Permadeath: {}", on_off(self.permadeath)), "main.config.permadeath".into(), None),
+			MenuItem::item(format!("Fast boot: {}", on_off(self.fast_boot)), "main.config.fast_boot".into(), None),
+		]
+	}
+	/// Repeats or trims the list of optional item requests according to the item density setting
+	/// This doesn't use the RNG so that the PlacementMode::Deterministic layouts stay deterministic
+	pub fn scale_item_requests<T: Clone>(&self, requests: Vec<T>) -> Vec<T> {
+		if requests.is_empty() || self.item_density == 100 { return requests; }
+		let target = requests.len() * self.item_density as usize / 100;
+		requests.iter().cycle().take(target).cloned().collect()
+	}
+}
+//   ##: SaveMeta
+/// Describes a savegame, so that the player can tell their saves apart in the load menu
+/// This is written to a small file next to the savegame, so that it can be shown without loading the whole game
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveMeta {
+	pub saved_at: u64, // When the game was saved, in seconds since the Unix epoch
+	pub turns: u64, // The TurnCount at the time of the save
+	pub location: String, // The name of the room that the player was in
+	pub playtime: u64, // How long the game has been played for, in seconds, across every session
+}
+impl SaveMeta {
+	pub fn new(turns: u64, location: &str, playtime: u64) -> SaveMeta {
+		SaveMeta {
+			saved_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0),
+			turns,
+			location: location.to_string(),
+			playtime,
+		}
+	}
+	/// Reads the metadata for the named savegame, if there is any
+	pub fn load(save_name: &str) -> Option<SaveMeta> {
+		let filepath = meta_filepath(save_name);
+		if !filepath.exists() { return None; }
+		Some(read_json_file(&filepath))
+	}
+	/// Writes out the metadata for the named savegame
+	pub fn save(&self, save_name: &str) {
+		write_json_file(&meta_filepath(save_name), self);
+	}
+	/// Removes the metadata along with its savegame; it's not an error if there wasn't any
+	pub fn delete(save_name: &str) {
+		let _ = fs::remove_file(meta_filepath(save_name));
+	}
+	/// Produces a one-line summary for the load menu, ie "Bridge, turn 120, 0:42 played, saved 2023-11-08 21:15"
+	pub fn describe(&self) -> String {
+		let location = if self.location.is_empty() { "Unknown location" } else { &self.location };
+		format!("{}, turn {}, {}:{:02} played, saved {}",
+			location, self.turns, self.playtime / 3600, (self.playtime / 60) % 60, format_timestamp(self.saved_at))
+	}
+}
+
+//  ###: SIMPLE TYPES AND HELPERS
+fn on_off(state: bool) -> &'static str {
+	if state { "on" } else { "off" }
+}
+/// The config files are stored next to the savegames
+fn config_filepath(name: &str) -> PathBuf {
+	bevy_save::get_save_file(name).with_extension("json")
+}
+/// The savegame metadata is stored next to the savegame that it describes
+fn meta_filepath(save_name: &str) -> PathBuf {
+	config_filepath(&format!("{}_meta", save_name))
+}
+/// Formats a Unix timestamp as a UTC date and time, ie "2023-11-08 21:15"
+fn format_timestamp(secs: u64) -> String {
+	// Converts the day count to a calendar date, using the civil_from_days algorithm by Howard Hinnant
+	let days = (secs / 86400) as i64 + 719468;
+	let era = days.div_euclid(146097);
+	let day_of_era = days.rem_euclid(146097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_index = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+	let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+	let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+	format!("{}-{:02}-{:02} {:02}:{:02}", year, month, day, (secs / 3600) % 24, (secs / 60) % 60)
+}
+/// Reads a config file, returning the defaults if it doesn't exist yet or can't be parsed
+fn read_json_file<T: DeserializeOwned + Default>(filepath: &Path) -> T {
+	let contents = if let Ok(text) = fs::read_to_string(filepath) { text } else {
+		return T::default(); // Not an error: the file won't exist until the settings are changed the first time
+	};
+	match serde_json::from_str(&contents) {
+		Ok(config) => { config }
+		Err(e) => {
+			warn!("* could not parse the config at {}, using the defaults: {}", filepath.display(), e); // DEBUG: report bad config file
+			T::default()
+		}
+	}
+}
+/// Writes a config file, creating the directory for it if needed; failures are reported but otherwise ignored
+fn write_json_file<T: Serialize>(filepath: &Path, data: &T) {
+	if let Some(dir) = filepath.parent() {
+		let _ = fs::create_dir_all(dir);
+	}
+	match serde_json::to_string_pretty(data) {
+		Ok(text) => {
+			if let Err(e) = fs::write(filepath, text) {
+				warn!("* could not write the config to {}: {}", filepath.display(), e); // DEBUG: report config write failure
+			}
+		}
+		Err(e) => { warn!("* could not serialize the config for {}: {}", filepath.display(), e); } // DEBUG: report config write failure
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn palette_option_cycles_through_the_presets() {
+		let mut config = Config::default();
+		assert_eq!(config.palette_name(), "standard");
+		config.next_palette();
+		assert_eq!(config.palette_name(), "high contrast");
+		config.next_palette();
+		assert_eq!(config.palette_name(), "standard");
+		config.device_theme.error = Some("pink".to_string());
+		assert_eq!(config.palette_name(), "custom");
+		config.next_palette();
+		assert_eq!(config.palette_name(), "standard");
+	}
+	#[test]
+	fn ship_option_offers_both_the_layout_file_and_procgen() {
+		let mut config = GameConfig::default();
+		assert_eq!(config.ship_file, "resources/test_ship_v3.json");
+		config.next_ship();
+		assert_eq!(config.ship_file, "procgen");
+		config.next_ship();
+		assert_eq!(config.ship_file, "resources/test_ship_v3.json");
+	}
+}
+
+// EOF
@@ -4,13 +4,15 @@
 //  ###: EXTERNAL LIBS
 use bevy::prelude::*;
 use bevy::ecs::entity::*;
+use bevy::ecs::reflect::ReflectMapEntities;
 use strum_macros::AsRefStr;
 use std::fmt::{Display, Formatter, Result};
 use std::borrow::Cow;
 
 //  ###: INTERNAL LIBS
-use crate::components::Direction;
+use crate::components::{Direction, Position};
 use crate::engine::EngineMode;
+use crate::planq::PlanqEvent;
 
 //  ###: COMPLEX TYPES
 //   ##: GameEvent
@@ -31,12 +33,21 @@ impl GameEvent {
 				None => { Entity::PLACEHOLDER }
 				Some(enty) => { enty }
 			},
+			target: None,
 		};
 		GameEvent {
 			etype: new_type,
 			context: if new_context.is_blank() { None } else { Some(new_context) },
 		}
 	}
+	/// Sets a specific Position for the action to take place at, ie which tile to drop an item onto
+	/// Has no effect on an event without a context, since there would be nobody to perform the action
+	pub fn at(mut self, posn: Position) -> Self {
+		if let Some(ref mut context) = self.context {
+			context.target = Some(posn);
+		}
+		self
+	}
 	/// Checks the Event's context to make sure there are non-PLACEHOLDER values in the right places for the Action
 	pub fn is_valid(&self) -> bool {
 		match self.etype {
@@ -48,6 +59,7 @@ impl GameEvent {
 					match action {
 						// Requires only a subject
 						ActionType::MoveTo(_)
+						| ActionType::Wait
 						=> {
 							if let Some(context) = self.context {
 								context.subject != Entity::PLACEHOLDER
@@ -61,6 +73,8 @@ impl GameEvent {
 						| ActionType::KillItem
 						| ActionType::OpenItem
 						| ActionType::CloseItem
+						| ActionType::LockItem
+						| ActionType::UnlockItem
 						=> {
 							context.subject != Entity::PLACEHOLDER && context.object != Entity::PLACEHOLDER
 						}
@@ -74,7 +88,8 @@ impl GameEvent {
 					false
 				}
 			}
-			GameEventType::PlanqConnect(target) => { target != Entity::PLACEHOLDER && if let Some(context) = self.context { !context.is_blank() } else { false } }
+			// A PLACEHOLDER target means a disconnect, but either way both the actor and the port must be specified
+			GameEventType::PlanqConnect(_) => { if let Some(context) = self.context { !context.is_partial() } else { false } }
 		}
 	}
 }
@@ -127,6 +142,51 @@ pub enum ActionType {
 	CloseItem,          // Openable
 	LockItem,           // Lockable
 	UnlockItem,         // Lockable
+	Wait,               // NONE: the actor spends a moment doing nothing
+}
+impl ActionType {
+	/// Returns how many ticks of game time it takes an entity of normal speed to perform the action
+	pub fn cost(&self) -> u32 {
+		match self {
+			ActionType::NoAction
+			| ActionType::Examine
+			| ActionType::Inventory
+			| ActionType::KillItem    => { 0 }
+			ActionType::MoveTo(Direction::UP)
+			| ActionType::MoveTo(Direction::DOWN) => { 3 } // climbing a ladder is hard work
+			ActionType::LockItem
+			| ActionType::UnlockItem  => { 2 }
+			_                         => { 1 }
+		}
+	}
+	/// Returns how much Stamina the action requires; most actions aren't strenuous enough to need any
+	pub fn exertion(&self) -> u32 {
+		match self {
+			ActionType::MoveTo(Direction::UP)
+			| ActionType::MoveTo(Direction::DOWN) => { 10 }
+			_                                     => { 0 }
+		}
+	}
+	/// Describes the action in a word or two of past tense, for the player's action log, ie "opened"
+	pub fn log_verb(&self) -> String {
+		match self {
+			ActionType::NoAction     => { "did nothing".to_string() }
+			ActionType::Examine      => { "examined".to_string() }
+			ActionType::MoveTo(Direction::UP)   => { "climbed up".to_string() }
+			ActionType::MoveTo(Direction::DOWN) => { "climbed down".to_string() }
+			ActionType::MoveTo(dir)  => { format!("moved {}", dir) }
+			ActionType::Inventory    => { "checked inventory".to_string() }
+			ActionType::MoveItem     => { "picked up".to_string() }
+			ActionType::DropItem     => { "dropped".to_string() }
+			ActionType::UseItem      => { "used".to_string() }
+			ActionType::KillItem     => { "destroyed".to_string() }
+			ActionType::OpenItem     => { "opened".to_string() }
+			ActionType::CloseItem    => { "closed".to_string() }
+			ActionType::LockItem     => { "locked".to_string() }
+			ActionType::UnlockItem   => { "unlocked".to_string() }
+			ActionType::Wait         => { "waited".to_string() }
+		}
+	}
 }
 impl Display for ActionType {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -151,6 +211,7 @@ impl Display for ActionType {
 			ActionType::CloseItem    => { "Close".to_string() }
 			ActionType::LockItem     => { "Lock".to_string() }
 			ActionType::UnlockItem   => { "Unlock".to_string() }
+			ActionType::Wait         => { "Wait".to_string() }
 		};
 		// Trying to write the output var directly causes major borrow issues
 		// Using the output var as an interstitial allows us to use format! to build the string dynamically
@@ -173,12 +234,14 @@ impl From<ActionType> for Cow<'_, str> {
 pub struct GameEventContext {
 	pub subject: Entity, // the entity performing the action; by defn, only one
 	pub object: Entity, // the entity upon which the subject will perform the action
+	pub target: Option<Position>, // where the action should happen, if not simply wherever the subject is
 }
 impl GameEventContext {
 	pub fn new(actor: Entity, target: Entity) -> GameEventContext {
 		GameEventContext {
 			subject: actor,
 			object: target,
+			target: None,
 		}
 	}
 	/// Returns true if either of the context elements are set to the Placeholder
@@ -195,6 +258,7 @@ impl Default for GameEventContext {
 		GameEventContext {
 			subject: Entity::PLACEHOLDER,
 			object: Entity::PLACEHOLDER,
+			target: None,
 		}
 	}
 }
@@ -204,8 +268,86 @@ impl MapEntities for GameEventContext { // Maintain Entity references wrt bevy_s
 		self.object = entity_mapper.get_or_reserve(self.object);
 	}
 }
+//   ##: ScheduledEvent
+/// Holds an event that will be fired into its event queue once its trigger condition has been reached
+/// Spawn these as standalone entities, ie via `commands.schedule(...)`;
+/// the scheduler_system will despawn each one after it goes off
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct ScheduledEvent {
+	pub trigger: ScheduleTrigger,
+	pub payload: ScheduledPayload,
+}
+impl ScheduledEvent {
+	/// Schedules a GameEvent to go off when the trigger is reached
+	pub fn game(trigger: ScheduleTrigger, event: GameEvent) -> ScheduledEvent {
+		ScheduledEvent {
+			trigger,
+			payload: ScheduledPayload::Game(event),
+		}
+	}
+	/// Schedules a PlanqEvent to go off when the trigger is reached
+	pub fn planq(trigger: ScheduleTrigger, event: PlanqEvent) -> ScheduledEvent {
+		ScheduledEvent {
+			trigger,
+			payload: ScheduledPayload::Planq(event),
+		}
+	}
+	/// Returns true if the event's trigger has been reached, given the current turn count and ship time
+	pub fn is_due(&self, turns: u64, ticks: u64) -> bool {
+		match self.trigger {
+			ScheduleTrigger::Turn(fire_at)     => { fire_at <= turns }
+			ScheduleTrigger::ShipTime(fire_at) => { fire_at <= ticks }
+		}
+	}
+}
+impl MapEntities for ScheduledEvent { // Maintain Entity references wrt bevy_save
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		if let ScheduledPayload::Game(ref mut event) = self.payload {
+			if let Some(ref mut context) = event.context {
+				context.map_entities(entity_mapper);
+			}
+		}
+	}
+}
+//    #: ScheduleTrigger
+/// Describes when a ScheduledEvent should go off
+/// Both of these counters are saved with the game, so a pending event will still go off at the right time after a
+/// reload; Bevy's Time starts over from zero whenever the game is loaded, so it can't be used for this
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum ScheduleTrigger {
+	Turn(u64),     // The player's TurnCount
+	ShipTime(u64), // The GameClock's ticks
+}
+impl Default for ScheduleTrigger {
+	fn default() -> ScheduleTrigger {
+		ScheduleTrigger::Turn(0)
+	}
+}
+//    #: ScheduledPayload
+/// Identifies which of the event queues a ScheduledEvent should be delivered to
+#[derive(Clone, Copy, Debug, Reflect)]
+pub enum ScheduledPayload {
+	Game(GameEvent),
+	Planq(PlanqEvent),
+}
+impl Default for ScheduledPayload {
+	fn default() -> ScheduledPayload {
+		ScheduledPayload::Game(GameEvent::default())
+	}
+}
 
 //  ###: SIMPLE TYPES AND HELPERS
+/// Allows any system with a Commands param to enqueue ScheduledEvents, ie
+///   `commands.schedule(ScheduledEvent::game(ScheduleTrigger::Turn(100), reactor_warning));`
+pub trait ScheduleEventExt {
+	fn schedule(&mut self, event: ScheduledEvent);
+}
+impl ScheduleEventExt for Commands<'_, '_> {
+	fn schedule(&mut self, event: ScheduledEvent) {
+		self.spawn(event);
+	}
+}
 /// Allows comparison of two variant enums without regard to their type, ie
 ///   `ModeSwitch(Paused) == ModeSwitch(Running)`
 /// should return TRUE where Rust would return FALSE
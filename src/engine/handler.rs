@@ -13,9 +13,12 @@ use crate::components::*;
 use crate::components::Direction;
 use crate::engine::*;
 use crate::engine::handler::ActionType::*;
+use crate::engine::keybinds::KeyAction;
+use crate::engine::config::GameConfig;
 use crate::engine::event::*;
 use crate::engine::event::GameEventType::*;
 use crate::planq::*;
+use crate::sys::posn_to_point;
 //use crate::engine::planq::PlanqEventType::*;
 
 /// Parses the player inputs coming from ratatui and turns them into game logic
@@ -33,7 +36,9 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 	if (key_event.code == KeyCode::Char('c') || key_event.code == KeyCode::Char('C'))
 	&& key_event.modifiers == KeyModifiers::CONTROL {
 		// Always allow the program to be closed via Ctrl-C
+		// Saving blocks the input loop until the file is written, so this can't interrupt a save
 		eng.quit();
+		return Ok(())
 	}
 	// Extract entity ids for the player and the player's planq
 	let mut player_query = eng.bevy.world.query_filtered::<Entity, With<Player>>();
@@ -41,6 +46,15 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 	let player = player_ref.unwrap_or(Entity::PLACEHOLDER);
 	// ###: GAME CONTROL HANDLING
 	if eng.mode == EngineMode::Running {
+		//  ##: CAMERA PANNING
+		// While the camera is panned away from the player, the movement keys move the camera instead of the player
+		let cli_open = eng.bevy.world.get_resource::<PlanqData>().map(|x| x.show_cli_input).unwrap_or(false);
+		if !cli_open && eng.visible_menu == MenuType::None && eng.is_panning() {
+			if let Some(dir) = eng.keys.action_for(key_event.code).and_then(|x| x.direction()) {
+				eng.pan_camera(dir);
+				return Ok(())
+			}
+		}
 		let mut new_game_event = GameEvent::new(GameEventType::NullEvent, Some(player), None);
 		let mut new_planq_event = PlanqEvent::new(PlanqEventType::NullEvent);
 		// FIXME: once the show_cli_input flag is moved to the GameEngine, this get_resource_mut and unwrap() call can be moved
@@ -59,7 +73,7 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					eng.planq_stdin.input.move_cursor(tui_textarea::CursorMove::Head);
 					eng.planq_stdin.input.delete_line_by_end();
 					let input_text = eng.planq_stdin.input.yank_text().to_string();
-					let echo_text = "[[fg:green]]>[[end]] ".to_string() + &*input_text;
+					let echo_text = eng.cli_prompt() + &*input_text;
 					// WARN: We must finish working with the PLANQ reference before we can get the msglog
 					/*
 					// If there's an idle graphic enabled, we'll want to overwrite it instead of adding another line
@@ -68,10 +82,8 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 						msglog.replace(echo_text.clone(), "planq".to_string(), 0, 0);
 					} else {
 					*/
-					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() { // See above ^^^
-						msglog.tell_planq(&echo_text);
-					}
-					eng.exec(planq_parser(&input_text));
+					eng.tell_planq(&echo_text); // See above ^^^
+					eng.exec_line(&input_text);
 				}
 				// TODO: set up the cursor dirs to allow movement? or reserve for planq menus?
 				the_input => {
@@ -90,14 +102,14 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			return Ok(()) // WARN: do not disable this, lest key inputs be parsed twice (ie again below) by mistake!
 		}
 		//  ##: STANDARD GAME INPUTS
-		match key_event.code {
+		match eng.keys.action_for(key_event.code) {
 			//   #: Meta/menu controls
-			KeyCode::Char('p') => { // Pause key toggle
+			Some(KeyAction::Pause) => { // Pause key toggle
 				// Dispatch immediately, do not defer
 				eng.pause_game();
 				return Ok(())
 			}
-			KeyCode::Esc | KeyCode::Char('Q') => { // Close any open menus, or if none are open, open the main menu
+			Some(KeyAction::Menu) => { // Close any open menus, or if none are open, open the main menu
 				eng.menu_context.reset();
 				if eng.visible_menu != MenuType::None {
 					eng.visible_menu = MenuType::None;
@@ -107,7 +119,7 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					return Ok(())
 				}
 			}
-			KeyCode::Enter => {
+			Some(KeyAction::Confirm) => {
 				if eng.visible_menu == MenuType::Context {
 					eng.menu_context.select();
 					eng.visible_menu = MenuType::None;
@@ -115,28 +127,28 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				}
 			}
 			//   #: The cursor controls will be directed to any open menu before fallthru to player movement
-			KeyCode::Left => {
+			Some(KeyAction::CursorLeft) => {
 				if eng.visible_menu == MenuType::Context {
 					eng.menu_context.left();
 				} else {
 					new_game_event.etype = PlayerAction(MoveTo(Direction::W));
 				}
 			}
-			KeyCode::Down => {
+			Some(KeyAction::CursorDown) => {
 				if eng.visible_menu == MenuType::Context {
 					eng.menu_context.down();
 				} else {
 					new_game_event.etype = PlayerAction(MoveTo(Direction::S));
 				}
 			}
-			KeyCode::Up => {
+			Some(KeyAction::CursorUp) => {
 				if eng.visible_menu == MenuType::Context {
 					eng.menu_context.up();
 				} else {
 					new_game_event.etype = PlayerAction(MoveTo(Direction::N));
 				}
 			}
-			KeyCode::Right => {
+			Some(KeyAction::CursorRight) => {
 				if eng.visible_menu == MenuType::Context {
 					eng.menu_context.right();
 				} else {
@@ -145,26 +157,28 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			}
 			//   #: Simple actions, no context required
 			// The player movement controls will only operate menus if the game is Paused
-			KeyCode::Char('h') => { new_game_event.etype = PlayerAction(MoveTo(Direction::W));}
-			KeyCode::Char('j') => { new_game_event.etype = PlayerAction(MoveTo(Direction::S));}
-			KeyCode::Char('k') => { new_game_event.etype = PlayerAction(MoveTo(Direction::N));}
-			KeyCode::Char('l') => { new_game_event.etype = PlayerAction(MoveTo(Direction::E));}
-			KeyCode::Char('y') => { new_game_event.etype = PlayerAction(MoveTo(Direction::NW));}
-			KeyCode::Char('u') => { new_game_event.etype = PlayerAction(MoveTo(Direction::NE));}
-			KeyCode::Char('b') => { new_game_event.etype = PlayerAction(MoveTo(Direction::SW));}
-			KeyCode::Char('n') => { new_game_event.etype = PlayerAction(MoveTo(Direction::SE));}
-			KeyCode::Char('>') => { new_game_event.etype = PlayerAction(MoveTo(Direction::DOWN));}
-			KeyCode::Char('<') => { new_game_event.etype = PlayerAction(MoveTo(Direction::UP));}
+			Some(KeyAction::MoveW)  => { new_game_event.etype = PlayerAction(MoveTo(Direction::W));}
+			Some(KeyAction::MoveS)  => { new_game_event.etype = PlayerAction(MoveTo(Direction::S));}
+			Some(KeyAction::MoveN)  => { new_game_event.etype = PlayerAction(MoveTo(Direction::N));}
+			Some(KeyAction::MoveE)  => { new_game_event.etype = PlayerAction(MoveTo(Direction::E));}
+			Some(KeyAction::MoveNW) => { new_game_event.etype = PlayerAction(MoveTo(Direction::NW));}
+			Some(KeyAction::MoveNE) => { new_game_event.etype = PlayerAction(MoveTo(Direction::NE));}
+			Some(KeyAction::MoveSW) => { new_game_event.etype = PlayerAction(MoveTo(Direction::SW));}
+			Some(KeyAction::MoveSE) => { new_game_event.etype = PlayerAction(MoveTo(Direction::SE));}
+			Some(KeyAction::Descend) => { new_game_event.etype = PlayerAction(MoveTo(Direction::DOWN));}
+			Some(KeyAction::Ascend) => { new_game_event.etype = PlayerAction(MoveTo(Direction::UP));}
 			//   #: Compound actions, context required: may require secondary inputs from player
-			KeyCode::Char('i') => { // INVENTORY the player's possessions and allow selection
+			Some(KeyAction::Inventory) => { // INVENTORY the player's possessions and allow selection
 				let mut item_names = Vec::new();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
 				// Get every Entity that has a Description, is Portable, and is currently being carried by someone
 				let mut backpack_query = eng.bevy.world.query::<(Entity, &Description, &Portable, &ActionSet)>();
 				for (i_enty, i_desc, i_portable, i_actions) in backpack_query.iter(&eng.bevy.world) {
 					debug!("* found item {}", i_desc.name.clone()); // DEBUG: report the item being worked on
 					if i_portable.carrier == player {
 						let mut menu_entries = Vec::new();
-						for action in i_actions.actions.iter() {
+						// Only offer the actions that the player is able to perform on the item
+						for action in p_actions.intersect(i_actions).iter() {
 							menu_entries.push(GameEvent::new(PlayerAction(*action), Some(player), Some(i_enty)));
 						}
 						let submenu = make_new_submenu(menu_entries);
@@ -174,9 +188,7 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				}
 				if item_names.is_empty() {
 					debug!("* Nothing in inventory to display"); // DEBUG: announce feedback
-					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
-						msglog.tell_player("You are not carrying anything.");
-					}
+					eng.tell_player("You are not carrying anything.");
 					return Ok(());
 				} else {
 					//debug!("* Attempting to show_chooser()"); // DEBUG: announce attempt to show the context menu
@@ -184,40 +196,48 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('d') => { // DROP an item from player's inventory
+			Some(KeyAction::Drop) => { // DROP an item from player's inventory
 				let mut item_names = Vec::new();
-				let mut backpack_query = eng.bevy.world.query_filtered::<(Entity, &Description, &Portable), With<IsCarried>>();
-				for (i_enty, i_desc, i_portable) in backpack_query.iter(&eng.bevy.world) {
-					if i_portable.carrier == player {
-						item_names.push(MenuItem::item(
-							i_desc.name.clone(),
-							GameEvent::new(PlayerAction(DropItem), Some(player), Some(i_enty)),
-							None,
-							)
-						);
+				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
+					*value
+				} else {
+					return Ok(())
+				};
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				let mut backpack_query = eng.bevy.world.query_filtered::<(Entity, &Description, &Portable, &ActionSet), With<IsCarried>>();
+				for (i_enty, i_desc, i_portable, i_actions) in backpack_query.iter(&eng.bevy.world) {
+					if i_portable.carrier == player && p_actions.intersect(i_actions).contains(&DropItem) {
+						// Each item gets a submenu for picking which tile to put it on
+						let drop_event = GameEvent::new(PlayerAction(DropItem), Some(player), Some(i_enty));
+						let mut drop_spots = vec![MenuItem::item("Here", drop_event, Some(p_posn))];
+						for dir in Direction::COMPASS.iter() {
+							let (x_diff, y_diff, _) = dir.offset();
+							let spot = Position::new(p_posn.x + x_diff, p_posn.y + y_diff, p_posn.z);
+							drop_spots.push(MenuItem::item(dir.to_string(), drop_event.at(spot), Some(spot)));
+						}
+						item_names.push(MenuItem::group(i_desc.name.clone(), drop_spots));
 					}
 				}
 				if item_names.is_empty() {
-					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
-						msglog.tell_player("You have nothing to drop.");
-					}
+					eng.tell_player("You have nothing to drop.");
 					return Ok(())
 				} else {
 					eng.menu_context = MenuState::new(item_names);
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('g') => { // GET an item from the ground
+			Some(KeyAction::PickUp) => { // GET an item from the ground
 				let mut item_names = Vec::new();
-				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &Portable)>();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &ActionSet)>();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
 					value
 				} else {
 					return Ok(())
 				};
-				for (t_enty, t_desc, t_body, _portable) in item_query.iter(&eng.bevy.world) {
+				for (t_enty, t_desc, t_body, t_actions) in item_query.iter(&eng.bevy.world) {
 					//debug!("* found item {}", target.1.name.clone()); // DEBUG: announce found targets for GET
-					if t_body.contains(p_posn) {
+					if t_enty != player && t_body.contains(p_posn) && p_actions.intersect(t_actions).contains(&MoveItem) {
 						item_names.push(MenuItem::item(
 							t_desc.name.clone(),
 							GameEvent::new(PlayerAction(MoveItem), Some(player), Some(t_enty)),
@@ -227,27 +247,35 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				}
 				if item_names.is_empty() {
 					//debug!("* Nothing to pick up at player's position"); // DEBUG: announce feedback
-					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
-						msglog.tell_player("There's nothing here to pick up.");
-					}
+					eng.tell_player("There's nothing here to pick up.");
 					return Ok(())
 				} else {
+					if item_names.len() > 1 {
+						// A MoveItem without an object is expanded into one event per item by GameEngine::tick()
+						item_names.push(MenuItem::item(
+							"Take all",
+							GameEvent::new(PlayerAction(MoveItem), Some(player), None),
+							None,
+						));
+					}
 					//debug!("* Attempting to set the entity menu"); // DEBUG: announce entity menu use
 					eng.menu_context = MenuState::new(item_names);
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('o') => { // OPEN an Openable item
+			Some(KeyAction::Open) => { // OPEN an Openable item
 				let mut item_names = Vec::new();
-				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &Openable)>();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				// An Openable only offers whichever of OpenItem and CloseItem would change something
+				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &ActionSet)>();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
 					value
 				} else {
 					return Ok(())
 				};
-				for (t_enty, t_desc, t_body, t_open) in item_query.iter(&eng.bevy.world) {
+				for (t_enty, t_desc, t_body, t_actions) in item_query.iter(&eng.bevy.world) {
 					//debug!("* found item {}", target.1.name.clone()); // DEBUG: report found OPENABLE items
-					if t_body.is_adjacent_to(p_posn) && !t_open.is_open {
+					if t_body.is_adjacent_to(p_posn) && p_actions.intersect(t_actions).contains(&OpenItem) {
 						item_names.push(MenuItem::item(
 								t_desc.name.clone(),
 								GameEvent::new(PlayerAction(OpenItem), Some(player), Some(t_enty)),
@@ -258,8 +286,7 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				}
 				if item_names.is_empty() {
 					//debug!("* Nothing to open nearby"); // DEBUG: announce feedback
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to open.");
+					eng.tell_player("There's nothing nearby to open.");
 					return Ok(())
 				} else {
 					//debug!("* Attempting to set the entity menu"); // DEBUG: announce entity menu use
@@ -267,17 +294,19 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('c') => { // CLOSE an Openable nearby
+			Some(KeyAction::Close) => { // CLOSE an Openable nearby
 				let mut item_names = Vec::new();
-				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &Openable)>();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				// An Openable only offers whichever of OpenItem and CloseItem would change something
+				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &ActionSet)>();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
 					value
 				} else {
 					return Ok(())
 				};
-				for (t_enty, t_desc, t_body, t_open) in item_query.iter(&eng.bevy.world) {
+				for (t_enty, t_desc, t_body, t_actions) in item_query.iter(&eng.bevy.world) {
 					//debug!("* found item {}", target.1.name.clone()); // DEBUG: report found closed OPENABLE items
-					if t_body.is_adjacent_to(p_posn) && t_open.is_open {
+					if t_body.is_adjacent_to(p_posn) && p_actions.intersect(t_actions).contains(&CloseItem) {
 						item_names.push(MenuItem::item(
 								t_desc.name.clone(),
 								GameEvent::new(PlayerAction(CloseItem), Some(player), Some(t_enty)),
@@ -288,8 +317,7 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				}
 				if item_names.is_empty() {
 					//debug!("* Nothing to close nearby"); // DEBUG: announce feedback
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to close.");
+					eng.tell_player("There's nothing nearby to close.");
 					return Ok(())
 				} else {
 					//debug!("* Attempting to set the entity menu"); // DEBUG: announce entity menu use
@@ -297,28 +325,56 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('x') => { // EXAMINE a nearby Entity
+			Some(KeyAction::Examine) => { // EXAMINE a nearby Entity
 				let mut enty_names = Vec::new();
-				let mut enty_query = eng.bevy.world.query::<(Entity, &Description, &Body)>();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
+					*value
 				} else {
 					return Ok(())
 				};
-				for (t_enty, t_desc, t_body) in enty_query.iter(&eng.bevy.world) {
+				let nearby = if let Some(model) = eng.bevy.world.get_resource::<WorldModel>() {
+					model.get_entities_in_range(p_posn, EXAMINE_RANGE)
+				} else {
+					return Ok(())
+				};
+				for t_enty in nearby.iter() {
 					//debug!("* Found target {}", target.1.name.clone()); // DEBUG: announce EXAMINE target
-					if t_body.in_range_of(p_posn, 2) {
+					let can_examine = eng.bevy.world.get::<ActionSet>(*t_enty).is_some_and(|x| p_actions.intersect(x).contains(&Examine));
+					if !can_examine { continue; }
+					if let (Some(t_desc), Some(t_body)) = (eng.bevy.world.get::<Description>(*t_enty), eng.bevy.world.get::<Body>(*t_enty)) {
 						enty_names.push(MenuItem::item(
 							t_desc.name.clone(),
-							GameEvent::new(PlayerAction(Examine), Some(player), Some(t_enty)),
+							GameEvent::new(PlayerAction(Examine), Some(player), Some(*t_enty)),
 							Some(t_body.ref_posn),
 						));
 					}
 				}
+				// Anything that the player remembers seeing elsewhere on this deck can be examined too, from memory
+				let mut remembered: Vec<(Position, Entity)> = Vec::new();
+				if let Some(p_memory) = eng.bevy.world.get::<Memory>(player) {
+					let p_viewshed = eng.bevy.world.get::<Viewshed>(player);
+					for (posn, entys) in p_memory.visual.iter() {
+						if posn.z != p_posn.z || p_viewshed.is_some_and(|x| x.visible_points.contains(&posn_to_point(posn))) { continue; }
+						for enty in entys.iter() {
+							if *enty == player || nearby.contains(enty) || eng.bevy.world.get::<IsCarried>(*enty).is_some() { continue; }
+							remembered.push((*posn, *enty));
+						}
+					}
+				}
+				remembered.sort_by_key(|(posn, _)| ((posn.x - p_posn.x).abs().max((posn.y - p_posn.y).abs()), *posn));
+				for (posn, t_enty) in remembered.iter() {
+					if let Some(t_desc) = eng.bevy.world.get::<Description>(*t_enty) {
+						enty_names.push(MenuItem::item(
+							format!("{} (remembered)", t_desc.name),
+							GameEvent::new(PlayerAction(Examine), Some(player), Some(*t_enty)),
+							Some(*posn),
+						));
+					}
+				}
 				if enty_names.is_empty() {
 					//debug!("* Nothing close enough to examine"); // DEBUG: report EXAMINE failure
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to examine.");
+					eng.tell_player("There's nothing nearby to examine.");
 					return Ok(());
 				} else {
 					//debug!("* Attempting to set the entity menu with targets");// DEBUG: announce examine menu use
@@ -326,10 +382,11 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('a') => { // APPLY (use) an Operable item
+			Some(KeyAction::Apply) => { // APPLY (use) an Operable item
 				// Get a list of all Operable items in the player's vicinity
 				let mut device_names = Vec::new();
-				let mut device_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, Option<&Portable>, &Device)>();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				let mut device_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, Option<&Portable>, &ActionSet)>();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
 					value
 				} else {
@@ -337,7 +394,8 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				};
 				//eng.item_chooser.list.clear();
 				// Drop them into one of the choosers
-				for (d_enty, d_body, d_desc, d_portable, _device) in device_query.iter(&eng.bevy.world) {
+				for (d_enty, d_body, d_desc, d_portable, d_actions) in device_query.iter(&eng.bevy.world) {
+					if !p_actions.intersect(d_actions).contains(&UseItem) { continue; }
 					if let Some(is_portable) = d_portable {
 						if is_portable.carrier == player {
 							device_names.push(MenuItem::item(
@@ -348,7 +406,7 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 						}
 					//} else if device.1.is_some() { // Is the player near it?
 					} else if let Some(has_body) = d_body {
-						if p_posn.in_range_of(&has_body.ref_posn, 1) {
+						if p_posn.in_range_of(&has_body.ref_posn, REACH_RANGE) {
 							device_names.push(MenuItem::item(
 								d_desc.name.clone(),
 								GameEvent::new(PlayerAction(UseItem), Some(player), Some(d_enty)),
@@ -358,26 +416,26 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					}
 				}
 				if device_names.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to use.");
+					eng.tell_player("There's nothing nearby to use.");
 					return Ok(())
 				} else {
 					eng.menu_context = MenuState::new(device_names);
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('L') => { // LOCK a Lockable item
+			Some(KeyAction::Lock) => { // LOCK a Lockable item
 				let mut lock_names = Vec::new();
-				let mut lock_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, &Lockable)>();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				let mut lock_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, &Lockable, &ActionSet)>();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
 					value
 				} else {
 					return Ok(())
 				};
-				for (l_enty, l_body, l_desc, l_lock) in lock_query.iter(&eng.bevy.world) {
+				for (l_enty, l_body, l_desc, l_lock, l_actions) in lock_query.iter(&eng.bevy.world) {
 					if let Some(l_posn) = l_body {
-						if l_posn.in_range_of(p_posn, 1)
-						&& l_lock.is_locked {
+						if p_actions.intersect(l_actions).contains(&LockItem)
+						&& action_is_available(LockItem, l_posn.in_range_of(p_posn, REACH_RANGE), false, None, Some(l_lock)) {
 							lock_names.push(MenuItem::item(
 								l_desc.name.clone(),
 								GameEvent::new(PlayerAction(LockItem), Some(player), Some(l_enty)),
@@ -387,26 +445,26 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					}
 				}
 				if lock_names.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing to lock nearby.");
+					eng.tell_player("There's nothing to lock nearby.");
 					return Ok(())
 				} else {
 					eng.menu_context = MenuState::new(lock_names);
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('U') => { // UNLOCK a Lockable item
+			Some(KeyAction::Unlock) => { // UNLOCK a Lockable item
 				let mut lock_names = Vec::new();
-				let mut lock_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, &Lockable)>();
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				let mut lock_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, &Lockable, &ActionSet)>();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
 					value
 				} else {
 					return Ok(())
 				};
-				for (l_enty, l_body, l_desc, l_lock) in lock_query.iter(&eng.bevy.world) {
+				for (l_enty, l_body, l_desc, l_lock, l_actions) in lock_query.iter(&eng.bevy.world) {
 					if let Some(l_posn) = l_body {
-						if !l_lock.is_locked
-						&& l_posn.in_range_of(p_posn, 1) {
+						if p_actions.intersect(l_actions).contains(&UnlockItem)
+						&& action_is_available(UnlockItem, l_posn.in_range_of(p_posn, REACH_RANGE), false, None, Some(l_lock)) {
 							lock_names.push(MenuItem::item(
 								l_desc.name.clone(),
 								GameEvent::new(PlayerAction(UnlockItem), Some(player), Some(l_enty)),
@@ -416,15 +474,76 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					}
 				}
 				if lock_names.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing to unlock nearby.");
+					eng.tell_player("There's nothing to unlock nearby.");
 					return Ok(())
 				} else {
 					eng.menu_context = MenuState::new(lock_names);
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('C') => { // CONNECT the PLANQ to a nearby AccessPort
+			Some(KeyAction::Interact) => { // INTERACT with the closest thing nearby, using whichever action makes the most sense for it
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
+					*value
+				} else {
+					return Ok(())
+				};
+				// Keep the best-ranked (rank, distance, target, action) as the candidates are compared
+				let mut choice: Option<(usize, i32, Entity, ActionType)> = None;
+				let mut target_query = eng.bevy.world.query::<(Entity, &Body, &ActionSet, Option<&Openable>, Option<&Portable>)>();
+				for (t_enty, t_body, t_actions, t_open, t_portable) in target_query.iter(&eng.bevy.world) {
+					if t_enty == player || !t_body.in_range_of(&p_posn, REACH_RANGE) { continue; }
+					// Carried items don't update their Body, so don't let them be picked up again by accident
+					if t_portable.is_some_and(|x| x.carrier != Entity::PLACEHOLDER) { continue; }
+					let is_here = t_body.contains(&p_posn);
+					let distance = if is_here { 0 } else { 1 };
+					if let Some((rank, action)) = default_interaction(&p_actions.intersect(t_actions), t_open, is_here) {
+						if choice.is_none() || choice.is_some_and(|x| (rank, distance) < (x.0, x.1)) {
+							choice = Some((rank, distance, t_enty, action));
+						}
+					}
+				}
+				if let Some((_rank, _distance, t_enty, action)) = choice {
+					new_game_event.etype = PlayerAction(action);
+					new_game_event.context = Some(GameEventContext::new(player, t_enty));
+				} else {
+					eng.tell_player("There's nothing nearby to interact with.");
+					return Ok(())
+				}
+			}
+			Some(KeyAction::ActionMenu) => { // List EVERY action that can be taken on anything nearby, grouped by target
+				let p_actions = eng.bevy.world.get::<ActionSet>(player).cloned().unwrap_or_default();
+				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
+					*value
+				} else {
+					return Ok(())
+				};
+				let mut target_names = Vec::new();
+				let mut target_query = eng.bevy.world.query::<(Entity, &Body, &Description, &ActionSet, Option<&Openable>, Option<&Lockable>, Option<&Portable>)>();
+				for (t_enty, t_body, t_desc, t_actions, t_open, t_lock, t_portable) in target_query.iter(&eng.bevy.world) {
+					if t_enty == player || !t_body.in_range_of(&p_posn, EXAMINE_RANGE) { continue; }
+					// Carried items don't update their Body, and they have their own menu anyway
+					if t_portable.is_some_and(|x| x.carrier != Entity::PLACEHOLDER) { continue; }
+					let in_reach = t_body.in_range_of(&p_posn, REACH_RANGE);
+					let is_here = t_body.contains(&p_posn);
+					let mut menu_entries = Vec::new();
+					for action in p_actions.intersect(t_actions).iter() {
+						if action_is_available(*action, in_reach, is_here, t_open, t_lock) {
+							menu_entries.push(GameEvent::new(PlayerAction(*action), Some(player), Some(t_enty)));
+						}
+					}
+					if menu_entries.is_empty() { continue; }
+					target_names.push(MenuItem::group(t_desc.name.clone(), make_new_submenu(menu_entries)));
+				}
+				if target_names.is_empty() {
+					eng.tell_player("There's nothing nearby to interact with.");
+					return Ok(())
+				} else {
+					eng.menu_context = MenuState::new(target_names);
+					eng.set_menu(MenuType::Context, (15, 5));
+				}
+			}
+			Some(KeyAction::Connect) => { // CONNECT the PLANQ to a nearby AccessPort
 				let mut access_ports = Vec::new();
 				let mut port_query = eng.bevy.world.query_filtered::<(Entity, &Body, &Description), With<AccessPort>>();
 				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
@@ -442,32 +561,62 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 					}
 				}
 				if access_ports.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There are no access ports nearby.");
+					eng.tell_player("There are no access ports nearby.");
 					return Ok(())
 				} else {
 					eng.menu_context = MenuState::new(access_ports);
 					eng.set_menu(MenuType::Context, (15, 5));
 				}
 			}
-			KeyCode::Char('D') => { // DISCONNECT the PLANQ from a connected AccessPort, if set
+			Some(KeyAction::Disconnect) => { // DISCONNECT the PLANQ from a connected AccessPort, if set
 				if planq.jack_cnxn == Entity::PLACEHOLDER {
 					// report "no connection" and abort the action
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing connected to your PLANQ.");
+					eng.tell_player("There's nothing connected to your PLANQ.");
 				} else {
 					// disconnect the PLANQ
 					new_game_event.etype = PlanqConnect(Entity::PLACEHOLDER);
-					new_game_event.context = Some(GameEventContext{ subject: player, object: planq.jack_cnxn });
+					new_game_event.context = Some(GameEventContext::new(player, planq.jack_cnxn));
 				}
 			}
+			Some(KeyAction::Wait) => { // WAIT a moment, and catch your breath
+				new_game_event.etype = PlayerAction(Wait);
+			}
 			//   #: PLANQ 'sidebar'/ambient controls
-			KeyCode::Char('P') | KeyCode::Char(':') => {
+			Some(KeyAction::PlanqCli) => {
 				if planq.cpu_mode == PlanqCPUMode::Idle || planq.cpu_mode == PlanqCPUMode::Working {
 					new_planq_event.etype = PlanqEventType::CliOpen;
 				}
 			}
+			Some(KeyAction::Stats) => { // Toggle the character summary screen
+				eng.show_stats = !eng.show_stats;
+			}
+			Some(KeyAction::MsgChannel) => { // Cycle the message log pane through its channels
+				eng.msg_channel = (eng.msg_channel + 1) % MSG_PANE_CHANNELS.len();
+			}
+			Some(KeyAction::ReachOverlay) => { // Toggle the highlighting of the tiles within the player's reach
+				if let Some(mut camera) = eng.bevy.world.get_resource_mut::<CameraView>() {
+					camera.show_reach = !camera.show_reach;
+				}
+			}
+			Some(KeyAction::PanCamera) => { // Detach the camera from the player to look around the deck
+				if !eng.is_panning() {
+					eng.start_panning();
+					eng.tell_player(&format!("You look around. {}.", eng.keys.describe(KeyAction::CenterCamera)));
+				}
+			}
+			Some(KeyAction::CenterCamera) => { // Bring the camera back to the player
+				eng.stop_panning();
+			}
 			//   #: Debug keys and other tools
+			Some(KeyAction::ReloadDefns) => { // DEBUG: Reload the item definitions from disk
+				if !eng.debug_mode { return Ok(()) }
+				info!("* Reloading item definitions"); // DEBUG: announce item defn reload
+				eng.reload_item_defns();
+			}
+			Some(KeyAction::DebugHud) => { // DEBUG: Toggle the timing overlay
+				if !eng.debug_mode { return Ok(()) }
+				eng.frame_times.visible = !eng.frame_times.visible;
+			}
 			/* Disabled these since I deprecated the make_item function
 			 *KeyCode::Char('s') => { // DEBUG: Drop a generic snack item for testing
 			 *	info!("* Dropping snack at 5, 5, 0"); // DEBUG: announce arrival of debug snack
@@ -478,12 +627,25 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			 *	eng.give_item(ItemType::Snack, player);
 			 *}
 			 */
-			_ => {
+			None => {
 				error!("* Unhandled key: {:?}", key_event.code); // DEBUG: report an unhandled key from this method
 			}
 		}
 		// If an event was generated, send it off for processing
 		if new_game_event.etype != GameEventType::NullEvent {
+			// Drop any events that are missing a required subject or object, so that the systems don't have to
+			if !new_game_event.is_valid() {
+				warn!("* Dropped invalid event before dispatch: {:?}", new_game_event); // DEBUG: report the dropped event
+				return Ok(())
+			}
+			// Strenuous actions are blocked outright if the player doesn't have the stamina for them
+			if let PlayerAction(action) = new_game_event.etype {
+				let p_stamina = eng.bevy.world.get::<Stamina>(player).map(|x| x.current).unwrap_or(u32::MAX);
+				if p_stamina < action.exertion() {
+					eng.tell_player("You're too exhausted to do that. Try resting for a moment.");
+					return Ok(())
+				}
+			}
 			// Get a linkage to the game event distribution system
 			if let Some(mut game_events) = eng.bevy.world.get_resource_mut::<Events<GameEvent>>() {
 				game_events.send(new_game_event);
@@ -495,6 +657,16 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			}
 		}
 	} else { // ###: ALL OTHER SITUATIONS: Paused, Standby, etc
+		// The end screen only waits for the player to acknowledge it
+		if eng.mode == EngineMode::GoodEnd || eng.mode == EngineMode::BadEnd {
+			info!("* Game over, engine is shutting down..."); // DEBUG: announce engine shutdown
+			let permadeath = eng.bevy.world.get_resource::<GameConfig>().map(|x| x.permadeath).unwrap_or(false);
+			if eng.mode == EngineMode::BadEnd && permadeath {
+				let _ = eng.delete_game(&eng.savegame_filename.clone()); // Permadeath means no reloading the last save
+			}
+			eng.quit();
+			return Ok(())
+		}
 		match key_event.code {
 			// Only handle these keys if the game's actually in-progress
 			// Close open menus/unpause on Esc or Q
@@ -536,9 +708,58 @@ pub fn planq_parser(input: &str) -> PlanqCmd {
 		"reboot" => { PlanqCmd::Reboot }
 		"connect" => { PlanqCmd::Connect(input_vec[1].to_string()) }
 		"disconnect" => { PlanqCmd::Disconnect }
+		"locate" => {
+			if input_vec.len() < 2 { return PlanqCmd::Error("Usage: locate <item name>".to_string()); }
+			PlanqCmd::Locate(input_vec[1..].join(" "))
+		}
+		"reveal" => { PlanqCmd::Reveal(input_vec.get(1) == Some(&"all")) }
+		"status" => { PlanqCmd::Status }
+		"reload" => { PlanqCmd::Reload }
+		"export" => { PlanqCmd::Export }
+		"deck" => {
+			match input_vec.get(1).map(|x| x.parse::<usize>()) {
+				Some(Ok(z_level)) => { PlanqCmd::Deck(z_level) }
+				_ => { PlanqCmd::Error("Usage: deck <deck number>".to_string()) }
+			}
+		}
+		"scan" => {
+			if input_vec.len() < 2 { return PlanqCmd::Error("Usage: scan <room name>".to_string()); }
+			PlanqCmd::Scan(input_vec[1..].join(" "))
+		}
+		"note" => {
+			if input_vec.len() < 2 { return PlanqCmd::Error("Usage: note <text>".to_string()); }
+			PlanqCmd::Note(input_vec[1..].join(" "))
+		}
+		"notes" => { PlanqCmd::Notes }
+		"!!" => { PlanqCmd::Repeat }
 		input => { PlanqCmd::Error(format!("Unknown command: {}", input)) } // No matching command was found!
 	}
 }
+/// Picks the most sensible action from a set of possible interactions, along with its rank (lower is better)
+/// The Openable state is needed to decide between opening or closing; only items underfoot can be picked up
+pub fn default_interaction(actions: &HashSet<ActionType>, openable: Option<&Openable>, is_here: bool) -> Option<(usize, ActionType)> {
+	let is_open = openable.map(|x| x.is_open);
+	if actions.contains(&OpenItem) && is_open == Some(false) { return Some((0, OpenItem)); }
+	if actions.contains(&UseItem) { return Some((1, UseItem)); }
+	if actions.contains(&MoveItem) && is_here { return Some((2, MoveItem)); }
+	if actions.contains(&CloseItem) && is_open == Some(true) { return Some((3, CloseItem)); }
+	None
+}
+/// Decides whether an action makes sense for a target in its current state, for the combined action menu
+/// This applies the same rules as the per-action keys: only Examine works at a distance, only items underfoot can be
+/// picked up, and there's no point in opening an open door or locking a locked one
+pub fn action_is_available(action: ActionType, in_reach: bool, is_here: bool, openable: Option<&Openable>, lockable: Option<&Lockable>) -> bool {
+	match action {
+		Examine    => { true }
+		MoveItem   => { is_here }
+		OpenItem   => { in_reach && openable.is_some_and(|x| !x.is_open) }
+		CloseItem  => { in_reach && openable.is_some_and(|x| x.is_open) }
+		LockItem   => { in_reach && lockable.is_some_and(|x| !x.is_locked) }
+		UnlockItem => { in_reach && lockable.is_some_and(|x| x.is_locked) }
+		UseItem    => { in_reach }
+		_          => { false }
+	}
+}
 /// Converts my Event keycodes into tui_textarea::Input::Keys
 pub fn keycode_to_input_key(key_code: KeyCode) -> Key {
 	match key_code {
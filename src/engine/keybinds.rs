@@ -0,0 +1,272 @@
+// engine/keybinds.rs
+// Maps the player's keypresses to the game's controls, so that the controls can be described and rebound
+
+//  ###: EXTERNAL LIBRARIES
+use std::str::FromStr;
+use simplelog::*;
+use crossterm::event::KeyCode;
+use strum_macros::{AsRefStr, EnumString};
+
+//  ###: INTERNAL LIBRARIES
+use crate::components::Direction;
+
+//  ###: COMPLEX TYPES
+//   ##: KeyBindings
+/// Holds the set of keys that are bound to each of the game's controls while the game is running
+/// The key_parser looks up the KeyAction for each keypress here, so any text that describes the controls should
+/// ask this object for the bindings instead of naming the keys directly
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBindings {
+	bindings: Vec<(KeyAction, Vec<KeyCode>)>,
+}
+impl KeyBindings {
+	pub fn new() -> KeyBindings {
+		KeyBindings::default()
+	}
+	/// Replaces the set of keys that are bound to the given action
+	pub fn bind(mut self, action: KeyAction, keys: Vec<KeyCode>) -> Self {
+		if let Some(binding) = self.bindings.iter_mut().find(|x| x.0 == action) {
+			binding.1 = keys;
+		} else {
+			self.bindings.push((action, keys));
+		}
+		self
+	}
+	/// Returns the action that the given key is bound to, if any
+	pub fn action_for(&self, key: KeyCode) -> Option<KeyAction> {
+		self.bindings.iter().find(|x| x.1.contains(&key)).map(|x| x.0)
+	}
+	/// Returns all of the keys that are bound to the given action
+	pub fn keys_for(&self, action: KeyAction) -> Vec<KeyCode> {
+		self.bindings.iter().find(|x| x.0 == action).map(|x| x.1.clone()).unwrap_or_default()
+	}
+	/// Returns the names of the keys bound to the action, ready to use in a sentence, ie "Esc or Q"
+	pub fn key_names_for(&self, action: KeyAction) -> String {
+		let names: Vec<String> = self.keys_for(action).iter().map(|x| key_name(*x)).collect();
+		if names.is_empty() { return "(unbound)".to_string(); }
+		names.join(" or ")
+	}
+	/// Describes how to perform the given action using its current bindings, ie "Press g to pick up an item"
+	pub fn describe(&self, action: KeyAction) -> String {
+		format!("Press {} to {}", self.key_names_for(action), action.description())
+	}
+	/// Lists every binding by name, ie for writing them out to the config file
+	pub fn to_names(&self) -> Vec<(String, Vec<String>)> {
+		self.bindings.iter().map(|x| (x.0.as_ref().to_string(), x.1.iter().map(|y| key_name(*y)).collect())).collect()
+	}
+	/// Applies a list of bindings by name, ie from the config file; any that can't be understood are skipped
+	pub fn with_names(mut self, names: &[(String, Vec<String>)]) -> Self {
+		for (action_name, key_names) in names.iter() {
+			let action = if let Ok(value) = KeyAction::from_str(action_name) { value } else {
+				warn!("* unknown action '{}' in the keybindings config, skipping it", action_name); // DEBUG: report bad keybinding
+				continue;
+			};
+			let mut keys = Vec::new();
+			for name in key_names.iter() {
+				if let Some(key) = parse_key_name(name) { keys.push(key); } else {
+					warn!("* unknown key '{}' bound to {} in the keybindings config, skipping it", name, action_name); // DEBUG: report bad keybinding
+				}
+			}
+			self = self.bind(action, keys);
+		}
+		self
+	}
+	/// Lists every action along with the keys bound to it, for use in help screens
+	pub fn describe_all(&self) -> Vec<(String, String)> {
+		self.bindings.iter().map(|x| (self.key_names_for(x.0), x.0.description().to_string())).collect()
+	}
+}
+impl Default for KeyBindings {
+	fn default() -> KeyBindings {
+		use KeyAction::*;
+		KeyBindings {
+			bindings: vec![
+				(Pause,        vec![KeyCode::Char('p')]),
+				(Menu,         vec![KeyCode::Esc, KeyCode::Char('Q')]),
+				(Confirm,      vec![KeyCode::Enter]),
+				(CursorLeft,   vec![KeyCode::Left]),
+				(CursorDown,   vec![KeyCode::Down]),
+				(CursorUp,     vec![KeyCode::Up]),
+				(CursorRight,  vec![KeyCode::Right]),
+				(MoveW,        vec![KeyCode::Char('h')]),
+				(MoveS,        vec![KeyCode::Char('j')]),
+				(MoveN,        vec![KeyCode::Char('k')]),
+				(MoveE,        vec![KeyCode::Char('l')]),
+				(MoveNW,       vec![KeyCode::Char('y')]),
+				(MoveNE,       vec![KeyCode::Char('u')]),
+				(MoveSW,       vec![KeyCode::Char('b')]),
+				(MoveSE,       vec![KeyCode::Char('n')]),
+				(Descend,      vec![KeyCode::Char('>')]),
+				(Ascend,       vec![KeyCode::Char('<')]),
+				(Inventory,    vec![KeyCode::Char('i')]),
+				(Drop,         vec![KeyCode::Char('d')]),
+				(PickUp,       vec![KeyCode::Char('g')]),
+				(Open,         vec![KeyCode::Char('o')]),
+				(Close,        vec![KeyCode::Char('c')]),
+				(Examine,      vec![KeyCode::Char('x')]),
+				(Apply,        vec![KeyCode::Char('a')]),
+				(Lock,         vec![KeyCode::Char('L')]),
+				(Unlock,       vec![KeyCode::Char('U')]),
+				(Interact,     vec![KeyCode::Char('e')]),
+				(ActionMenu,   vec![KeyCode::Char('E')]),
+				(Connect,      vec![KeyCode::Char('C')]),
+				(Disconnect,   vec![KeyCode::Char('D')]),
+				(Wait,         vec![KeyCode::Char('.')]),
+				(PlanqCli,     vec![KeyCode::Char('P'), KeyCode::Char(':')]),
+				(Stats,        vec![KeyCode::Char('@')]),
+				(MsgChannel,   vec![KeyCode::Tab]),
+				(ReachOverlay, vec![KeyCode::Char('r')]),
+				(PanCamera,    vec![KeyCode::Char('v')]),
+				(CenterCamera, vec![KeyCode::Char('V')]),
+				(ReloadDefns,  vec![KeyCode::F(5)]),
+				(DebugHud,     vec![KeyCode::F(3)]),
+			],
+		}
+	}
+}
+
+//  ###: SIMPLE TYPES AND HELPERS
+//   ##: KeyAction
+/// Names each of the controls that the player can use while the game is running
+/// The variant names are also used for the keybindings in the config file, so renaming one will unbind it
+#[derive(AsRefStr, EnumString, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyAction {
+	Pause,
+	Menu,
+	Confirm,
+	CursorLeft,
+	CursorDown,
+	CursorUp,
+	CursorRight,
+	MoveW,
+	MoveS,
+	MoveN,
+	MoveE,
+	MoveNW,
+	MoveNE,
+	MoveSW,
+	MoveSE,
+	Descend,
+	Ascend,
+	Inventory,
+	Drop,
+	PickUp,
+	Open,
+	Close,
+	Examine,
+	Apply,
+	Lock,
+	Unlock,
+	Interact,
+	ActionMenu,
+	Connect,
+	Disconnect,
+	Wait,
+	PlanqCli,
+	ReloadDefns, // DEBUG
+	Stats,
+	MsgChannel,
+	ReachOverlay,
+	PanCamera,
+	CenterCamera,
+	DebugHud,    // DEBUG
+}
+impl KeyAction {
+	/// Provides a short description of the action, phrased to follow "Press <key> to ..."
+	pub fn description(&self) -> &'static str {
+		match self {
+			KeyAction::Pause       => { "pause or unpause the game" }
+			KeyAction::Menu        => { "close any open menu, or open the main menu" }
+			KeyAction::Confirm     => { "choose the selected menu entry" }
+			KeyAction::CursorLeft  => { "move west, or move left in a menu" }
+			KeyAction::CursorDown  => { "move south, or move down in a menu" }
+			KeyAction::CursorUp    => { "move north, or move up in a menu" }
+			KeyAction::CursorRight => { "move east, or move right in a menu" }
+			KeyAction::MoveW       => { "move west" }
+			KeyAction::MoveS       => { "move south" }
+			KeyAction::MoveN       => { "move north" }
+			KeyAction::MoveE       => { "move east" }
+			KeyAction::MoveNW      => { "move northwest" }
+			KeyAction::MoveNE      => { "move northeast" }
+			KeyAction::MoveSW      => { "move southwest" }
+			KeyAction::MoveSE      => { "move southeast" }
+			KeyAction::Descend     => { "climb down a ladder" }
+			KeyAction::Ascend      => { "climb up a ladder" }
+			KeyAction::Inventory   => { "look through your inventory" }
+			KeyAction::Drop        => { "drop an item" }
+			KeyAction::PickUp      => { "pick up an item" }
+			KeyAction::Open        => { "open something nearby" }
+			KeyAction::Close       => { "close something nearby" }
+			KeyAction::Examine     => { "examine something nearby" }
+			KeyAction::Apply       => { "use an item" }
+			KeyAction::Lock        => { "lock something nearby" }
+			KeyAction::Unlock      => { "unlock something nearby" }
+			KeyAction::Interact    => { "interact with the closest thing" }
+			KeyAction::ActionMenu  => { "choose from everything you can do nearby" }
+			KeyAction::Connect     => { "connect your PLANQ to an access port" }
+			KeyAction::Disconnect  => { "disconnect your PLANQ from an access port" }
+			KeyAction::Wait        => { "wait a moment and catch your breath" }
+			KeyAction::PlanqCli    => { "type a command into your PLANQ" }
+			KeyAction::Stats       => { "show or hide your character summary" }
+			KeyAction::MsgChannel  => { "switch which messages are shown in the message log" }
+			KeyAction::ReachOverlay => { "show or hide how far you can reach" }
+			KeyAction::PanCamera   => { "look around the deck without moving" }
+			KeyAction::CenterCamera => { "stop looking around and bring the view back to yourself" }
+			KeyAction::ReloadDefns => { "reload the item definitions" }
+			KeyAction::DebugHud    => { "show or hide the timing display" }
+		}
+	}
+	/// Returns the direction that a movement action moves in, or None if it isn't one
+	pub fn direction(&self) -> Option<Direction> {
+		match self {
+			KeyAction::MoveW  | KeyAction::CursorLeft  => { Some(Direction::W) }
+			KeyAction::MoveS  | KeyAction::CursorDown  => { Some(Direction::S) }
+			KeyAction::MoveN  | KeyAction::CursorUp    => { Some(Direction::N) }
+			KeyAction::MoveE  | KeyAction::CursorRight => { Some(Direction::E) }
+			KeyAction::MoveNW => { Some(Direction::NW) }
+			KeyAction::MoveNE => { Some(Direction::NE) }
+			KeyAction::MoveSW => { Some(Direction::SW) }
+			KeyAction::MoveSE => { Some(Direction::SE) }
+			_ => { None }
+		}
+	}
+}
+/// Provides a readable name for a key, for use in messages to the player
+pub fn key_name(key: KeyCode) -> String {
+	match key {
+		KeyCode::Char(' ') => { "Space".to_string() }
+		KeyCode::Char(val) => { val.to_string() }
+		KeyCode::F(num)    => { format!("F{}", num) }
+		KeyCode::Esc       => { "Esc".to_string() }
+		KeyCode::Enter     => { "Enter".to_string() }
+		KeyCode::Left      => { "Left".to_string() }
+		KeyCode::Right     => { "Right".to_string() }
+		KeyCode::Up        => { "Up".to_string() }
+		KeyCode::Down      => { "Down".to_string() }
+		KeyCode::Tab       => { "Tab".to_string() }
+		other              => { format!("{:?}", other) }
+	}
+}
+/// Converts a key's readable name back into the key, ie when reading the keybindings config
+/// This is the reverse of key_name(), and accepts the names that it produces
+pub fn parse_key_name(name: &str) -> Option<KeyCode> {
+	match name {
+		"Space" => { return Some(KeyCode::Char(' ')); }
+		"Esc"   => { return Some(KeyCode::Esc); }
+		"Enter" => { return Some(KeyCode::Enter); }
+		"Left"  => { return Some(KeyCode::Left); }
+		"Right" => { return Some(KeyCode::Right); }
+		"Up"    => { return Some(KeyCode::Up); }
+		"Down"  => { return Some(KeyCode::Down); }
+		"Tab"   => { return Some(KeyCode::Tab); }
+		_ => { }
+	}
+	let mut chars = name.chars();
+	match (chars.next(), chars.next()) {
+		(Some(val), None) => { Some(KeyCode::Char(val)) }
+		(Some('F'), Some(_)) => { name[1..].parse::<u8>().ok().map(KeyCode::F) }
+		_ => { None }
+	}
+}
+
+// EOF
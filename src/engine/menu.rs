@@ -504,7 +504,8 @@ impl MenuHelperGameEvent {
 		match self.action {
 			ActionType::MoveTo(_)
 			| ActionType::Inventory
-			| ActionType::KillItem => {
+			| ActionType::KillItem
+			| ActionType::Wait => {
 				self.subject != Entity::PLACEHOLDER
 			},
 			ActionType::Examine
@@ -5,6 +5,13 @@
 use bevy::prelude::*;
 use ratatui::text::{Line, Span};
 use ratatui::style::{Style, Color, Modifier};
+use simplelog::{error, warn}; // Goes to the game's logfile, unlike the bevy::prelude versions
+
+//  ###: CONSTANTS
+/// The channels that the message log pane can be switched between, in the order that they're cycled through
+pub const MSG_PANE_CHANNELS: &[&str] = &["world", "combat", "actions"];
+/// The channel that receives any message sent to a channel that doesn't exist
+pub const MSG_CATCHALL: &str = "debug";
 
 //  ###: COMPLEX TYPES
 //   ##: MessageLog
@@ -24,26 +31,35 @@ impl MessageLog {
 		MessageLog{ logs: new_logs }
 	}
 	//  * TOOLS
-	/// Adds a new message to the given channel; if the channel does not exist it will be made
+	/// Adds a new message to the given channel
+	/// A message for a channel that the log wasn't made with is most likely a typo, so it's reported and the message
+	/// goes to the MSG_CATCHALL channel instead of being lost; the catch-all itself is made if needed
+	/// Returns false if the message had to be rerouted like that
 	/// # Arguments
 	/// * `msg_text` - The text of the message
 	/// * `msg_chan` - The msg channel's name, ie "world"
 	/// * `msg_prio` - Higher -> more important
 	/// * `msg_time` - As number of seconds since game epoch
-	pub fn add(&mut self, msg_text: &str, msg_chan: &str, msg_prio: i32, msg_time: i32) {
+	pub fn add(&mut self, msg_text: &str, msg_chan: &str, msg_prio: i32, msg_time: i32) -> bool {
 		// Check for an existing channel to add the new message to
 		for channel in &mut self.logs {
 			if channel.name == msg_chan {
 				// add the message to this channel
 				channel.add(Message::new(msg_time, msg_prio, msg_chan, msg_text));
-				return;
+				return true;
 			}
 		}
 		// if we arrived here, we didn't find a matching channel
-		// make a new channel and add the message to it
-		let mut new_channel = MessageChannel::new(msg_chan);
-		new_channel.add(Message::new(msg_time, msg_prio, msg_chan, msg_text));
+		if msg_chan != MSG_CATCHALL {
+			warn!("* message sent to unknown channel '{}', routing it to '{}': {}", msg_chan, MSG_CATCHALL, msg_text); // DEBUG: report unknown channel
+			self.add(msg_text, MSG_CATCHALL, msg_prio, msg_time);
+			return false;
+		}
+		// make the catch-all channel and add the message to it
+		let mut new_channel = MessageChannel::new(MSG_CATCHALL);
+		new_channel.add(Message::new(msg_time, msg_prio, MSG_CATCHALL, msg_text));
 		self.logs.push(new_channel);
+		true
 	}
 	/// Replaces the last message in the given channel with the new message; does nothing if channel does not exist
 	pub fn replace(&mut self, msg_text: &str, msg_chan: &str, msg_prio: i32, msg_time: i32) {
@@ -116,6 +132,15 @@ impl MessageLog {
 		}
 		false
 	}
+	/// Provides every message on the given channel that has at least the given priority, as ratatui Lines
+	pub fn get_filtered_lines(&self, req_channel: &str, min_priority: i32) -> Vec<Line> {
+		self.logs.iter()
+			.filter(|x| x.name == req_channel)
+			.flat_map(|x| x.contents.iter())
+			.filter(|x| x.priority >= min_priority)
+			.map(|x| x.clone().into())
+			.collect()
+	}
 	/// Retrieves a set of log messages from a specified channel as ratatui::Line
 	/// This means the text will be formatted for display in a ratatui::Paragraph!
 	/// If the given channel does not exist, an empty vector will be returned
@@ -152,6 +177,13 @@ impl MessageLog {
 		}
 		Vec::new()
 	}
+	/// Provides the messages on a specified channel from the given index onward, without copying them
+	/// If the given channel does not exist or doesn't have that many messages, an empty slice will be returned
+	pub fn get_messages_from(&self, req_channel: &str, start: usize) -> &[Message] {
+		self.logs.iter().find(|x| x.name == req_channel)
+			.and_then(|x| x.contents.get(start..))
+			.unwrap_or(&[])
+	}
 	/// Helper method for writing a message directly to the "world" channel, ie the main feedback message channel
 	pub fn tell_player(&mut self, msg_text: &str) {
 		self.add(msg_text, "world", 0, 0);
@@ -160,6 +192,25 @@ impl MessageLog {
 	pub fn tell_planq(&mut self, msg_text: &str) {
 		self.add(msg_text, "planq", 0, 0);
 	}
+	/// Helper method: adds a line to the "actions" channel, which keeps a terse record of everything the player did
+	pub fn log_action(&mut self, msg_text: &str) {
+		self.add(msg_text, "actions", 0, 0);
+	}
+	/// Helper method: adds a message to the "combat" channel, to keep the blow-by-blow out of the world log
+	/// Set `mirror` for the lines that the player shouldn't miss, ie a death, to also copy them to the world log
+	pub fn tell_combat(&mut self, msg_text: &str, mirror: bool) {
+		self.add(msg_text, "combat", 0, 0);
+		if mirror { self.tell_player(msg_text); }
+	}
+	/// Helper method: adds one of the player's own notes to the "journal" channel, stamped with the given turn
+	/// Nothing ever clears this channel, so the notes last for as long as the savegame does
+	pub fn add_note(&mut self, msg_text: &str, turn: i32) {
+		self.add(msg_text, "journal", 0, turn);
+	}
+	/// Provides all of the player's notes, oldest first
+	pub fn get_notes(&self) -> Vec<Message> {
+		self.get_log_as_messages("journal", 0)
+	}
 
 }
 /// Implements the Default trait for the reference type
@@ -218,141 +269,168 @@ impl Message {
 }
 impl From<Message> for Line<'_> {
 	fn from(input: Message) -> Self {
-		// SYNTAX
-		// enclose the text modifications inside double brackets; fg/bg take color names only
-		// "This is some [[fg:red,bg:white,mod:+italic]]red text[[end]]."
-		// (end)
 		// We can ignore the channel and priority fields because they're for organizational purposes anyway
 		// later it might be useful to add some kind of a channel prefix to the message, if so desired
 		// -  TODO: Format the timestamp into a suitable prefix
 		// -  TODO: Format the priority into a suitable prefix
 		// -  TODO: Format the channel into a suitable prefix
-		// Parse the text out into raw spans, separated by the inlined control chars
-		let mut blocks: Vec<String> = Vec::new(); // The set of substrings that begin with '[['
-		let mut line: Vec<Span> = Vec::new();
-		// Split the input line into sections that start with control chars
-		for chunk in input.text.split("[[") {
-			blocks.push(chunk.to_string());
-		}
-		// For each block of text, ie 'fg:red]]EXIT', 'end]]'
-		for block in blocks.iter() {
-			let mut style = Style::default();
-			if block.is_empty() { continue; } // The leading delimiters cause the split operation to insert empty strings
-			let spans = block.split("]]").map(String::from).collect::<Vec<String>>(); // Split each block into two, before/after the control chars
-			if spans.len() < 2 { line.push(Span::raw(spans[0].clone())); continue; }
-			let trim_chars: &[_] = &['[', ']']; // the split() is supposed to do this, but let's just make sure
-			let style_line: Vec<&str> = spans[0].trim_matches(trim_chars).split(',').collect(); // Split the control chars into ind. mods
-			// For each individual modification, figure out what type it is and apply it to the Style
-			// TODO: make use of the color/modification conversion tools in camera.rs (maybe export them to lib.rs?)
-			for token in style_line.iter() {
-				let keyval: Vec<&str> = token.split(':').collect();
-				match keyval[0] {
-					"fg" => {
-						match keyval[1] {
-							"black"      => { style = style.fg(Color::Black); }
-							"red"        => { style = style.fg(Color::Red); }
-							"green"      => { style = style.fg(Color::Green); }
-							"yellow"     => { style = style.fg(Color::Yellow); }
-							"blue"       => { style = style.fg(Color::Blue); }
-							"pink"
-							| "magenta"
-							| "purple"   => { style = style.fg(Color::Magenta); }
-							"cyan"       => { style = style.fg(Color::Cyan); }
-							"white"      => { style = style.fg(Color::Gray); }
-							"ltblack"
-							| "grey"
-							| "gray"     => { style = style.fg(Color::DarkGray); }
-							"ltred"      => { style = style.fg(Color::LightRed); }
-							"ltgreen"    => { style = style.fg(Color::LightGreen); }
-							"ltyellow"   => { style = style.fg(Color::LightYellow); }
-							"ltblue"     => { style = style.fg(Color::LightBlue); }
-							"ltpink"
-							| "ltmagenta"
-							| "ltpurple" => { style = style.fg(Color::LightMagenta); }
-							"ltcyan"     => { style = style.fg(Color::LightCyan); }
-							"ltwhite"    => { style = style.fg(Color::White); }
-							"default"
-							| "reset"
-							| "end"      => { style = style.fg(Color::Reset); }
-							_ => { }
-						}
-					}
-					"bg" => {
-						match keyval[1] {
-							"black"      => { style = style.bg(Color::Black); }
-							"red"        => { style = style.bg(Color::Red); }
-							"green"      => { style = style.bg(Color::Green); }
-							"yellow"     => { style = style.bg(Color::Yellow); }
-							"blue"       => { style = style.bg(Color::Blue); }
-							"pink"
-							| "magenta"
-							| "purple"   => { style = style.bg(Color::Magenta); }
-							"cyan"       => { style = style.bg(Color::Cyan); }
-							"white"      => { style = style.bg(Color::Gray); }
-							"ltblack"
-							| "grey"
-							| "gray"     => { style = style.bg(Color::DarkGray); }
-							"ltred"      => { style = style.bg(Color::LightRed); }
-							"ltgreen"    => { style = style.bg(Color::LightGreen); }
-							"ltyellow"   => { style = style.bg(Color::LightYellow); }
-							"ltblue"     => { style = style.bg(Color::LightBlue); }
-							"ltpink"
-							| "ltmagenta"
-							| "ltpurple" => { style = style.bg(Color::LightMagenta); }
-							"ltcyan"     => { style = style.bg(Color::LightCyan); }
-							"ltwhite"    => { style = style.bg(Color::White); }
-							"default"
-							| "reset"
-							| "end"      => { style = style.bg(Color::Reset); }
-							_ => { }
-						}
-					}
-					"mod" => {
-						// need to do some special splitting and parsing here
-						let mut pos_mods = Modifier::empty();
-						let mut neg_mods = Modifier::empty();
-						let mods: Vec<&str> = keyval[1].split('/').collect();
-						for element in mods.iter() {
-							let mut token = element.to_string();
-							let polarity = token.remove(0); // get the first char off the element
-							let bit_mod = match &*token { // Arranged in order of descending support; blink/flash and strikeout esp. are rare
-								"bright"
-								| "bold"    => { Modifier::BOLD }
-								"dark"
-								| "dim"     => { Modifier::DIM }
-								"reverse"   => { Modifier::REVERSED }
-								"underline" => { Modifier::UNDERLINED }
-								"italic"    => { Modifier::ITALIC }
-								"hidden"    => { Modifier::HIDDEN }
-								"strikeout" => { Modifier::CROSSED_OUT }
-								"blink"     => { Modifier::SLOW_BLINK }
-								"flash"     => { Modifier::RAPID_BLINK }
-								_ => { Modifier::empty() }
-							};
-							if polarity == '+' {
-								pos_mods |= bit_mod;
-							} else if polarity == '-' {
-								neg_mods |= bit_mod;
-							} else {
-								error!("* ERR: color parse failure, unsupported mod: {}{}", polarity, element);
-							}
-							// Apply the bitfield modifiers, if any
-						}
-						if pos_mods != Modifier::empty() {
-							style = style.add_modifier(pos_mods);
-						}
-						if neg_mods != Modifier::empty() { style = style.remove_modifier(neg_mods); }
-					}
-					"default" | "reset" | "end" => {
-						style = Style::reset();
-					}
-					_ => { }
+		parse_markup(&input.text)
+	}
+}
+
+//  ###: SIMPLE TYPES AND HELPERS
+/// Converts a string with inline style markup into a ratatui::Line; this is used for every message channel,
+/// including the PLANQ's stdout, so that all of the game's text follows the same rules
+/// SYNTAX
+/// Enclose the text modifications inside double brackets; fg/bg take color names only
+///   "This is some [[fg:red,bg:white,mod:+italic]]red text[[end]]."
+/// Styles accumulate until they are changed or cleared, so that "[[fg:red]]a[[mod:+bold]]b" renders b as red and bold;
+/// use [[end]] to go back to the default style
+/// Malformed markup, such as an unterminated tag, is kept as plain text rather than being dropped
+pub fn parse_markup(text: &str) -> Line<'static> {
+	let mut line: Vec<Span> = Vec::new();
+	let mut style = Style::default();
+	// Split the input line into sections that start with control chars, ie 'fg:red]]EXIT', 'end]]'
+	let mut blocks = text.split("[[");
+	// Anything before the first tag is plain text
+	if let Some(first) = blocks.next() {
+		if !first.is_empty() { line.push(Span::raw(first.to_string())); }
+	}
+	for block in blocks {
+		// Split each block into two, before/after the control chars
+		let (tags, body) = if let Some(value) = block.split_once("]]") { value } else {
+			// The tag was never closed, so put the brackets back and show it as-is
+			line.push(Span::styled(format!("[[{}", block), style));
+			continue;
+		};
+		// For each individual modification, figure out what type it is and apply it to the Style
+		for token in tags.split(',') {
+			let (key, value) = token.split_once(':').unwrap_or((token, ""));
+			match key.trim() {
+				"fg" => {
+					if let Some(color) = markup_color(value) { style = style.fg(color); }
+					else { warn!("* unrecognized color in markup: {}", token); }
 				}
+				"bg" => {
+					if let Some(color) = markup_color(value) { style = style.bg(color); }
+					else { warn!("* unrecognized color in markup: {}", token); }
+				}
+				"mod" => {
+					let (pos_mods, neg_mods) = markup_mods(value);
+					style = style.add_modifier(pos_mods).remove_modifier(neg_mods);
+				}
+				"default" | "reset" | "end" => {
+					style = Style::reset();
+				}
+				_ => { warn!("* unrecognized markup token: {}", token); }
 			}
-			let new_span = Span::styled(spans[1].clone(), style);
-			line.push(new_span);
 		}
-		Line::from(line)
+		if !body.is_empty() { line.push(Span::styled(body.to_string(), style)); }
+	}
+	Line::from(line)
+}
+/// Converts a color name from the message markup into its ratatui equivalent
+fn markup_color(name: &str) -> Option<Color> {
+	let color = match name.trim() {
+		"black"      => { Color::Black }
+		"red"        => { Color::Red }
+		"green"      => { Color::Green }
+		"yellow"     => { Color::Yellow }
+		"blue"       => { Color::Blue }
+		"pink"
+		| "magenta"
+		| "purple"   => { Color::Magenta }
+		"cyan"       => { Color::Cyan }
+		"white"      => { Color::Gray }
+		"ltblack"
+		| "grey"
+		| "gray"     => { Color::DarkGray }
+		"ltred"      => { Color::LightRed }
+		"ltgreen"    => { Color::LightGreen }
+		"ltyellow"   => { Color::LightYellow }
+		"ltblue"     => { Color::LightBlue }
+		"ltpink"
+		| "ltmagenta"
+		| "ltpurple" => { Color::LightMagenta }
+		"ltcyan"     => { Color::LightCyan }
+		"ltwhite"    => { Color::White }
+		"default"
+		| "reset"
+		| "end"      => { Color::Reset }
+		_ => { return None; }
+	};
+	Some(color)
+}
+/// Parses a set of text modifiers from the message markup, ie "+bold/-italic", into the sets of modifiers to add
+/// and to remove, respectively
+fn markup_mods(input: &str) -> (Modifier, Modifier) {
+	let mut pos_mods = Modifier::empty();
+	let mut neg_mods = Modifier::empty();
+	for element in input.split('/') {
+		let (polarity, token) = if let Some(token) = element.strip_prefix('+') { ('+', token) }
+		else if let Some(token) = element.strip_prefix('-') { ('-', token) }
+		else {
+			error!("* ERR: markup parse failure, unsupported mod: {}", element);
+			continue;
+		};
+		let bit_mod = match token { // Arranged in order of descending support; blink/flash and strikeout esp. are rare
+			"bright"
+			| "bold"    => { Modifier::BOLD }
+			"dark"
+			| "dim"     => { Modifier::DIM }
+			"reverse"   => { Modifier::REVERSED }
+			"underline" => { Modifier::UNDERLINED }
+			"italic"    => { Modifier::ITALIC }
+			"hidden"    => { Modifier::HIDDEN }
+			"strikeout" => { Modifier::CROSSED_OUT }
+			"blink"     => { Modifier::SLOW_BLINK }
+			"flash"     => { Modifier::RAPID_BLINK }
+			_ => { Modifier::empty() }
+		};
+		if polarity == '+' { pos_mods |= bit_mod; } else { neg_mods |= bit_mod; }
+	}
+	(pos_mods, neg_mods)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn messages_to_an_unknown_channel_go_to_the_catchall() {
+		let mut msglog = MessageLog::new(vec!["world".to_string(), MSG_CATCHALL.to_string()]);
+		assert!(msglog.add("hello", "world", 0, 0));
+		assert!(!msglog.add("hello there", "wrold", 0, 0));
+		assert_eq!(msglog.get_log_as_messages("world", 0).len(), 1);
+		assert_eq!(msglog.get_log_as_messages(MSG_CATCHALL, 0).last().map(|x| x.text.as_str()), Some("hello there"));
+		// Without a catch-all channel to begin with, one is made for the message
+		let mut msglog = MessageLog::new(vec!["world".to_string()]);
+		assert!(!msglog.add("still here", "nowhere", 0, 0));
+		assert_eq!(msglog.get_log_as_messages(MSG_CATCHALL, 0).len(), 1);
+		// Whereas a message sent straight to the catch-all was never rerouted
+		let mut msglog = MessageLog::new(vec!["world".to_string()]);
+		assert!(msglog.add("debug output", MSG_CATCHALL, 0, 0));
+	}
+	#[test]
+	fn markup_styles_stack_until_they_are_ended() {
+		let line = parse_markup("[[fg:red]]a[[mod:+bold]]b[[end]]c");
+		assert_eq!(line.spans.len(), 3);
+		assert_eq!(line.spans[0].style.fg, Some(Color::Red));
+		assert_eq!(line.spans[1].style.fg, Some(Color::Red));
+		assert!(line.spans[1].style.add_modifier.contains(Modifier::BOLD));
+		assert_eq!(line.spans[2].style, Style::reset());
+	}
+	#[test]
+	fn malformed_markup_degrades_to_plain_text() {
+		let plain = |text: &str| parse_markup(text).spans.iter().map(|x| x.content.to_string()).collect::<String>();
+		// An unterminated tag is shown as it was written
+		assert_eq!(plain("Power at [[fg:red 40%"), "Power at [[fg:red 40%");
+		assert_eq!(plain("[[fg:green]]ok [[mod:+bold"), "ok [[mod:+bold");
+		// A stray closer, or a tag that can't be used, doesn't cost any of the text around it
+		assert_eq!(plain("50%]] done"), "50%]] done");
+		assert_eq!(plain("[[fg:plaid]]still [[wobble]]here"), "still here");
+		assert_eq!(parse_markup("[[fg:plaid]]still here").spans[0].style.fg, None);
+		assert!(parse_markup("").spans.is_empty());
 	}
 }
 
@@ -0,0 +1,122 @@
+// engine/mirror.rs
+// Copies the key parts of the game state out as plain text, for screen readers and other external tools
+
+//  ###: EXTERNAL LIBRARIES
+use std::fs::File;
+use std::io::Write;
+use bevy::prelude::{
+	Entity,
+	With,
+	World,
+};
+use simplelog::*;
+
+//  ###: INTERNAL LIBRARIES
+use crate::components::{
+	Body,
+	Description,
+	Player,
+	Position,
+	Viewshed,
+};
+use crate::engine::messagelog::{
+	MessageLog,
+	MSG_PANE_CHANNELS,
+	parse_markup,
+};
+use crate::worldmap::WorldModel;
+
+//  ###: COMPLEX TYPES
+//   ##: TextMirror
+/// Writes out a plain-text line whenever the player's room, the things in their view, or the message log changes
+/// This reads the same Bevy data that the renderers use, so it never disagrees with what's on the screen
+pub struct TextMirror {
+	output: Box<dyn Write>,
+	last_room: String,
+	last_visible: Vec<String>,
+	msgs_seen: Vec<usize>, // How many messages have been written out so far, for each of the MSG_PANE_CHANNELS
+}
+impl TextMirror {
+	pub fn new(new_output: Box<dyn Write>) -> TextMirror {
+		TextMirror {
+			output: new_output,
+			last_room: String::new(),
+			last_visible: Vec::new(),
+			msgs_seen: vec![0; MSG_PANE_CHANNELS.len()],
+		}
+	}
+	/// Checks the game state for any changes since the last update and writes them out
+	pub fn update(&mut self, world: &mut World) {
+		let mut lines = Vec::new();
+		let mut p_query = world.query_filtered::<(Entity, &Body, &Description, Option<&Viewshed>), With<Player>>();
+		if let Ok((p_enty, p_body, p_desc, p_view)) = p_query.get_single(world) {
+			if p_desc.locn != self.last_room {
+				self.last_room = p_desc.locn.clone();
+				lines.push(format!("Location: {}", self.last_room));
+			}
+			// Make a list of everything in the player's view, not counting the player
+			let mut visible = Vec::new();
+			if let (Some(viewshed), Some(model)) = (p_view, world.get_resource::<WorldModel>()) {
+				for point in viewshed.visible_points.iter() {
+					for enty in model.get_contents_at(Position::new(point.x, point.y, p_body.ref_posn.z)) {
+						if enty == p_enty { continue; }
+						if let Some(desc) = world.get::<Description>(enty) {
+							visible.push(desc.get_name());
+						}
+					}
+				}
+			}
+			visible.sort();
+			visible.dedup();
+			if visible != self.last_visible {
+				if visible.is_empty() {
+					lines.push("Visible: nothing".to_string());
+				} else {
+					lines.push(format!("Visible: {}", visible.join(", ")));
+				}
+				self.last_visible = visible;
+			}
+		}
+		if let Some(msglog) = world.get_resource::<MessageLog>() {
+			for (index, channel) in MSG_PANE_CHANNELS.iter().enumerate() {
+				let total = msglog.channel_len(channel);
+				// A shorter log means that a new game was started or loaded, so start over from the top
+				if total < self.msgs_seen[index] { self.msgs_seen[index] = 0; }
+				for message in msglog.get_messages_from(channel, self.msgs_seen[index]).iter() {
+					lines.push(format!("Message ({}): {}", channel, plain_text(&message.text)));
+				}
+				self.msgs_seen[index] = total;
+			}
+		}
+		if lines.is_empty() { return; }
+		let result = lines.iter().try_for_each(|x| writeln!(self.output, "{}", x)).and_then(|_| self.output.flush());
+		if let Err(e) = result {
+			warn!("* could not write to the text mirror: {}", e); // DEBUG: report text mirror failure
+		}
+	}
+}
+
+//  ###: SIMPLE TYPES AND HELPERS
+/// Strips the style markup out of a message, leaving only the text that would be shown
+pub fn plain_text(text: &str) -> String {
+	parse_markup(text).spans.iter().map(|x| x.content.as_ref()).collect()
+}
+/// Reads the text mirror option from the command line, ie `spacegame --text-mirror game.txt`
+/// The mirror needs a file of its own, since the terminal is taken up by the game; if no file is given, or the file
+/// can't be created, then there's no mirror at all
+pub fn text_mirror_from_args(args: &[String]) -> Option<TextMirror> {
+	let position = args.iter().position(|x| x == "--text-mirror")?;
+	let filename = if let Some(value) = args.get(position + 1).filter(|x| !x.starts_with("--")) { value } else {
+		warn!("* --text-mirror needs the name of a file to write to, the mirror is disabled"); // DEBUG: report missing mirror file
+		return None;
+	};
+	match File::create(filename) {
+		Ok(file) => { Some(TextMirror::new(Box::new(file))) }
+		Err(e) => {
+			warn!("* --text-mirror could not create {}, the mirror is disabled: {}", filename, e); // DEBUG: report bad mirror file
+			None
+		}
+	}
+}
+
+// EOF
@@ -25,25 +25,36 @@ use ratatui::{
 use strum::IntoEnumIterator;
 
 // ###: INTERNAL LIBS
+pub mod config;
 pub mod event;
 pub mod handler;
+pub mod keybinds;
 pub mod menu;
 pub mod messagelog;
+pub mod mirror;
+pub mod script;
 pub mod tui;
 pub mod viewport;
 use crate::{
 	artisan::*,
+	artisan::creature::CreatureBuilder,
 	camera::*,
 	components::*,
 	engine::{
+		config::{Config, GameConfig, SaveMeta},
 		event::*,
+		keybinds::{KeyAction, KeyBindings},
 		menu::*,
 		messagelog::*,
+		mirror::TextMirror,
 		tui::*,
 		viewport::Viewport,
 	},
 	mason::{
 		get_world_builder,
+		NpcRequest,
+		logical_map::PlacementMode,
+		procgen_world::PROCGEN_SOURCE,
 		rexpaint_loader::load_rex_pgraph,
 		WorldBuilder,
 	},
@@ -64,6 +75,7 @@ pub struct GameEngine<'a> {
 	pub bevy:           App, // bevy::app::App, contains all of the ECS and related things
 	pub mason:          Box<dyn WorldBuilder>,
 	pub artisan:        ItemBuilder,
+	pub creatures:      CreatureBuilder,
 	pub visible_menu:   MenuType,
 	pub menu_main:      MenuState<Cow<'static, str>>,
 	pub menu_context:   MenuState<GameEvent>,
@@ -75,17 +87,46 @@ pub struct GameEngine<'a> {
 	pub savegame_filename: String,
 	pub term_dims:      Rect,
 	pub planq_stdin:    PlanqInput<'a>,
+	pub debug_mode:     bool, // If true, PLANQ commands may report things that the player hasn't discovered yet
+	pub placement:      PlacementMode, // Use PlacementMode::Deterministic to get the same item layout every time
+	pub keys:           KeyBindings,
+	pub generating:     bool, // If true, a new game will be built on the next tick, after the loading screen is drawn
+	pub frame_times:    FrameTimes, // DEBUG: timing info for the debug HUD
+	pub show_stats:     bool, // If true, the character summary is drawn over the camera view
+	pub game_config:    GameConfig, // The settings that the next new game will be started with
+	pub config:         Config, // The player's preferences from the Options menu
+	pub msg_channel:    usize, // Indexes into MSG_PANE_CHANNELS to pick which channel the message log pane shows
+	pub text_mirror:    Option<TextMirror>, // If set, the game state is also written out as plain text
+	pub playtime:       Duration, // How long the current game has been played for, including any earlier sessions
+	pub nav_target:     Option<Entity>, // If set, the compass points the way to this Entity, wherever it goes
+	pub rng_seed:       Option<u64>, // If set, each new game's RNG starts from this seed, so that it plays out the same way
+	pub keep_settings:  bool, // If false, changes to the settings are never written to disk, ie for the script runner
 }
 impl GameEngine<'_> {
 	/// Constructs a new instance of [`GameEngine`].
 	pub fn new(max_area: Rect) -> Self {
+		GameEngine::with_settings(max_area, Config::load(), GameConfig::load())
+	}
+	/// Constructs a GameEngine that doesn't read or write any of the player's settings, and whose games always play
+	/// out the same way: the ship is generated from the seed, and the RNG starts from the same seed every time
+	pub fn new_headless(max_area: Rect, seed: u64) -> Self {
+		let game_config = GameConfig { ship_file: format!("{}:{}", PROCGEN_SOURCE, seed), ..GameConfig::default() };
+		let mut new_eng = GameEngine::with_settings(max_area, Config::default(), game_config);
+		new_eng.placement = PlacementMode::Deterministic;
+		new_eng.rng_seed = Some(seed);
+		new_eng.keep_settings = false;
+		new_eng
+	}
+	/// Constructs a GameEngine with the given settings
+	fn with_settings(max_area: Rect, config: Config, game_config: GameConfig) -> Self {
 		let mut new_eng = GameEngine {
 			running: false,
 			standby: true,
 			mode: EngineMode::Standby,
 			bevy: App::new(),
-			mason: get_world_builder(),
+			mason: get_world_builder(&game_config.ship_file),
 			artisan: ItemBuilder::new(),
+			creatures: CreatureBuilder::new(),
 			// HINT: These menu items are handled via a match case in GameEngine::tick()
 			visible_menu: MenuType::None,
 			menu_main: MenuState::new(vec![]),
@@ -98,7 +139,23 @@ impl GameEngine<'_> {
 			savegame_filename: "demo_game".to_string(),
 			term_dims: max_area,
 			planq_stdin: PlanqInput::new(),
+			debug_mode: cfg!(debug_assertions),
+			placement: PlacementMode::default(),
+			keys: KeyBindings::default().with_names(&config.keys),
+			generating: false,
+			frame_times: FrameTimes::default(),
+			show_stats: false,
+			game_config,
+			config,
+			msg_channel: 0,
+			text_mirror: None,
+			playtime: Duration::ZERO,
+			nav_target: None,
+			rng_seed: None,
+			keep_settings: true,
 		};
+		new_eng.ui_grid.msg_height = new_eng.config.msg_log_height;
+		new_eng.ui_grid.sidebar_width = new_eng.config.sidebar_width;
 		new_eng.planq_stdin.input.set_cursor_line_style(Style::default().fg(Color::Yellow).bg(Color::Black));
 		new_eng.bevy.add_plugins(MinimalPlugins).add_plugins(SavePlugins);
 		new_eng
@@ -124,13 +181,42 @@ impl GameEngine<'_> {
 	 */
 		// This is where I'd pull any mode changes that might have happened during the last Bevy update and apply them
 		//if settings.mode_changed { ... }
+		// Keep track of how long it's actually been between ticks, for the debug HUD
+		let now = Instant::now();
+		if let Some(last_tick) = self.frame_times.last_tick {
+			self.frame_times.tick_interval = now.duration_since(last_tick);
+		}
+		self.frame_times.last_tick = Some(now);
+		// If a new game was requested, the loading screen has been drawn by now, so go ahead and build it
+		if self.generating {
+			self.generating = false;
+			self.new_game();
+			return;
+		}
 		// If there are any menu events, handle them
 		for event in self.menu_main.drain_events() {
 			// NOTE: if the user selects a submenu heading as their choice, *nothing* will be generated; the menu will just close
 			//       not sure yet if there's a way to trap that outcome
 			match event {
 				MenuEvent::Selected(item) => match item.as_ref() {
-					"main.new_game"  => { self.new_game(); }
+					"main.new_game"  => { self.start_new_game(); }
+					"main.config.ship"       => { self.game_config.next_ship(); self.set_menu(MenuType::Main, self.menu_posn); }
+					"main.config.density"    => { self.game_config.next_density(); self.set_menu(MenuType::Main, self.menu_posn); }
+					"main.config.permadeath" => {
+						self.game_config.permadeath = !self.game_config.permadeath;
+						self.set_menu(MenuType::Main, self.menu_posn);
+					}
+					"main.config.fast_boot" | "main.options.fast_boot" => {
+						self.game_config.fast_boot = !self.game_config.fast_boot;
+						if let Some(mut config) = self.bevy.world.get_resource_mut::<GameConfig>() {
+							config.fast_boot = self.game_config.fast_boot; // Also applies to the game in progress
+						}
+						if self.keep_settings { self.game_config.save(); }
+						self.set_menu(MenuType::Main, self.menu_posn);
+					}
+					"main.options.msg_priority" => { self.config.next_msg_priority(); self.apply_config(); }
+					"main.options.tick_rate"    => { self.config.next_tick_rate(); self.apply_config(); }
+					"main.options.palette"      => { self.config.next_palette(); self.apply_config(); }
 					"main.load_game" => { self.load_game(&self.savegame_filename.clone()); }
 					"main.save_game" => { self.save_game(&self.savegame_filename.clone()); }
 					"main.abandon_game" => {
@@ -152,10 +238,19 @@ impl GameEngine<'_> {
 			match events {
 				MenuEvent::Selected(event) => {
 					trace!("* tick(): menu event: {:?}", event); // DEBUG: announce the context event that got matched
+					// A MoveItem with no object is the "Take all" entry from the GET menu
+					if let (GameEventType::PlayerAction(ActionType::MoveItem), Some(context)) = (event.etype, event.context) {
+						if context.object == Entity::PLACEHOLDER {
+							self.take_all_items(context.subject);
+							continue;
+						}
+					}
 					if event.is_valid() {
 						if let Some(event_handler) = &mut self.bevy.world.get_resource_mut::<Events<GameEvent>>() {
 							event_handler.send(event);
 						}
+					} else {
+						warn!("* Dropped invalid event from menu: {:?}", event); // DEBUG: report the dropped event
 					}
 					// WARN: In theory this should be the only GameEventType that comes through here, no guarantees though!
 					if let GameEventType::PlayerAction(action) = event.etype {
@@ -187,7 +282,19 @@ impl GameEngine<'_> {
 			}
 			EngineMode::Running => {
 				/* the main running mode of the game */
+				let update_start = Instant::now();
 				self.bevy.update();
+				self.frame_times.update_time = update_start.elapsed();
+				self.playtime += self.frame_times.tick_interval;
+				if let Some(mirror) = self.text_mirror.as_mut() {
+					mirror.update(&mut self.bevy.world);
+				}
+				self.update_nav_target();
+				match self.bevy.world.get_resource::<GameOutcome>() {
+					Some(GameOutcome::Won)  => { self.set_mode(EngineMode::GoodEnd); }
+					Some(GameOutcome::Lost) => { self.set_mode(EngineMode::BadEnd); }
+					_ => { }
+				}
 			}
 			EngineMode::Paused  => {
 				/* halts the execution/processing of the game state vs Running */
@@ -205,6 +312,9 @@ impl GameEngine<'_> {
 		// If the layout is dirty, recalculate it
 		if self.layout_changed { self.solve_layout(frame.size()); }
 		let default_block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White).bg(Color::Black));
+		let render_start = Instant::now();
+		// If a new game is about to be built, show the loading screen instead of whatever was there before
+		if self.generating { self.render_loading_screen(frame, "Generating ship..."); return; }
 		// If the engine is in standby mode, defer immediately
 		if self.standby { self.render_main_menu(frame); return; }
 		// Try to get the player's position out of Bevy
@@ -214,13 +324,19 @@ impl GameEngine<'_> {
 			if self.visible_menu == MenuType::Context {
 				if let Some(target) = self.menu_context.target {
 					if target != Position::INVALID && p_posn.is_valid() {
-						view.reticle = target.to_camera_coords(self.ui_grid.camera_main, p_posn);
+						view.reticle = view.to_screen_coords(target);
 					}
 				}
 			} else if view.reticle != Position::INVALID {
 				view.reticle = Position::INVALID;
 			}
-			frame.render_widget(Viewport::new(&view).block(default_block), self.ui_grid.camera_main);
+			// Make it obvious that the player isn't going anywhere while the camera's been panned away from them
+			let camera_block = if view.is_panning() {
+				default_block.title(format!("[ LOOKING AROUND - {} to return ]", self.keys.key_names_for(KeyAction::CenterCamera)))
+			} else {
+				default_block
+			};
+			frame.render_widget(Viewport::new(&view).block(camera_block), self.ui_grid.camera_main);
 		} else {
 			frame.render_widget(Block::default().title("[no CameraView initialized]"), self.ui_grid.camera_main);
 		}
@@ -236,6 +352,8 @@ impl GameEngine<'_> {
 		self.render_planq(frame);
 		// Always render the message log
 		self.render_message_log(frame);
+		// Show the character summary on top of everything else if it was asked for
+		if self.show_stats { self.render_stats_screen(frame); }
 		// Display the fancy "PAUSED" banner if the game is paused
 		if self.mode == EngineMode::Paused {
 			if let Ok(xpfile) = &XpFile::from_resource("../resources/big_pause.xp") {
@@ -245,13 +363,113 @@ impl GameEngine<'_> {
 				frame.render_widget(Clear, banner_area);
 				frame.render_widget(banner_img, banner_area);
 			}
-		} else if self.mode == EngineMode::GoodEnd {
-			info!("*************************");
-			info!("*** Victory detected! ***");
-			info!("*************************");
-			self.quit();
+		} else if self.mode == EngineMode::GoodEnd || self.mode == EngineMode::BadEnd {
+			self.render_end_screen(frame);
+		}
+		// Point the way to whatever the player last asked the PLANQ to find
+		if self.nav_target.is_some() { self.render_compass(frame); }
+		// DEBUG: Show the timing overlay if it was requested; the render time shown is from the previous frame
+		if self.frame_times.visible { self.render_debug_hud(frame); }
+		self.frame_times.render_time = render_start.elapsed();
+	}
+	/// Renders the end-of-game screen, with a summary of how the game went
+	pub fn render_end_screen<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let title = if self.mode == EngineMode::GoodEnd { "VICTORY" } else { "GAME OVER" };
+		let mut lines = self.run_summary();
+		lines.push(" ".to_string());
+		lines.push("Press any key to quit".to_string());
+		let camera = self.ui_grid.camera_main;
+		let width = (lines.iter().map(|x| x.len()).max().unwrap_or(0) as u16 + 4).min(camera.width);
+		let height = (lines.len() as u16 + 2).min(camera.height);
+		let area = Rect::new(camera.x + (camera.width - width) / 2, camera.y + (camera.height - height) / 2, width, height);
+		let text: Vec<Line> = lines.into_iter().map(Line::from).collect();
+		frame.render_widget(Clear, area);
+		frame.render_widget(Paragraph::new(text).block(self.default_block.clone().title(title)), area);
+	}
+	/// Renders the character summary as an overlay in the middle of the camera view
+	pub fn render_stats_screen<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let lines = self.player_summary();
+		let camera = self.ui_grid.camera_main;
+		let width = (lines.iter().map(|x| x.len()).max().unwrap_or(0) as u16 + 4).min(camera.width);
+		let height = (lines.len() as u16 + 2).min(camera.height);
+		let area = Rect::new(camera.x + (camera.width - width) / 2, camera.y + (camera.height - height) / 2, width, height);
+		let text: Vec<Line> = lines.into_iter().map(Line::from).collect();
+		frame.render_widget(Clear, area);
+		frame.render_widget(Paragraph::new(text).block(self.default_block.clone().title("CHARACTER")), area);
+	}
+	/// Renders a small compass in the corner of the camera view that points toward the nav_target
+	pub fn render_compass<B: Backend>(&self, frame: &mut Frame<'_, B>) {
+		use crate::components::Direction; // Not the ratatui layout Direction
+		let p_posn = *self.bevy.world.get_resource::<Position>().unwrap_or(&Position::INVALID);
+		let (name, target) = if let Some(value) = self.nav_target_info() { value } else { return; };
+		if !p_posn.is_valid() { return; }
+		let bearing = p_posn.bearing_to(&target);
+		let arrow = match bearing {
+			Direction::N  => { "↑" }
+			Direction::NE => { "↗" }
+			Direction::E  => { "→" }
+			Direction::SE => { "↘" }
+			Direction::S  => { "↓" }
+			Direction::SW => { "↙" }
+			Direction::W  => { "←" }
+			Direction::NW => { "↖" }
+			Direction::UP   => { "⇑" }
+			Direction::DOWN => { "⇓" }
+			Direction::X    => { "·" }
+		};
+		let line = if bearing == Direction::UP || bearing == Direction::DOWN {
+			format!("{} {}, deck {}", arrow, bearing, target.z)
+		} else {
+			let (x_diff, y_diff, _) = p_posn.difference(&target);
+			format!("{} {}, {}m", arrow, bearing, x_diff.abs().max(y_diff.abs()))
+		};
+		let width = (line.chars().count().max(name.chars().count()) as u16 + 4).min(self.ui_grid.camera_main.width);
+		let camera = self.ui_grid.camera_main;
+		if camera.width < width + 2 || camera.height < 5 { return; }
+		let area = Rect::new(camera.x + 1, camera.y + 1, width, 3);
+		frame.render_widget(Clear, area);
+		frame.render_widget(Paragraph::new(line).block(Block::default().borders(Borders::ALL).title(name)), area);
+	}
+	/// Provides the name and current Position of the nav_target, or None if it's unset or no longer has a Body
+	pub fn nav_target_info(&self) -> Option<(String, Position)> {
+		let target = self.nav_target?;
+		let t_posn = self.bevy.world.get::<Body>(target)?.ref_posn;
+		let name = self.bevy.world.get::<Description>(target).map(|x| x.name.clone()).unwrap_or_else(|| NAMELESS.to_string());
+		Some((name, t_posn))
+	}
+	/// Drops the nav_target once the player is standing next to it, since they won't need directions any more, or if
+	/// it's gone from the game entirely
+	pub fn update_nav_target(&mut self) {
+		if self.nav_target.is_none() { return; }
+		let p_posn = *self.bevy.world.get_resource::<Position>().unwrap_or(&Position::INVALID);
+		match self.nav_target_info() {
+			Some((_, target)) => {
+				if p_posn.is_valid() && (p_posn == target || p_posn.is_adjacent_to(&target)) {
+					self.nav_target = None;
+				}
+			}
+			None => { self.nav_target = None; }
 		}
 	}
+	/// DEBUG: Renders a small overlay in the corner of the camera view with the engine's timing info
+	pub fn render_debug_hud<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let lines = self.frame_times.describe();
+		let width = lines.iter().map(|x| x.len()).max().unwrap_or(0) as u16 + 2;
+		let camera = self.ui_grid.camera_main;
+		if camera.width < width + 1 || camera.height < lines.len() as u16 + 2 { return; }
+		let area = Rect::new(camera.x + camera.width - width - 1, camera.y + 1, width, lines.len() as u16 + 2);
+		let text: Vec<Line> = lines.into_iter().map(Line::from).collect();
+		frame.render_widget(Clear, area);
+		frame.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("DEBUG")), area);
+	}
+	/// Renders a full-screen status message, for use while the game is busy with something slow like worldgen
+	pub fn render_loading_screen<B: Backend>(&mut self, frame: &mut Frame<'_, B>, status: &str) {
+		let area = frame.size();
+		frame.render_widget(Clear, area);
+		frame.render_widget(self.default_block.clone(), area);
+		let msg_area = Rect::new(area.x, area.y + area.height / 2, area.width, 1);
+		frame.render_widget(Paragraph::new(status.to_string()).alignment(Alignment::Center), msg_area);
+	}
 	/// Renders the main menu, using the main menu object
 	pub fn render_main_menu<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
 		//debug!("* rendering main menu"); // DEBUG: announce main menu render event
@@ -296,6 +514,10 @@ impl GameEngine<'_> {
 					planq.render_cli(frame, self.ui_grid.planq_stdin, &mut self.planq_stdin);
 				}
 			}
+		} else {
+			// The PlanqData hasn't been created yet, ie a new game is still being set up
+			frame.render_widget(Block::default().title("[no PlanqData initialized]"), self.ui_grid.planq_status);
+			return;
 		}
 		// Always render the status widgets: need to provide battery power, ship time, PLANQ status
 		// WARN: this MUST be after we are done with the planq object above due to borrow checking
@@ -309,7 +531,8 @@ impl GameEngine<'_> {
 		let msglog_ref = self.bevy.world.get_resource::<MessageLog>();
 		let msglog = msglog_ref.unwrap_or_default(); // get a handle on the msglog service
 		if msglog_ref.is_some() {
-			let worldmsg = msglog.get_log_as_lines("world", 0); // get the full backlog
+			let channel = MSG_PANE_CHANNELS[self.msg_channel % MSG_PANE_CHANNELS.len()];
+			let worldmsg = msglog.get_filtered_lines(channel, self.config.min_msg_priority); // get the full backlog
 			/* WARN: magic number offset for window borders
 			 * NOTE: it would be possible to 'reserve' space here by setting the magic num offset
 			 *       greater than is strictly required to cause scrollback
@@ -326,9 +549,12 @@ impl GameEngine<'_> {
 					Block::default()
 					.borders(Borders::ALL)
 					.border_style(Style::default().fg(Color::White))
+					.title(if channel == "world" { String::new() } else { format!("[{}]", channel) })
 				),
 				self.ui_grid.msg_world,
 			);
+		} else {
+			frame.render_widget(Block::default().borders(Borders::ALL).title("[no MessageLog initialized]"), self.ui_grid.msg_world);
 		}
 	}
 	/// Enables and places the given menu type at the specified position; should only need to be called at menu creation
@@ -337,32 +563,79 @@ impl GameEngine<'_> {
 		//debug!("* Enabling menu {:?} at {}, {}", m_type, posn.0, posn.1); // DEBUG: announce menu display
 		if m_type == MenuType::Main {
 			let mut menu_items: Vec<MenuItem<Cow<'_, str>>> = Vec::new();
-			menu_items.push(MenuItem::item("New Game", "main.new_game".into(), None));
+			menu_items.push(MenuItem::group("New Game", self.game_config.menu_items()));
 			let filepath = bevy_save::get_save_file(&self.savegame_filename);
 			if !self.standby {
 				menu_items.push(MenuItem::item("Save Game", "main.save_game".into(), None));
 			}
 			if std::fs::metadata(filepath).is_ok() {
-				menu_items.push(MenuItem::item("Load Game", "main.load_game".into(), None));
+				// Each save slot is listed along with its details, if they were recorded
+				let slot_name = if let Some(meta) = SaveMeta::load(&self.savegame_filename) {
+					format!("{}: {}", self.savegame_filename, meta.describe())
+				} else {
+					self.savegame_filename.clone()
+				};
+				let slots = vec![MenuItem::item(slot_name, "main.load_game".into(), None)];
+				menu_items.push(MenuItem::group("Load Game", slots));
 			}
 			if !self.standby {
 				menu_items.push(MenuItem::item("Abandon Game", "main.abandon_game".into(), None));
 			}
+			menu_items.push(MenuItem::group("Options", self.config.menu_items(&self.game_config)));
 			menu_items.push(MenuItem::item("Quit", "main.quit".into(), None));
 			self.menu_main = MenuState::new(menu_items);
 		}
 		self.menu_posn = posn;
 		self.visible_menu = m_type;
 	}
+	/// Saves the player's options and hands them to the game in progress, then reopens the main menu to show them
+	pub fn apply_config(&mut self) {
+		if self.keep_settings { self.config.save(); }
+		if !self.standby {
+			self.bevy.world.insert_resource(self.config.clone());
+		}
+		self.set_menu(MenuType::Main, self.menu_posn);
+	}
 	/// Helper for changing the current mode of the GameEngine
 	pub fn set_mode(&mut self, new_mode: EngineMode) {
 		//debug!("* eng.mode set to {new_mode:?}"); // DEBUG: announce engine mode switch
 		self.mode = new_mode;
 	}
+	/// Sends a MoveItem event for every loose item at the taker's position; the item_collection_system reports
+	/// how many of them were actually picked up
+	pub fn take_all_items(&mut self, taker: Entity) {
+		let t_posn = if let Some(t_body) = self.bevy.world.get::<Body>(taker) {
+			t_body.ref_posn
+		} else {
+			return;
+		};
+		let mut item_list = Vec::new();
+		let mut item_query = self.bevy.world.query_filtered::<(Entity, &Body, &Portable), Without<IsCarried>>();
+		for (i_enty, i_body, i_portable) in item_query.iter(&self.bevy.world) {
+			if i_body.contains(&t_posn) && i_portable.carrier == Entity::PLACEHOLDER {
+				item_list.push(i_enty);
+			}
+		}
+		// TODO: stop adding items once the taker's Container is full, when Container gets a capacity
+		if let Some(mut game_events) = self.bevy.world.get_resource_mut::<Events<GameEvent>>() {
+			for item in item_list.iter() {
+				game_events.send(GameEvent::new(GameEventType::PlayerAction(ActionType::MoveItem), Some(taker), Some(*item)));
+			}
+		}
+		if item_list.is_empty() {
+			self.tell_player("There's nothing here to pick up.");
+		}
+	}
 	/// Causes the GameEngine to halt and quit
 	pub fn quit(&mut self) {
 		self.running = false;
 	}
+	/// Requests a new game: the loading screen will be drawn on the next frame, and then the game will be built
+	/// during the following tick, so that the player isn't left staring at a frozen main menu during worldgen
+	pub fn start_new_game(&mut self) {
+		self.visible_menu = MenuType::None;
+		self.generating = true;
+	}
 	/// Starts a new game from scratch
 	pub fn new_game(&mut self) {
 		// If no game is running, then self.standby should be TRUE
@@ -372,6 +645,9 @@ impl GameEngine<'_> {
 			self.standby = true;
 			self.running = false;
 		}
+		if self.keep_settings { self.game_config.save(); } // Remember these settings for next time
+		self.playtime = Duration::ZERO;
+		self.nav_target = None;
 		self.init_bevy();
 		self.build_new_worldmap();
 		self.bevy.update();
@@ -389,12 +665,19 @@ impl GameEngine<'_> {
 	/// Saves the currently-running game to an external file
 	//  INFO: By default (not sure how to change this!), on Linux, this savegame will be at
 	//      ~/.local/share/spacegame/saves/FILENAME.sav
+	//  NOTE: The save is written synchronously, on the same thread that reads the keyboard, so there's no way for a
+	//      Ctrl-C to arrive while the file is half-written; if saving is ever moved off of this thread, then the quit
+	//      key will need to wait for it to finish
 	pub fn save_game(&mut self, filename: &str) {
 		//debug!("* save_game() called on {}", filename); // DEBUG: alert when save_game is called
 		if let Err(e) = self.bevy.world.save(filename) {
 			error!("! ! save_game() failed on '{}', error: {}", filename, e); // DEBUG: warn about save game error
 			return;
 		}
+		let turns = self.bevy.world.get_resource::<TurnCount>().map(|x| x.turns).unwrap_or(0);
+		let mut p_query = self.bevy.world.query_filtered::<&Description, With<Player>>();
+		let location = p_query.get_single(&self.bevy.world).map(|x| x.locn.clone()).unwrap_or_default();
+		SaveMeta::new(turns, &location, self.playtime.as_secs()).save(filename);
 		self.quit();
 	}
 	/// Loads a saved game from the given external file
@@ -407,6 +690,8 @@ impl GameEngine<'_> {
 			self.running = false;
 		}
 		self.init_bevy();
+		self.nav_target = None;
+		self.playtime = Duration::from_secs(SaveMeta::load(filename).map(|x| x.playtime).unwrap_or(0));
 		match self.bevy.world.load_applier(filename) {
 			Ok(applier) => {
 				if let Err(f) = applier.despawn(DespawnMode::Unmapped).apply() {
@@ -417,24 +702,53 @@ impl GameEngine<'_> {
 				error!("! ERR: load_game() failed on '{}', error: {}", filename, e); // DEBUG: warn about loading error
 			}
 		}
+		self.reload_tile_contents();
 		self.bevy.update();
 		self.standby = false;
 		self.running = true;
 		self.set_mode(EngineMode::Running);
 		//debug!("* load_game() finished successfully"); // DEBUG: alert when load_game finishes
 	}
+	/// Rebuilds the WorldModel's tile contents from the Bodies of the entities that are on the map
+	/// The Entity IDs change when a game is loaded, so the contents that were saved with the model can't be used
+	pub fn reload_tile_contents(&mut self) {
+		let mut b_query = self.bevy.world.query::<(Entity, &Body, Option<&Portable>)>();
+		let mut occupants = Vec::new();
+		for (enty, body, portable) in b_query.iter(&self.bevy.world) {
+			if portable.is_some_and(|x| x.carrier != Entity::PLACEHOLDER) { continue; } // Carried items aren't on the map
+			occupants.push((enty, RenderLayer::priority_of(&self.bevy.world, enty), body.posns()));
+		}
+		if let Some(mut model) = self.bevy.world.get_resource_mut::<WorldModel>() {
+			model.reload_tile_contents(&occupants);
+		} else {
+			error!("! ERR: reload_tile_contents() could not find a WorldModel to update"); // DEBUG: warn about missing model
+		}
+	}
 	/// Deletes the game save, ie after dying or abandoning the game
 	pub fn delete_game(&mut self, filename: &str) -> std::io::Result<()> {
 		//debug!("* delete_game() called on {}", filename); // DEBUG: alert when delete_game is called
 		let filepath = bevy_save::get_save_file(filename);
+		SaveMeta::delete(filename);
 		std::fs::remove_file(filepath)
 	}
 	/// Puts the game into a PAUSED state
 	pub fn pause_game(&mut self) {
+		if let Some(mut time) = self.bevy.world.get_resource_mut::<Time>() {
+			time.pause();
+		}
 		self.set_mode(EngineMode::Paused);
 	}
 	/// Puts the game back into a RUNNING state
 	pub fn unpause_game(&mut self) {
+		// Bevy's Time measures its delta from the last update, which was before the pause started: if that gap were
+		// passed along then every PlanqProcess and DataSampleTimer would fast-forward through the whole pause at once
+		// Running one update on the Time while it's still paused moves that reference point up to now at no cost
+		if let Some(mut time) = self.bevy.world.get_resource_mut::<Time>() {
+			if time.is_paused() {
+				time.update();
+				time.unpause();
+			}
+		}
 		self.set_mode(EngineMode::Running);
 	}
 	/// Toggles the game from paused to unpaused or vice versa
@@ -450,16 +764,26 @@ impl GameEngine<'_> {
 		//debug!("* Initializing Bevy..."); // DEBUG: announce Bevy startup
 		let chanlist = vec!["world".to_string(),
 			                  "planq".to_string(),
-			                  "debug".to_string()];
+			                  "shipnet".to_string(),
+			                  "combat".to_string(),
+			                  "actions".to_string(),
+			                  "debug".to_string(),
+			                  "journal".to_string()];
+		let rng_plugin = match self.rng_seed {
+			Some(seed) => { RngPlugin::new().with_rng_seed(seed) } // Forces the RNG to be deterministic
+			None => { RngPlugin::default() } // Non-deterministic RNG
+		};
 		self.bevy
-		.add_plugins(RngPlugin::default()) // Non-deterministic RNG
-		//.add_plugins(RngPlugin::new().with_rng_seed(69420)) // Forces the RNG to be deterministic
+		.add_plugins(rng_plugin)
 		.add_systems(Startup, (new_player_spawn,
 			                     new_lmr_spawn,
 			                     ))
-		.add_systems(Update, (action_referee_system,
+		.add_systems(Update, (action_log_system,
+			                    action_referee_system,
+			                    action_refresh_system,
 			                    camera_update_system,
 			                    examination_system,
+			                    game_outcome_system,
 			                    item_collection_system,
 			                    lockable_system,
 			                    map_indexing_system,
@@ -468,17 +792,28 @@ impl GameEngine<'_> {
 			                    operable_system,
 			                    planq_update_system,
 			                    planq_monitor_system,
+			                    scheduler_system,
+			                    shipnet_system,
+			                    stamina_system,
+			                    turn_scheduler_system,
 			                    visibility_system,
 			                    ))
+		.add_systems(Update, crew_system.run_if(npc_turn_ready).after(turn_scheduler_system).before(end_npc_turn_system))
+		.add_systems(Update, end_npc_turn_system.after(turn_scheduler_system))
 		.register_type::<(i32, i32, i32)>()
+		.register_type::<CrewRole>()
 		.register_type::<DeviceState>()
+		.register_type::<crate::components::Direction>()
 		.register_type::<PlanqDataType>()
 		.register_type::<PlanqEvent>()
 		.register_type::<PlanqEventType>()
 		.register_type::<Portal>()
 		.register_type::<Position>()
+		.register_type::<ScheduledPayload>()
+		.register_type::<ScheduleTrigger>()
 		.register_type::<TimerMode>()
 		.register_type::<Vec<bool>>()
+		.register_type::<Vec<Option<u8>>>()
 		.register_type::<Vec<Entity>>()
 		.register_type::<Vec<WorldMap>>()
 		.register_type::<Vec<Message>>()
@@ -490,20 +825,28 @@ impl GameEngine<'_> {
 		.register_type::<HashMap<(i32, i32, i32), (i32, i32, i32)>>()
 		.register_type::<HashMap<Entity, Position>>() // planned to be superceded by the below type
 		.register_type::<HashMap<Position, Vec<Entity>>>()
+		.register_type::<HashMap<Position, u64>>()
 		.register_type::<HashMap<String, PlanqDataType>>()
 		.register_type::<HashMap<Position, ScreenCell>>()
 		.register_type::<bevy::utils::HashSet<ActionType>>()
 		.register_saveable::<AccessPort>()
 		.register_saveable::<ActionSet>()
-		.register_saveable::<CameraView>()
+		.register_saved_resource::<CameraView>()
 		.register_saveable::<Container>()
+		.register_saveable::<Critical>()
+		.register_saveable::<Crew>()
 		.register_saveable::<DataSampleTimer>()
 		.register_saveable::<Description>()
 		.register_saveable::<Device>()
+		.register_saveable::<Facing>()
+		.register_saveable::<Friendly>()
 		.register_saveable::<GameEvent>()
 		.register_saveable::<GameEventContext>()
+		.register_saved_resource::<GameClock>()
+		.register_saved_resource::<GameConfig>()
 		.register_saveable::<GameEventType>()
-		.register_saveable::<GlobalRng>()
+		.register_saved_resource::<GameOutcome>()
+		.register_saved_resource::<GlobalRng>()
 		.register_saveable::<Key>()
 		.register_saveable::<LMR>()
 		.register_saveable::<Lockable>()
@@ -511,38 +854,82 @@ impl GameEngine<'_> {
 		.register_saveable::<Memory>()
 		.register_saveable::<Message>()
 		.register_saveable::<MessageChannel>()
-		.register_saveable::<MessageLog>()
+		.register_saved_resource::<MessageLog>()
 		.register_saveable::<Mobile>()
-		.register_saveable::<WorldModel>()
+		.register_saveable::<Multilevel>()
+		.register_saved_resource::<WorldModel>()
 		.register_saveable::<Networkable>()
+		.register_saved_resource::<NpcTurn>()
 		.register_saveable::<Obstructive>()
 		.register_saveable::<Opaque>()
 		.register_saveable::<Openable>()
 		.register_saveable::<Planq>()
 		.register_saveable::<PlanqActionMode>()
 		.register_saveable::<PlanqCPUMode>()
-		.register_saveable::<PlanqData>()
-		.register_saveable::<PlanqMonitor>()
+		.register_saved_resource::<PlanqData>()
+		.register_saved_resource::<PlanqMonitor>()
 		.register_saveable::<PlanqProcess>()
 		.register_saveable::<Player>()
 		.register_saveable::<Portable>()
-		.register_saveable::<Position>()
+		.register_saved_resource::<Position>()
+		.register_saveable::<RenderLayer>()
 		.register_saveable::<RngComponent>()
+		.register_saved_resource::<RunStats>()
+		.register_saveable::<Speed>()
+		.register_saveable::<Stamina>()
+		.register_saveable::<ScheduledEvent>()
 		.register_saveable::<Tile>()
 		.register_saveable::<TileType>()
+		.register_saved_resource::<TurnCount>()
 		.register_saveable::<bevy::utils::hashbrown::HashMap<Position, Position>>()
 		.register_saveable::<bevy::utils::hashbrown::HashSet<ActionType>>()
 		.insert_resource(Events::<GameEvent>::default())
 		.insert_resource(Events::<PlanqEvent>::default())
+		.insert_resource(GameClock::default())
+		.insert_resource(GameOutcome::default())
+		.insert_resource(self.config.clone())
+		.insert_resource(self.game_config.clone())
 		.insert_resource(MessageLog::new(chanlist))
+		.insert_resource(NpcTurn::default())
 		.insert_resource(PlanqData::new())
 		.insert_resource(PlanqMonitor::new())
 		.insert_resource(Position::new(4, 14, 1)) // DEBUG: arbitrary player spawnpoint
 		.insert_resource(RexAssets::new())
+		.insert_resource(RunStats::default())
+		.insert_resource(TurnCount::default())
 		;
+		#[cfg(debug_assertions)]
+		self.bevy.add_systems(Update, contents_check_system);
 		self.mode = EngineMode::Startup;
 		self.solve_layout(self.term_dims);
 		self.build_camera();
+		#[cfg(debug_assertions)]
+		self.check_type_registry();
+	}
+	/// DEBUG: Fails fast if any of the resources that go into a savegame can't actually be written out and read back in
+	/// The list comes from the register_saved_resource() calls in init_bevy(), so there's nothing else to keep in sync;
+	/// otherwise the omission won't show up until a load fails, and bevy_save's error won't say which type was missed
+	#[cfg(debug_assertions)]
+	pub fn check_type_registry(&self) {
+		let saved_resources = if let Some(value) = self.bevy.world.get_resource::<SavedResources>() { value } else { return; };
+		let registry = self.bevy.world.resource::<AppTypeRegistry>().read();
+		for (type_id, type_name) in saved_resources.0.iter() {
+			assert!(registry.get_type_data::<ReflectResource>(*type_id).is_some(), "! ERROR: saved resource {} is missing #[reflect(Resource)]", type_name);
+		}
+	}
+	/// DEBUG: Reloads the item definitions from disk and reports the outcome on the debug channel
+	/// Returns the report that was sent, so that the caller may also show it elsewhere
+	pub fn reload_item_defns(&mut self) -> Vec<String> {
+		let report = match self.artisan.reload_defns() {
+			Ok(count) => { vec![format!("* Reloaded {} item definitions", count)] }
+			Err(errors) => { errors }
+		};
+		if let Some(mut msglog) = self.bevy.world.get_resource_mut::<MessageLog>() {
+			for line in report.iter() {
+				msglog.add(line, "debug", 0, 0);
+			}
+		}
+		report
 	}
 	/// Creates the initial worldmap from scratch
 	pub fn build_new_worldmap(&mut self) {
@@ -551,43 +938,114 @@ impl GameEngine<'_> {
 		// - Creates the 'logical' topology map of GraphRooms/GraphPortals that provide pathfinding and placement
 		// - Generates the baseline list of doors required to connect all of the rooms in the map
 		// - Generates the list of 'ladders' that connect rooms across z-levels and allow movement
+		// The critical items and the rooms all need to be reachable from where the player starts out
+		let spawnpoint = *self.bevy.world.get_resource::<Position>().unwrap_or(&Position::INVALID);
 		let mut rng = self.bevy.world.get_resource_mut::<GlobalRng>().expect("RNG should always be an available Bevy resource");
+		self.mason = get_world_builder(&self.game_config.ship_file);
+		self.mason.set_source(&self.game_config.ship_file);
 		self.mason.build_world(); // <- remove the RNG from here for starters, insert it closer to where it's needed
 		// Get a copy of the freshly-constructed world model
 		let mut model = self.mason.get_model();
+		self.artisan.deck_count = model.levels.len();
 		let mut new_item_list = Vec::new();
 
 		// Get the list of items that we know for sure need to be generated at specific positions
 		let mut item_spawns = self.mason.get_essential_item_requests(); // list of (name, posn)
 		//eprintln!("* DEBUG: build_new_worldmap: essential: {:?}", item_spawns);
 		new_item_list.append(&mut item_spawns);
+		// The room layouts don't know where the essential items went, nor that a Multilevel one reaches up through the
+		// decks above it, so set those cells aside before anything else gets placed
+		for (item_name, posn) in new_item_list.iter() {
+			model.layout.reserve_cells(&self.artisan.footprint(item_name, *posn));
+		}
 		// Next, get the list of requested items, find spawnpoints for them, and add them to the list of spawns
-		let item_reqs = self.mason.get_additional_item_requests();
+		let item_reqs = self.game_config.scale_item_requests(self.mason.get_additional_item_requests());
+		let mode = self.placement;
 		//eprintln!("* DEBUG: build_new_worldmap: additional: {:?}", item_reqs); // DEBUG:
 		for (room_name, item_name) in item_reqs.iter() {
 			//eprintln!("* DEBUG: Attempting to spawn {} in {}", item_name, room_name); // DEBUG:
 			// get the item shape from artisan (returns a SpawnTemplate)
 			//eprintln!("** DEBUG: looking to get a shape for {}", item_name);
-			if let Some(item_shape) = self.artisan.get_random_shape(item_name, &mut rng) {
+			if let Some(item_shape) = self.artisan.get_random_shape(item_name, &mut rng, mode) {
 				// try to get a spawnpoint from mason using the ItemTemplate (returns a Option<Vec<(name: String, ref_posn: Position)>>)
 				//eprintln!("*** DEBUG: looking to get a spawnpoint for {}", item_name);
-				if let Some(mut item_spawns) = model.find_spawnpoint_in(room_name, item_shape, &mut rng) {
+				if let Some(mut item_spawns) = model.find_spawnpoint_in(room_name, item_shape.clone(), &mut rng, mode) {
 					//eprintln!("**** DEBUG: found a place to spawn {}: {:?}", item_name, item_spawns);
+					// Critical items get a few more tries if they landed somewhere that the player can't get to, and so
+					// do Multilevel items that would run into something on one of the decks above
+					let mut attempts = 0;
+					while attempts < CRITICAL_SPAWN_ATTEMPTS
+					&& item_spawns.iter().any(|(name, posn)| (self.artisan.is_critical(name) && !model.is_accessible_from(spawnpoint, *posn))
+						|| !model.can_place_at(&upper_parts(&self.artisan, name, *posn))) {
+						if let Some(new_spawns) = model.find_spawnpoint_in(room_name, item_shape.clone(), &mut rng, mode) {
+							item_spawns = new_spawns;
+						}
+						attempts += 1;
+					}
+					// A Multilevel item that still doesn't fit is left out, rather than being built into a wall upstairs
+					item_spawns.retain(|(name, posn)| {
+						let fits = model.can_place_at(&upper_parts(&self.artisan, name, *posn));
+						if !fits { warn!("* no room on the decks above {} for the {}, leaving it out", posn, name); } // DEBUG: report dropped multilevel item
+						fits
+					});
+					for (name, posn) in item_spawns.iter() {
+						model.layout.reserve_cells(&upper_parts(&self.artisan, name, *posn));
+					}
 					new_item_list.append(&mut item_spawns);
 				}
 			}
 		}
+		// Find a place for each of the NPCs, making sure that the layout didn't put them anywhere they can't stand
+		// None of the items are on the map yet, so the fixed NPC positions are checked against where they're going to be
+		let item_posns: Vec<Position> = new_item_list.iter().flat_map(|(name, posn)| self.artisan.footprint(name, *posn)).collect();
+		let mut npc_spawns: Vec<(NpcRequest, Position)> = Vec::new();
+		for request in self.mason.get_npc_requests().iter() {
+			let mut spawnpoint = None;
+			if let Some(posn) = request.posn {
+				match model.check_spawnpoint(posn, &request.room) {
+					Ok(()) if item_posns.contains(&posn) => { warn!("* NPC {} would be standing on an item at {}, moving them", request.name, posn); } // DEBUG: report bad NPC posn
+					Ok(()) if npc_spawns.iter().any(|x| x.1 == posn) => { warn!("* NPC {} would share {} with another NPC, moving them", request.name, posn); } // DEBUG: report bad NPC posn
+					Ok(()) => {
+						model.layout.reserve_cells(&[posn]); // Keeps the NPCs that are placed at random from landing here
+						spawnpoint = Some(posn);
+					}
+					Err(reason) => { warn!("* NPC {} can't be placed: {}, moving them", request.name, reason); } // DEBUG: report bad NPC posn
+				}
+			}
+			if spawnpoint.is_none() {
+				spawnpoint = model.find_spawnpoint_in(&request.room, vec!["A".to_string()].into(), &mut rng, mode)
+					.and_then(|x| x.first().map(|y| y.1));
+			}
+			if let Some(posn) = spawnpoint {
+				npc_spawns.push((request.clone(), posn));
+			} else {
+				error!("! ERR: could not find anywhere to put NPC {} in room {}", request.name, request.room); // DEBUG: report NPC placement failure
+			}
+		}
+		for (item_name, posn) in new_item_list.iter() {
+			if self.artisan.is_critical(item_name) && !model.is_accessible_from(spawnpoint, *posn) {
+				error!("! ERR: critical item {} was placed at an inaccessible position {}", item_name, posn); // DEBUG: report unreachable critical item
+			}
+		}
 		// Spawn all of the items we need for the game
 		// This CANNOT be executed in the loop above or Rust will complain about a double borrow
 		// WARN: Need to have *all* positions decided on by this point
 		//eprintln!("* DEBUG: Sending the following list for spawn:\n{:#?}", new_item_list); // DEBUG:
-		for (i_name, i_posn) in new_item_list.iter() {
-			let item_list = self.artisan.create(i_name).at(*i_posn).build(&mut self.bevy.world);
-			for (i_enty, i_shape) in item_list.iter() {
-				model.add_contents(i_shape, 0, i_enty.id());
-				//debug!("* added new item '{}' at posn {:?}", i_name, i_posn);
-				//eprintln!("DEBUG: * added new item '{}' at posn {:?}", i_name, i_posn);
+		for (i_enty, i_shape) in self.artisan.build_batch(&new_item_list, &mut self.bevy.world).iter() {
+			model.add_contents(i_shape, RenderLayer::priority_of(&self.bevy.world, *i_enty), *i_enty);
+			//debug!("* added new item {:?} at posns {:?}", i_enty, i_shape);
+		}
+		for (request, posn) in npc_spawns.iter() {
+			self.creatures.create(&request.kind).name(&request.name);
+			if let Some(role_name) = request.role.as_ref() {
+				self.creatures.role(role_name);
 			}
+			let (n_enty, n_shape) = self.creatures.at(*posn).build(&mut self.bevy.world);
+			model.add_contents(&n_shape, RenderLayer::ACTOR, n_enty);
+		}
+		// Make sure that the player can get everywhere on the ship from where they start out
+		for room_name in model.find_unreachable_rooms(spawnpoint).iter() {
+			error!("! ERR: room {} can't be reached from the player's starting point {}", room_name, spawnpoint); // DEBUG: report unreachable room
 		}
 		// Add the fully-constructed world model to Bevy
 		self.bevy.insert_resource(model);
@@ -625,6 +1083,38 @@ impl GameEngine<'_> {
 		let main_camera = CameraView::new(self.ui_grid.camera_main.width as i32, self.ui_grid.camera_main.height as i32);
 		self.bevy.insert_resource(main_camera);
 	}
+	/// Detaches the camera from the player, so that it can be panned around the current deck
+	pub fn start_panning(&mut self) {
+		let p_posn = *self.bevy.world.get_resource::<Position>().unwrap_or(&Position::INVALID);
+		if let Some(mut camera) = self.bevy.world.get_resource_mut::<CameraView>() {
+			camera.focus = p_posn;
+		}
+	}
+	/// Moves the camera's focus by one tile, keeping it within the bounds of the current deck
+	pub fn pan_camera(&mut self, dir: crate::components::Direction) {
+		let (width, height) = if let Some(model) = self.bevy.world.get_resource::<WorldModel>() {
+			let p_posn = *self.bevy.world.get_resource::<Position>().unwrap_or(&Position::INVALID);
+			if let Some(level) = model.levels.get(p_posn.z as usize) { (level.width as i32, level.height as i32) } else { return; }
+		} else {
+			return;
+		};
+		if let Some(mut camera) = self.bevy.world.get_resource_mut::<CameraView>() {
+			if !camera.is_panning() { return; }
+			let (x_diff, y_diff, _) = dir.offset();
+			camera.focus.x = (camera.focus.x + x_diff).clamp(0, width - 1);
+			camera.focus.y = (camera.focus.y + y_diff).clamp(0, height - 1);
+		}
+	}
+	/// Reattaches the camera to the player
+	pub fn stop_panning(&mut self) {
+		if let Some(mut camera) = self.bevy.world.get_resource_mut::<CameraView>() {
+			camera.focus = Position::INVALID;
+		}
+	}
+	/// Returns true if the camera is currently detached from the player
+	pub fn is_panning(&self) -> bool {
+		self.bevy.world.get_resource::<CameraView>().map(|x| x.is_panning()).unwrap_or(false)
+	}
 	/// Solves the layout configuration given a set of layout constraints and an area to cover
 	pub fn solve_layout(&mut self, area: Rect) {
 		self.ui_grid.calc_layout(area);
@@ -632,38 +1122,413 @@ impl GameEngine<'_> {
 			camera.set_dims(self.ui_grid.camera_main.width as i32, self.ui_grid.camera_main.height as i32);
 		}
 	}
-	/// Executes a command on the PLANQ, generally from the CLI; DEBUG: always returns false
+	/// Runs a line of input from the PLANQ's CLI, which may chain several commands together with semicolons;
+	/// they run in order until one of them fails
+	pub fn exec_line(&mut self, input_text: &str) {
+		let cmd_list: Vec<&str> = input_text.split(';').map(|x| x.trim()).filter(|x| !x.is_empty()).collect();
+		for (index, cmd_text) in cmd_list.iter().enumerate() {
+			let command = handler::planq_parser(cmd_text);
+			// Keep the history free of repeats, so that a "!!" always refers to a real command
+			if command != PlanqCmd::Repeat {
+				self.planq_stdin.history.push(cmd_text.to_string());
+			}
+			if !self.exec(command) {
+				if cmd_list.len() > 1 {
+					self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]Stopped at command {} of {}: '{}'", index + 1, cmd_list.len(), cmd_text).as_str());
+					self.tell_planq(" ");
+				}
+				break;
+			}
+		}
+	}
+	/// Executes a command on the PLANQ, generally from the CLI; returns false if the command failed
 	pub fn exec(&mut self, cmd: PlanqCmd) -> bool {
-		// FIXME: this unwrap() cannot be replaced in situ, because regardless of whether or not there's a MessageLog,
-		// the PLANQ's commands should still be executed!
-		// Therefore, it would be better to pull all of these msglog-unwrap-tell_planq chains out to their own
-		// dedicated method, as self.tell_planq(), which itself handles these parts and can safely handle
-		// the unwrapping logic
-		let mut msglog = self.bevy.world.get_resource_mut::<MessageLog>().expect("MessageLog should be in Bevy");
 		match cmd {
 			PlanqCmd::Error(msg) => {
-				msglog.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:");
-				msglog.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", msg).as_str());
-				msglog.tell_planq(" ");
+				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:");
+				self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", msg).as_str());
+				self.tell_planq(" ");
+				return false;
 			}
 			PlanqCmd::Help => {
-				msglog.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Available commands:");
+				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Available commands:");
 				for command in PlanqCmd::iter() {
-					msglog.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]  {}", command).as_str());
+					self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]  {}", command).as_str());
+				}
+				self.tell_planq(" ");
+			}
+			// TODO: the shutdown, reboot, and planq.connect/disconnect subroutines
+			PlanqCmd::Shutdown | PlanqCmd::Reboot | PlanqCmd::Connect(_) | PlanqCmd::Disconnect => {
+				self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] '{}' is unimplemented", cmd).as_str());
+				self.tell_planq(" ");
+				return false;
+			}
+			PlanqCmd::Reveal(all_levels) => {
+				if !self.debug_mode {
+					self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] Permission denied");
+					self.tell_planq(" ");
+					return false;
+				}
+				let p_level = self.bevy.world.get_resource::<Position>().map(|x| x.z as usize).unwrap_or(0);
+				if let Some(mut model) = self.bevy.world.get_resource_mut::<WorldModel>() {
+					if all_levels {
+						for z_level in 0..model.levels.len() {
+							model.reveal_level(z_level);
+						}
+					} else {
+						model.reveal_level(p_level);
+					}
 				}
-				msglog.tell_planq(" ");
+				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Map data downloaded");
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Status => {
+				let turns = self.bevy.world.get_resource::<TurnCount>().map(|x| x.turns).unwrap_or(0);
+				let ticks = self.bevy.world.get_resource::<GameClock>().map(|x| x.ticks).unwrap_or(0);
+				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]STATUS:");
+				self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]  Turns taken: {}", turns).as_str());
+				self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]  Ship time: {} ticks", ticks).as_str());
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Reload => {
+				if !self.debug_mode {
+					self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] Permission denied");
+					self.tell_planq(" ");
+					return false;
+				}
+				for line in self.reload_item_defns().iter() {
+					self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", line).as_str());
+				}
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Export => {
+				if !self.debug_mode {
+					self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] Permission denied");
+					self.tell_planq(" ");
+					return false;
+				}
+				match self.export_map() {
+					Ok(filepath) => {
+						self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]Map exported to {}", filepath.display()).as_str());
+					}
+					Err(msg) => {
+						self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] {}", msg).as_str());
+						self.tell_planq(" ");
+						return false;
+					}
+				}
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Deck(z_level) => {
+				if !self.debug_mode {
+					self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] Permission denied");
+					self.tell_planq(" ");
+					return false;
+				}
+				match self.jump_to_deck(z_level) {
+					Ok(posn) => {
+						self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]Moved to {}", posn).as_str());
+					}
+					Err(msg) => {
+						self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] {}", msg).as_str());
+						self.tell_planq(" ");
+						return false;
+					}
+				}
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Scan(target) => {
+				for line in self.scan_room(&target).iter() {
+					self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", line).as_str());
+				}
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Locate(target) => {
+				for line in self.locate_items(&target).iter() {
+					self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", line).as_str());
+				}
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Note(text) => {
+				let turn = self.bevy.world.get_resource::<TurnCount>().map(|x| x.turns).unwrap_or(0);
+				if let Some(mut msglog) = self.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.add_note(&text, turn as i32);
+				}
+				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Note saved");
+				self.tell_planq(" ");
+			}
+			PlanqCmd::Repeat => {
+				if let Some(last_cmd) = self.planq_stdin.history.last().cloned() {
+					let last_parsed = handler::planq_parser(&last_cmd);
+					if !last_parsed.is_implemented() {
+						self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:[[end]] Cannot repeat '{}', it is unimplemented", last_parsed).as_str());
+						self.tell_planq(" ");
+						return false;
+					}
+					let echo_text = self.cli_prompt() + &last_cmd;
+					self.tell_planq(&echo_text);
+					return self.exec(last_parsed);
+				}
+				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]No previous command to repeat");
+				self.tell_planq(" ");
+				return false;
+			}
+			PlanqCmd::Notes => {
+				let notes = self.bevy.world.get_resource::<MessageLog>().map(|x| x.get_notes()).unwrap_or_default();
+				if notes.is_empty() {
+					self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]No notes yet; try 'note <text>'");
+				}
+				for note in notes.iter() {
+					self.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[fg:gray]]T{:<5}[[end]]{}", note.timestamp, note.text).as_str());
+				}
+				self.tell_planq(" ");
 			}
-			PlanqCmd::Shutdown => { todo!(); /* trigger a shutdown */ }
-			PlanqCmd::Reboot => { todo!(); /* execute a reboot */ }
-			PlanqCmd::Connect(_target) => { todo!(); /* run the planq.connect subroutine */ }
-			PlanqCmd::Disconnect => { todo!(); /* run the planq.disconnect subroutine */ }
 			_ => { /* NoOperation */ }
 		}
-		false
+		true
+	}
+	/// Collects the statistics for the whole game, for the end screen
+	pub fn run_summary(&mut self) -> Vec<String> {
+		let turns = self.bevy.world.get_resource::<TurnCount>().map(|x| x.turns).unwrap_or(0);
+		let ticks = self.bevy.world.get_resource::<GameClock>().map(|x| x.ticks).unwrap_or(0);
+		let total_rooms = self.bevy.world.get_resource::<WorldModel>().map(|x| x.layout.rooms.len()).unwrap_or(0);
+		let stats = self.bevy.world.get_resource::<RunStats>().cloned().unwrap_or_default();
+		vec![
+			format!("Turns survived:  {}", turns),
+			format!("Time elapsed:    {} ticks", ticks),
+			format!("Rooms explored:  {}/{}", stats.rooms_visited.len(), total_rooms),
+			format!("Items collected: {}", stats.items_collected),
+		]
+	}
+	/// Collects a summary of the player's current state for the character screen
+	pub fn player_summary(&mut self) -> Vec<String> {
+		let mut p_query = self.bevy.world.query_filtered::<(Entity, &Description, &Body, Option<&Stamina>), With<Player>>();
+		let (p_enty, p_desc, p_body, p_stamina) = if let Ok(value) = p_query.get_single(&self.bevy.world) { value } else {
+			return vec!["[no player found]".to_string()];
+		};
+		let mut output = vec![p_desc.name.clone(), p_desc.desc.clone(), " ".to_string()];
+		if let Some(stamina) = p_stamina {
+			output.push(format!("Stamina:  {}/{}", stamina.current, stamina.max));
+		}
+		let room = self.bevy.world.get_resource::<WorldModel>()
+			.and_then(|x| x.layout.get_room_name(p_body.ref_posn))
+			.unwrap_or("an unknown area".to_string());
+		output.push(format!("Location: {}, deck {}", room, p_body.ref_posn.z));
+		let turns = self.bevy.world.get_resource::<TurnCount>().map(|x| x.turns).unwrap_or(0);
+		output.push(format!("Turns:    {}", turns));
+		let planq_status = match self.bevy.world.get_resource::<PlanqData>() {
+			Some(planq) if !planq.is_carried => { "not carried".to_string() }
+			Some(planq) if !planq.power_is_on => { "powered off".to_string() }
+			Some(planq) => { planq.cpu_mode.to_string() }
+			None => { "missing".to_string() }
+		};
+		output.push(format!("PLANQ:    {}", planq_status));
+		output.push(" ".to_string());
+		let mut i_query = self.bevy.world.query::<(&Description, &Portable)>();
+		let mut items: Vec<String> = i_query.iter(&self.bevy.world)
+			.filter(|x| x.1.carrier == p_enty)
+			.map(|x| format!("- {}", x.0.name))
+			.collect();
+		items.sort();
+		if items.is_empty() {
+			output.push("Carrying nothing".to_string());
+		} else {
+			output.push("Carrying:".to_string());
+			output.append(&mut items);
+		}
+		output
+	}
+	/// Finds every item with the given name and describes where it is relative to the player
+	/// Only items that the player has seen before are reported, unless debug_mode is set
+	pub fn locate_items(&mut self, target: &str) -> Vec<String> {
+		let mut p_query = self.bevy.world.query_filtered::<(&Body, Option<&Memory>), With<Player>>();
+		let mut i_query = self.bevy.world.query::<(Entity, &Description, &Body)>();
+		let (p_posn, p_memory) = if let Ok((p_body, p_memory)) = p_query.get_single(&self.bevy.world) {
+			(p_body.ref_posn, p_memory.cloned().unwrap_or_default())
+		} else {
+			return vec!["ERROR: Could not find the PLANQ's owner".to_string()];
+		};
+		let model = if let Some(model) = self.bevy.world.get_resource::<WorldModel>() { model } else {
+			return vec!["ERROR: No ship layout available".to_string()];
+		};
+		let mut output = Vec::new();
+		let mut nearest: Option<(Entity, Position)> = None;
+		for (i_enty, i_desc, i_body) in i_query.iter(&self.bevy.world) {
+			if !i_desc.name.eq_ignore_ascii_case(target) { continue; }
+			if !self.debug_mode && !p_memory.visual.values().any(|x| x.contains(&i_enty)) { continue; }
+			let i_posn = i_body.ref_posn;
+			if nearest.as_ref().map(|x| nav_distance(&p_posn, &i_posn) < nav_distance(&p_posn, &x.1)).unwrap_or(true) {
+				nearest = Some((i_enty, i_posn));
+			}
+			let room = model.layout.get_room_name(i_posn).unwrap_or("an unknown area".to_string());
+			let deck = if i_posn.z == p_posn.z { "this deck".to_string() } else { format!("deck {}", i_posn.z) };
+			output.push(format!("{}: {}, {}", i_desc.name, room, deck));
+		}
+		if output.is_empty() {
+			output.push(format!("No known locations for '{}'", target));
+		}
+		// Point the compass at the closest one
+		if let Some((i_enty, _)) = nearest { self.nav_target = Some(i_enty); }
+		output
+	}
+	/// DEBUG: Moves the player straight to another deck, onto the open floor nearest to where they were standing
+	/// Returns the player's new Position, or the reason that they couldn't be moved
+	pub fn jump_to_deck(&mut self, z_level: usize) -> Result<Position, String> {
+		let mut p_query = self.bevy.world.query_filtered::<(Entity, &Body), With<Player>>();
+		let (p_enty, p_posns, p_posn) = if let Ok((enty, body)) = p_query.get_single(&self.bevy.world) {
+			(enty, body.posns(), body.ref_posn)
+		} else {
+			return Err("Could not find the PLANQ's owner".to_string());
+		};
+		let model = if let Some(model) = self.bevy.world.get_resource::<WorldModel>() { model } else {
+			return Err("No ship layout available".to_string());
+		};
+		let level = if let Some(level) = model.levels.get(z_level) { level } else {
+			return Err(format!("There is no deck {}; the decks are numbered 0 to {}", z_level, model.levels.len().saturating_sub(1)));
+		};
+		let mut target = None;
+		let mut best_distance = i32::MAX;
+		for y_posn in 0..level.height as i32 {
+			for x_posn in 0..level.width as i32 {
+				let index = level.to_index(x_posn, y_posn);
+				if level.tiles[index].ttype != TileType::Floor || level.blocked_tiles.get(index).copied().unwrap_or(false) { continue; }
+				let distance = (x_posn - p_posn.x).abs().max((y_posn - p_posn.y).abs());
+				if distance < best_distance {
+					best_distance = distance;
+					target = Some(Position::new(x_posn, y_posn, z_level as i32));
+				}
+			}
+		}
+		let target = if let Some(value) = target { value } else {
+			return Err(format!("There's nowhere to stand on deck {}", z_level));
+		};
+		let room_name = model.layout.get_room_name(target);
+		if let Some(mut model) = self.bevy.world.get_resource_mut::<WorldModel>() {
+			model.remove_contents(&p_posns, p_enty);
+		}
+		let mut new_posns = Vec::new();
+		if let Some(mut body) = self.bevy.world.get_mut::<Body>(p_enty) {
+			body.move_to(target);
+			new_posns = body.posns();
+		}
+		if let Some(mut model) = self.bevy.world.get_resource_mut::<WorldModel>() {
+			model.add_contents(&new_posns, RenderLayer::PLAYER, p_enty);
+		}
+		if let Some(mut posn) = self.bevy.world.get_mut::<Position>(p_enty) { *posn = target; }
+		if let Some(mut posn) = self.bevy.world.get_resource_mut::<Position>() { *posn = target; }
+		if let Some(mut viewshed) = self.bevy.world.get_mut::<Viewshed>(p_enty) { viewshed.dirty = true; }
+		if let (Some(mut desc), Some(name)) = (self.bevy.world.get_mut::<Description>(p_enty), room_name) { desc.locn = name; }
+		self.stop_panning();
+		Ok(target)
+	}
+	/// DEBUG: Writes every level of the WorldModel out to a text file next to the savegames, one char per tile
+	/// Each tile shows the glyph of the topmost entity on it, if there is one, so that the item placement can be checked
+	pub fn export_map(&mut self) -> Result<std::path::PathBuf, String> {
+		let model = if let Some(model) = self.bevy.world.get_resource::<WorldModel>() { model } else {
+			return Err("No ship layout available".to_string());
+		};
+		let mut output = Vec::new();
+		for (z_level, level) in model.levels.iter().enumerate() {
+			output.push(format!("# Deck {} ({}x{})", z_level, level.width, level.height));
+			for y in 0..level.height as i32 {
+				let mut line = String::new();
+				for x in 0..level.width as i32 {
+					let posn = Position::new(x, y, z_level as i32);
+					let glyph = level.get_visible_entity_at(posn)
+						.and_then(|x| self.bevy.world.get::<Body>(x))
+						.and_then(|x| x.glyph_at(&posn))
+						.map(|x| x.cell.glyph)
+						.unwrap_or_else(|| level.get_display_tile(posn).cell.glyph);
+					line.push_str(if glyph.is_empty() { " " } else { &glyph });
+				}
+				output.push(line.trim_end().to_string());
+			}
+			output.push(String::new());
+		}
+		let filepath = bevy_save::get_save_file("map_export").with_extension("txt");
+		if let Some(dir) = filepath.parent() {
+			let _ = std::fs::create_dir_all(dir);
+		}
+		std::fs::write(&filepath, output.join("\n")).map_err(|e| format!("Could not write to {}: {}", filepath.display(), e))?;
+		Ok(filepath)
+	}
+	/// Lists everything that the ship's sensors know to be in the named room, whether or not the player has seen it
+	/// The PLANQ must be jacked into the shipnet to get at the sensor data
+	pub fn scan_room(&mut self, target: &str) -> Vec<String> {
+		let connected = self.bevy.world.get_resource::<PlanqData>().map(|x| x.jack_cnxn != Entity::PLACEHOLDER).unwrap_or(false);
+		if !connected {
+			return vec!["ERROR: No shipnet connection; connect to an access port first".to_string()];
+		}
+		let model = if let Some(model) = self.bevy.world.get_resource::<WorldModel>() { model } else {
+			return vec!["ERROR: No ship layout available".to_string()];
+		};
+		let room = if let Some(room) = model.layout.rooms.iter().find(|x| x.name.eq_ignore_ascii_case(target)) { room } else {
+			return vec![format!("ERROR: No room named '{}' on the shipnet", target)];
+		};
+		// Multitile entities are listed once per tile, so they need to be deduplicated before they get counted
+		let mut found: Vec<Entity> = Vec::new();
+		for posn in room.new_interior.keys() {
+			for enty in model.get_contents_at(*posn) {
+				if !found.contains(&enty) { found.push(enty); }
+			}
+		}
+		let mut tally: Vec<(String, usize)> = Vec::new();
+		for enty in found.iter() {
+			let name = if let Some(desc) = self.bevy.world.get::<Description>(*enty) { desc.name.clone() } else { continue; };
+			if let Some(entry) = tally.iter_mut().find(|x| x.0 == name) {
+				entry.1 += 1;
+			} else {
+				tally.push((name, 1));
+			}
+		}
+		tally.sort();
+		let mut output = vec![format!("Sensor sweep of {}:", room.name)];
+		if tally.is_empty() {
+			output.push("  No contacts".to_string());
+		}
+		for (name, count) in tally.iter() {
+			if *count > 1 {
+				output.push(format!("  {} x{}", name, count));
+			} else {
+				output.push(format!("  {}", name));
+			}
+		}
+		output
+	}
+	/// Produces the PLANQ's CLI prompt, including the name of the device that it's jacked into, if any
+	pub fn cli_prompt(&self) -> String {
+		let planq = if let Some(value) = self.bevy.world.get_resource::<PlanqData>() { value } else {
+			return "> ".to_string();
+		};
+		let target_name = if planq.jack_cnxn == Entity::PLACEHOLDER { None } else {
+			self.bevy.world.get::<Description>(planq.jack_cnxn).map(|x| x.get_name().to_lowercase())
+		};
+		planq.get_prompt(target_name.as_deref())
+	}
+	/// Sends a message to the PLANQ's terminal; does nothing if there's no MessageLog in Bevy yet
+	pub fn tell_planq(&mut self, msg_text: &str) {
+		if let Some(mut msglog) = self.bevy.world.get_resource_mut::<MessageLog>() {
+			msglog.tell_planq(msg_text);
+		}
+	}
+	/// Sends a message to the player via the world log; does nothing if there's no MessageLog in Bevy yet
+	pub fn tell_player(&mut self, msg_text: &str) {
+		if let Some(mut msglog) = self.bevy.world.get_resource_mut::<MessageLog>() {
+			msglog.tell_player(msg_text);
+		}
 	}
 }
 
 //  ###: SIMPLE TYPES AND HELPERS
+/// Measures how far apart two Positions are for the compass, counting each deck between them as a long way off
+fn nav_distance(start: &Position, end: &Position) -> i32 {
+	let (x_diff, y_diff, z_diff) = start.difference(end);
+	x_diff.abs().max(y_diff.abs()) + z_diff.abs() * 1000
+}
+/// Lists the parts of an item that would be on the decks above the one that it's spawned on, if it's Multilevel
+fn upper_parts(artisan: &ItemBuilder, item_name: &str, posn: Position) -> Vec<Position> {
+	artisan.footprint(item_name, posn).into_iter().filter(|x| x.z != posn.z).collect()
+}
 //   ##: EngineMode
 /// Defines the set of modes that the GameEngine may run in during the course of the program
 #[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
@@ -675,11 +1540,175 @@ pub enum EngineMode {
 	Startup,
 	Running,
 	Paused,
-	GoodEnd,
-	BadEnd,     // TODO: set up variants for both this and GoodEnd? maybe just a GameOver mode?
+	GoodEnd,    // Set once the GameOutcome is Won
+	BadEnd,     // Set once the GameOutcome is Lost
+}
+//   ##: SavedResources
+/// Lists the TypeId and name of each resource that was registered with register_saved_resource()
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SavedResources(pub Vec<(std::any::TypeId, &'static str)>);
+/// Registers a resource with bevy_save, and adds it to the SavedResources list for GameEngine::check_type_registry()
+pub trait SavedResourceExt {
+	fn register_saved_resource<T: Resource + bevy::reflect::GetTypeRegistration>(&mut self) -> &mut Self;
+}
+impl SavedResourceExt for App {
+	fn register_saved_resource<T: Resource + bevy::reflect::GetTypeRegistration>(&mut self) -> &mut Self {
+		self.init_resource::<SavedResources>();
+		self.world.resource_mut::<SavedResources>().0.push((std::any::TypeId::of::<T>(), std::any::type_name::<T>()));
+		self.register_saveable::<T>()
+	}
+}
+//   ##: FrameTimes
+/// DEBUG: Holds the timing measurements that are shown on the debug HUD
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameTimes {
+	pub visible:       bool, // If true, the debug HUD will be drawn over the camera view
+	pub last_tick:     Option<Instant>,
+	pub tick_interval: Duration, // The measured time between the two most recent ticks
+	pub update_time:   Duration, // How long the most recent Bevy update took
+	pub render_time:   Duration, // How long the most recent full screen render took
+}
+impl FrameTimes {
+	/// Produces the lines of text for the HUD
+	pub fn describe(&self) -> Vec<String> {
+		vec![
+			format!("tick:   {:>6.1}ms", self.tick_interval.as_secs_f64() * 1000.0),
+			format!("update: {:>6.1}ms", self.update_time.as_secs_f64() * 1000.0),
+			format!("render: {:>6.1}ms", self.render_time.as_secs_f64() * 1000.0),
+		]
+	}
 }
 //   ##: AppResult
 /// Application result type, provides some nice handling if the game crashes
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ratatui::{backend::TestBackend, Terminal};
+
+	#[test]
+	fn items_on_the_floor_are_back_on_the_map_after_a_load() {
+		let mut eng = GameEngine::new_headless(Rect::new(0, 0, 100, 50), 1);
+		eng.init_bevy();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(8, 8));
+		eng.bevy.insert_resource(model);
+		let (posn, carrier_posn) = (Position::new(3, 3, 0), Position::new(5, 5, 0));
+		let wrench = eng.bevy.world.spawn((Description::new().name("wrench"), Body::small(posn, ScreenCell::placeholder()), Portable::empty())).id();
+		let carrier = eng.bevy.world.spawn((Description::new().name("crewman"), Body::small(carrier_posn, ScreenCell::placeholder()))).id();
+		eng.bevy.world.spawn((Description::new().name("keycard"), Body::small(carrier_posn, ScreenCell::placeholder()), Portable::new(carrier)));
+		eng.bevy.world.resource_mut::<WorldModel>().add_contents(&vec![posn], RenderLayer::ITEM, wrench);
+		let snapshot = eng.bevy.world.snapshot();
+		// A load starts over with a fresh world, where everything comes back under a new Entity ID
+		eng.halt_game();
+		eng.init_bevy();
+		for _ in 0..5 { eng.bevy.world.spawn_empty(); }
+		snapshot.applier(&mut eng.bevy.world).despawn(DespawnMode::None).apply().expect("the snapshot should apply");
+		eng.reload_tile_contents();
+		let mut d_query = eng.bevy.world.query::<(Entity, &Description)>();
+		let mut find = |world: &World, name: &str| d_query.iter(world).find(|x| x.1.name == name).map(|x| x.0);
+		let loaded = find(&eng.bevy.world, "wrench").expect("the wrench was not loaded");
+		let loaded_carrier = find(&eng.bevy.world, "crewman").expect("the crewman was not loaded");
+		assert_ne!(loaded, wrench);
+		let model = eng.bevy.world.resource::<WorldModel>();
+		assert_eq!(model.get_contents_at(posn), vec![loaded]);
+		// The carried keycard stays in its carrier's pocket, rather than turning up on the floor
+		assert_eq!(model.get_contents_at(carrier_posn), vec![loaded_carrier]);
+	}
+	#[test]
+	fn unimplemented_commands_stop_a_chain_instead_of_crashing() {
+		let mut eng = GameEngine::new_headless(Rect::new(0, 0, 100, 50), 1);
+		eng.init_bevy();
+		eng.exec_line("connect reactor; status; disconnect");
+		let output: Vec<String> = eng.bevy.world.resource::<MessageLog>().get_log_as_messages("planq", 0)
+			.into_iter().map(|x| x.text).collect();
+		assert!(output.iter().any(|x| x.contains("'connect' is unimplemented")), "{:?}", output);
+		assert!(output.iter().any(|x| x.contains("Stopped at command 1 of 3: 'connect reactor'")), "{:?}", output);
+		// Nothing past the failed command gets to run
+		assert!(!output.iter().any(|x| x.contains("STATUS:")), "{:?}", output);
+		assert!(!output.iter().any(|x| x.contains("'disconnect' is unimplemented")), "{:?}", output);
+	}
+	#[test]
+	fn repeating_an_unimplemented_command_is_refused() {
+		let mut eng = GameEngine::new_headless(Rect::new(0, 0, 100, 50), 1);
+		eng.init_bevy();
+		eng.exec_line("disconnect");
+		eng.exec_line("!!");
+		let output: Vec<String> = eng.bevy.world.resource::<MessageLog>().get_log_as_messages("planq", 0)
+			.into_iter().map(|x| x.text).collect();
+		assert!(output.iter().any(|x| x.contains("Cannot repeat 'disconnect'")), "{:?}", output);
+		assert_eq!(output.iter().filter(|x| x.contains("'disconnect' is unimplemented")).count(), 1, "{:?}", output);
+	}
+	#[test]
+	fn pausing_during_the_planq_boot_does_not_fast_forward_it() {
+		use bevy::{time::TimeUpdateStrategy, utils::Instant};
+		let mut eng = GameEngine::new_headless(Rect::new(0, 0, 120, 50), 1);
+		eng.bevy.insert_resource(Events::<GameEvent>::default())
+			.insert_resource(Events::<PlanqEvent>::default())
+			.insert_resource(GameConfig { fast_boot: false, ..GameConfig::default() })
+			.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string(), "debug".to_string()]))
+			.insert_resource(PlanqData::new())
+			.add_systems(Update, planq_update_system);
+		let player = eng.bevy.world.spawn((Player::default(), Body::small(Position::new(1, 1, 0), ScreenCell::placeholder()))).id();
+		let mut device = Device::new(0);
+		device.power_on();
+		eng.bevy.world.spawn((Planq::new(), device, Portable::new(player)));
+		// The pause lasts for most of the gap between the last update before it and the first one after it
+		let before_pause = Instant::now().checked_sub(Duration::from_secs(10)).expect("the clock should be past 10s");
+		eng.bevy.insert_resource(TimeUpdateStrategy::ManualInstant(before_pause));
+		eng.bevy.update(); // Powers on the PLANQ and starts the first boot stage's timer
+		eng.bevy.insert_resource(TimeUpdateStrategy::ManualInstant(before_pause + Duration::from_secs(1)));
+		eng.bevy.update();
+		eng.pause_game();
+		eng.unpause_game();
+		eng.bevy.insert_resource(TimeUpdateStrategy::ManualInstant(Instant::now()));
+		eng.bevy.update();
+		let planq = eng.bevy.world.resource::<PlanqData>();
+		assert_eq!(planq.cpu_mode, PlanqCPUMode::Startup);
+		assert_eq!(planq.boot_stage, 0);
+		let boot = *planq.proc_table.first().expect("the boot process was never started");
+		let timer = &eng.bevy.world.get::<PlanqProcess>(boot).expect("the boot process went missing").timer;
+		assert!(!timer.finished());
+		assert!(timer.elapsed() < Duration::from_secs(2));
+	}
+	#[test]
+	fn the_compass_follows_its_target_until_the_player_gets_there() {
+		let mut eng = GameEngine::new_headless(Rect::new(0, 0, 100, 50), 1);
+		eng.init_bevy();
+		eng.bevy.insert_resource(Position::new(1, 1, 0));
+		let wrench = eng.bevy.world.spawn((Description::new().name("wrench"), Body::small(Position::new(8, 1, 0), ScreenCell::placeholder()))).id();
+		eng.nav_target = Some(wrench);
+		eng.update_nav_target();
+		assert_eq!(eng.nav_target_info(), Some(("wrench".to_string(), Position::new(8, 1, 0))));
+		// Someone else carries it off, and the compass keeps pointing at it
+		eng.bevy.world.get_mut::<Body>(wrench).expect("the wrench lost its Body").move_to(Position::new(2, 6, 0));
+		eng.update_nav_target();
+		assert_eq!(eng.nav_target_info().map(|x| x.1), Some(Position::new(2, 6, 0)));
+		eng.bevy.insert_resource(Position::new(2, 5, 0));
+		eng.update_nav_target();
+		assert_eq!(eng.nav_target, None);
+		// A target that's destroyed along the way is dropped too
+		eng.nav_target = Some(wrench);
+		eng.bevy.world.despawn(wrench);
+		eng.update_nav_target();
+		assert_eq!(eng.nav_target, None);
+	}
+	#[test]
+	fn rendering_before_the_game_is_set_up_shows_placeholders() {
+		let mut eng = GameEngine::new_headless(Rect::new(0, 0, 120, 50), 1);
+		let mut terminal = Terminal::new(TestBackend::new(120, 50)).expect("could not set up the TestBackend");
+		let screen_text = |terminal: &Terminal<TestBackend>| terminal.backend().buffer().content.iter().map(|x| x.symbol.clone()).collect::<String>();
+		// Leaving standby before any of the game's resources exist, as for the first tick or so of a new game
+		eng.standby = false;
+		terminal.draw(|frame| eng.render(frame)).expect("could not render without any resources");
+		let text = screen_text(&terminal);
+		assert!(text.contains("[no CameraView initialized]"));
+		assert!(text.contains("[no MessageLog initialized]"));
+		// Partway through the setup, with the resources in place but no ship or player yet
+		eng.init_bevy();
+		terminal.draw(|frame| eng.render(frame)).expect("could not render a half-built game");
+	}
+}
+
 // EOF
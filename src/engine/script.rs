@@ -0,0 +1,151 @@
+// engine/script.rs
+// Plays through a game from a script of keypresses and commands, without a terminal, for end-to-end testing
+
+/* The format of a play script, one instruction per line; blank lines and lines starting with '#' are skipped:
+ *   new_game            - builds a new game on a ship generated from SCRIPT_SEED, with the deterministic item placement
+ *   key <name>...       - presses each of the named keys, ie "key h h l" or "key Enter"; see parse_key_name()
+ *   press <action>...   - presses whichever key is bound to each of the named KeyActions, ie "press Open"
+ *   cmd <text>          - runs a command on the PLANQ, as if it was typed into the CLI
+ *   tick [count]        - runs the engine for one or more ticks; keypresses take effect on the next tick
+ *   expect_msg <text>   - fails unless a message in the world channel contains the text
+ *   expect_posn x,y,z   - fails unless the player is standing at the given position
+ *   expect_room <name>  - fails unless the player is in the named room
+ */
+
+//  ###: EXTERNAL LIBRARIES
+use std::fs;
+use std::str::FromStr;
+use bevy::prelude::With;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::Rect;
+
+//  ###: INTERNAL LIBRARIES
+use crate::components::{
+	Body,
+	Description,
+	Player,
+	Position,
+	TurnCount,
+};
+use crate::engine::{
+	GameEngine,
+	handler::{key_parser, planq_parser},
+	keybinds::{parse_key_name, KeyAction},
+	messagelog::MessageLog,
+	mirror::plain_text,
+};
+
+//  ###: CONSTANTS
+/// The screen size that the headless engine pretends to have, which is the smallest that the game allows
+pub const SCRIPT_SCREEN_SIZE: Rect = Rect { x: 0, y: 0, width: 80, height: 40 };
+/// The seed for the ship layout and the RNG, so that a script plays out the same way every time it's run
+/// The player's own settings are ignored in favor of the defaults, for the same reason
+pub const SCRIPT_SEED: u64 = 1701;
+
+//  ###: SIMPLE TYPES AND HELPERS
+/// Reads the play script option from the command line, ie `spacegame --script tests/open_locker.txt`
+pub fn script_from_args(args: &[String]) -> Option<String> {
+	let position = args.iter().position(|x| x == "--script")?;
+	args.get(position + 1).cloned()
+}
+/// Runs the play script at the given path on a new headless GameEngine, and then prints out the final game state
+/// Returns an error naming the line of the first instruction that could not be run or whose expectation failed
+pub fn run_script(filename: &str) -> Result<(), String> {
+	for line in play_script(filename)?.iter() {
+		println!("{}", line);
+	}
+	Ok(())
+}
+/// As run_script(), but hands back the description of the final game state instead of printing it
+pub fn play_script(filename: &str) -> Result<Vec<String>, String> {
+	let script = fs::read_to_string(filename).map_err(|e| format!("could not read the script at {}: {}", filename, e))?;
+	let mut eng = GameEngine::new_headless(SCRIPT_SCREEN_SIZE, SCRIPT_SEED);
+	for (index, line) in script.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') { continue; }
+		let (instruction, args) = line.split_once(' ').unwrap_or((line, ""));
+		run_instruction(&mut eng, instruction, args.trim()).map_err(|e| format!("{}:{}: {}: {}", filename, index + 1, line, e))?;
+	}
+	Ok(describe_state(&mut eng))
+}
+/// Carries out a single line of a play script
+fn run_instruction(eng: &mut GameEngine, instruction: &str, args: &str) -> Result<(), String> {
+	match instruction {
+		"new_game" => { eng.new_game(); }
+		"key" => {
+			for name in args.split(' ').filter(|x| !x.is_empty()) {
+				let key = parse_key_name(name).ok_or(format!("unknown key '{}'", name))?;
+				press_key(eng, key)?;
+			}
+		}
+		"press" => {
+			for name in args.split(' ').filter(|x| !x.is_empty()) {
+				let action = KeyAction::from_str(name).map_err(|_| format!("unknown action '{}'", name))?;
+				let key = *eng.keys.keys_for(action).first().ok_or(format!("nothing is bound to {}", name))?;
+				press_key(eng, key)?;
+			}
+		}
+		"cmd" => {
+			if !eng.exec(planq_parser(args)) { return Err("the PLANQ command failed".to_string()); }
+		}
+		"tick" => {
+			let count = if args.is_empty() { 1 } else { args.parse::<usize>().map_err(|_| format!("bad tick count '{}'", args))? };
+			for _ in 0..count { eng.tick(); }
+		}
+		"expect_msg" => {
+			let messages = eng.bevy.world.get_resource::<MessageLog>().map(|x| x.get_log_as_messages("world", 0)).unwrap_or_default();
+			if !messages.iter().any(|x| plain_text(&x.text).contains(args)) {
+				let last = messages.last().map(|x| plain_text(&x.text)).unwrap_or_default();
+				return Err(format!("no message contains '{}'; the last one was '{}'", args, last));
+			}
+		}
+		"expect_posn" => {
+			let target: Position = args.into();
+			let posn = player_body(eng).map(|x| x.0).ok_or("there is no player")?;
+			if posn != target { return Err(format!("the player is at {}", posn)); }
+		}
+		"expect_room" => {
+			let room = player_body(eng).map(|x| x.1).ok_or("there is no player")?;
+			if room != args { return Err(format!("the player is in the {}", room)); }
+		}
+		_ => { return Err(format!("unknown instruction '{}'", instruction)); }
+	}
+	Ok(())
+}
+/// Sends a keypress to the key_parser, just as the TUI would
+fn press_key(eng: &mut GameEngine, key: KeyCode) -> Result<(), String> {
+	key_parser(KeyEvent::new(key, KeyModifiers::NONE), eng).map_err(|e| e.to_string())
+}
+/// Gets the player's position and the name of the room that they're in
+fn player_body(eng: &mut GameEngine) -> Option<(Position, String)> {
+	let mut p_query = eng.bevy.world.query_filtered::<(&Body, &Description), With<Player>>();
+	p_query.get_single(&eng.bevy.world).ok().map(|(body, desc)| (body.ref_posn, desc.locn.clone()))
+}
+/// Describes the end state of the game, for the script runner's output
+fn describe_state(eng: &mut GameEngine) -> Vec<String> {
+	let mut output = vec![format!("Mode: {:?}", eng.mode)];
+	if let Some((posn, room)) = player_body(eng) {
+		output.push(format!("Player: {} in the {}", posn, room));
+	}
+	output.push(format!("Turns: {}", eng.bevy.world.get_resource::<TurnCount>().map(|x| x.turns).unwrap_or(0)));
+	let messages = eng.bevy.world.get_resource::<MessageLog>().map(|x| x.get_log_as_messages("world", 0)).unwrap_or_default();
+	for message in messages.iter() {
+		output.push(format!("Message: {}", plain_text(&message.text)));
+	}
+	output
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn example_script_passes_and_plays_out_the_same_way_twice() {
+		let filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scripts/walk_the_hallway.txt");
+		let first = play_script(filename).expect("the example script failed");
+		let second = play_script(filename).expect("the example script failed on the second run");
+		assert_eq!(first, second);
+	}
+}
+
+// EOF
@@ -7,6 +7,7 @@ use std::io;
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
+use simplelog::*;
 use crossterm::event::{
 	self,
 	Event as CrosstermEvent,
@@ -34,6 +35,16 @@ use ratatui::{
 // ###: INTERNAL LIBRARIES
 use crate::engine::{AppResult, GameEngine};
 
+// ###: CONSTANTS
+/// The default interval between engine ticks, in milliseconds
+/// Shorter ticks make the time-based systems (clock, battery, NPC turns) update more smoothly but cost more CPU,
+/// longer ticks are easier on the CPU but make those systems, and the game's response to input, more sluggish
+pub const DEFAULT_TICK_RATE: u64 = 250;
+/// The shortest allowed tick interval; anything faster than ~60 updates/sec just burns CPU on redraws
+pub const MIN_TICK_RATE: u64 = 16;
+/// The longest allowed tick interval; past this point the game clock and PLANQ visibly stutter
+pub const MAX_TICK_RATE: u64 = 2000;
+
 //  ###: UIGrid
 /// Provides a bunch of named fields (rather than a tuple) of grid components
 /// # Fields
@@ -46,6 +57,8 @@ use crate::engine::{AppResult, GameEngine};
 /// * `planq_stdin`     The PLANQ's CLI input box
 /// * 'p_status_height' Sets the height of the status bar widget
 /// * 'p_stdin_height'  Sets the height of the CLI input widget
+/// * 'msg_height'      Sets the height of the message log pane
+/// * 'sidebar_width'   Sets the width of the PLANQ sidebar
 pub struct UIGrid {
 	/// Provides the main view onto the worldmap
 	pub camera_main:      Rect,
@@ -64,7 +77,11 @@ pub struct UIGrid {
 	/// Sets the height of the planq_status widget, will be updated during gameplay
 	pub p_status_height:  usize,
 	/// Sets the height of the planq's CLI widget
-	pub p_stdin_height:   usize
+	pub p_stdin_height:   usize,
+	/// Sets the height of the message log pane
+	pub msg_height:       u16,
+	/// Sets the width of the PLANQ sidebar
+	pub sidebar_width:    u16,
 }
 impl UIGrid {
 	pub fn new() -> UIGrid {
@@ -78,6 +95,8 @@ impl UIGrid {
 			planq_stdin: Rect::default(),
 			p_status_height: 0,
 			p_stdin_height: 1,
+			msg_height: 12,
+			sidebar_width: 32,
 		}
 	}
 	/// Recalculates the PLANQ's layout based on its stored size
@@ -144,12 +163,12 @@ impl UIGrid {
 		// Split the entire window between [1/2](0) and [3](1) horizontally
 		let main_horiz_split = Layout::default()
 			.direction(Direction::Horizontal)
-			.constraints([Constraint::Min(30), Constraint::Length(32)].as_ref())
+			.constraints([Constraint::Min(30), Constraint::Length(self.sidebar_width)].as_ref())
 			.split(max_area).to_vec();
 		// Split [1](0) and [2](1) vertically
 		let camera_worldmsg_split = Layout::default()
 			.direction(Direction::Vertical)
-			.constraints([Constraint::Min(30), Constraint::Length(12)].as_ref())
+			.constraints([Constraint::Min(30), Constraint::Length(self.msg_height)].as_ref())
 			.split(main_horiz_split[0]).to_vec();
 		// Update the UIGrid itself to hold the new sizes
 		self.camera_main = camera_worldmsg_split[0];
@@ -260,6 +279,28 @@ impl TuiEventHandler {
 	pub fn next(&self) -> AppResult<TuiEvent> {
 		Ok(self.receiver.recv()?)
 	}
+	/// Receive the next event from the handler thread, if there's one waiting already.
+	///
+	/// Unlike next(), this will never block, so it can be used to empty out the queue.
+	pub fn try_next(&self) -> Option<TuiEvent> {
+		self.receiver.try_recv().ok()
+	}
+}
+/// Reads the tick interval from the command line, ie `spacegame --tick-rate 100`
+/// Falls back to the given default if the option is missing or unreadable, and clamps it to the allowed range
+pub fn tick_rate_from_args(args: &[String], default: u64) -> u64 {
+	let default = default.clamp(MIN_TICK_RATE, MAX_TICK_RATE);
+	let position = if let Some(index) = args.iter().position(|x| x == "--tick-rate") { index } else {
+		return default;
+	};
+	let rate = if let Some(Ok(value)) = args.get(position + 1).map(|x| x.parse::<u64>()) { value } else {
+		warn!("--tick-rate needs a number of milliseconds, using the default of {}ms", default);
+		return default;
+	};
+	if !(MIN_TICK_RATE..=MAX_TICK_RATE).contains(&rate) {
+		warn!("--tick-rate must be between {} and {}ms, got {}ms", MIN_TICK_RATE, MAX_TICK_RATE, rate);
+	}
+	rate.clamp(MIN_TICK_RATE, MAX_TICK_RATE)
 }
 //  ###: TuiEvent
 /// Defines the set of interface events in the TUI
@@ -6,7 +6,7 @@ use ratatui::{
 	buffer::Buffer,
 	widgets::{Block, Widget},
 	layout::{Alignment, Rect},
-	style::{Color::Indexed, Style},
+	style::{Color::Indexed, Modifier, Style},
 };
 
 //  ###: INTERNAL LIBRARIES
@@ -14,6 +14,12 @@ use crate::worldmap::xy_to_index;
 use crate::camera::CameraView;
 
 //  ###: Viewport
+/// Draws a CameraView into a ratatui Buffer, one ScreenCell per terminal cell
+/// The CameraView must be exactly the size of the area that it's drawn into, border included: each cell of the view
+/// is drawn at the same x, y in the Buffer, which means the area must start at the origin, and any Block border
+/// simply covers up the outer ring of the view
+/// The Viewport only copies cells, so it can be checked by rendering into a ratatui::backend::TestBackend with a
+/// handmade CameraView, without any of the Bevy systems that normally fill it in
 pub struct Viewport<'a> {
 	source: &'a CameraView,
 	// these are the tui-rs attributes
@@ -44,7 +50,8 @@ impl<'a> Widget for Viewport<'a> {
 		for map_y in area.top()..area.bottom() {        // Hooray
 			for map_x in area.left()..area.right() {      // for 1:1 mapping!
 				let index = xy_to_index(map_x.into(), map_y.into(), self.source.width as usize);
-				let tilestyle = Style::default().fg(Indexed(self.source.output[index].fg)).bg(Indexed(self.source.output[index].bg));
+				let tilestyle = Style::default().fg(Indexed(self.source.output[index].fg)).bg(Indexed(self.source.output[index].bg))
+					.add_modifier(Modifier::from_bits_truncate(self.source.output[index].modifier));
 				buf.set_string(map_x, map_y, &self.source.output[index].glyph, tilestyle);
 			}
 		}
@@ -78,4 +85,103 @@ impl <'a> Viewport<'a> {
 	}
 }
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bevy::app::{App, Update};
+	use bracket_pathfinding::prelude::Point;
+	use ratatui::{backend::TestBackend, Terminal};
+	use crate::camera::{camera_update_system, ScreenCell};
+	use crate::components::{Body, Color, Memory, Player, Position, Viewshed};
+	use crate::worldmap::{Tile, WorldMap, WorldModel};
+
+	/// Builds a single deck of the given size, walled in around its edges and with open floor inside
+	fn walled_deck(width: usize, height: usize) -> WorldModel {
+		let mut level = WorldMap::new(width, height);
+		for y in 0..height as i32 {
+			for x in 0..width as i32 {
+				if x == 0 || y == 0 || x == width as i32 - 1 || y == height as i32 - 1 {
+					let index = level.to_index(x, y);
+					level.tiles[index] = Tile::new_wall();
+				}
+			}
+		}
+		let mut model = WorldModel::default();
+		model.add_level(level);
+		model
+	}
+	/// Runs the camera over the model with the player standing at p_posn and able to see the whole deck, then
+	/// draws the resulting CameraView into a TestBackend of the same size and hands back its Buffer
+	fn render_view(model: WorldModel, p_posn: Position, width: u16, height: u16) -> Buffer {
+		let mut viewshed = Viewshed::new(99);
+		let level = &model.levels[p_posn.z as usize];
+		for y in 0..level.height as i32 {
+			for x in 0..level.width as i32 {
+				viewshed.visible_points.push(Point::new(x, y));
+			}
+		}
+		let mut app = App::new();
+		app.insert_resource(model)
+			.insert_resource(p_posn)
+			.insert_resource(CameraView::new(width as i32, height as i32))
+			.add_systems(Update, camera_update_system);
+		let player_cell = ScreenCell::create("@", Color::LtYellow, Color::Black, 0);
+		app.world.spawn((Player::default(), Body::small(p_posn, player_cell), viewshed, Memory::new()));
+		app.update();
+		let camera = app.world.resource::<CameraView>();
+		let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("could not set up the TestBackend");
+		terminal.draw(|frame| frame.render_widget(Viewport::new(camera), frame.size())).expect("could not draw the Viewport");
+		terminal.backend().buffer().clone()
+	}
+
+	#[test]
+	fn player_is_drawn_at_the_center_with_walls_at_the_edges() {
+		let buf = render_view(walled_deck(7, 5), Position::new(3, 2, 0), 7, 5);
+		let player = buf.get(3, 2);
+		assert_eq!(player.symbol, "@");
+		assert_eq!(player.fg, Indexed(Color::LtYellow as u8));
+		assert_eq!(player.bg, Indexed(Color::Black as u8));
+		for (x, y) in [(0, 0), (6, 0), (0, 4), (6, 4), (3, 0), (0, 2)] {
+			let wall = buf.get(x, y);
+			assert_eq!(wall.symbol, "╳", "no wall at {}, {}", x, y);
+			assert_eq!(wall.fg, Indexed(Color::White as u8));
+		}
+		let floor = buf.get(2, 2);
+		assert_eq!(floor.symbol, ".");
+		assert_eq!(floor.fg, Indexed(Color::LtBlack as u8));
+	}
+	#[test]
+	fn player_near_a_map_edge_is_drawn_off_center() {
+		// The view stops scrolling at the edge of the map, so the player moves toward the corner of the view instead
+		// and the edge of the map still fills the view, rather than any out-of-bounds cells
+		let buf = render_view(walled_deck(20, 10), Position::new(1, 1, 0), 7, 5);
+		assert_eq!(buf.get(1, 1).symbol, "@");
+		assert_eq!(buf.get(3, 2).symbol, ".");
+		for x in 0..7 {
+			assert_eq!(buf.get(x, 0).symbol, "╳");
+		}
+		for y in 0..5 {
+			assert_eq!(buf.get(0, y).symbol, "╳");
+		}
+		for y in 0..5 {
+			for x in 0..7 {
+				assert_ne!(buf.get(x, y).symbol, "*", "out-of-bounds cell at {}, {}", x, y);
+			}
+		}
+	}
+	#[test]
+	fn text_modifiers_are_carried_into_the_buffer() {
+		let mut camera = CameraView::new(3, 1);
+		camera.output[0] = ScreenCell::new_from_str_vec(vec!["B", "white", "black", "bold"]);
+		camera.output[1] = ScreenCell::new_from_str_vec(vec!["I", "white", "black", "mod:+italic,+reverse"]);
+		camera.output[2] = ScreenCell::new_from_str_vec(vec!["p", "white", "black"]);
+		let mut terminal = Terminal::new(TestBackend::new(3, 1)).expect("could not set up the TestBackend");
+		terminal.draw(|frame| frame.render_widget(Viewport::new(&camera), frame.size())).expect("could not draw the Viewport");
+		let buf = terminal.backend().buffer();
+		assert_eq!(buf.get(0, 0).modifier, Modifier::BOLD);
+		assert_eq!(buf.get(1, 0).modifier, Modifier::ITALIC | Modifier::REVERSED);
+		assert_eq!(buf.get(2, 0).modifier, Modifier::empty());
+	}
+}
+
 // EOF
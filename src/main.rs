@@ -14,6 +14,8 @@ use spacegame::engine::{
 	GameEngine,
 	handler::key_parser,
 	menu::*,
+	mirror::text_mirror_from_args,
+	script::{run_script, script_from_args},
 	tui::*,
 	tui::TuiEvent, // this line is required for disambiguiation vs Bevy
 };
@@ -29,6 +31,11 @@ fn main() -> AppResult<()> {
 	//debug!("This is a test debug message"); // Level::Debug, will include some debug context info prepended to the message
 	//trace!("This is a test trace message"); // Level::Trace, will include any trace debug info from other modules that support it!
 	std::env::set_var("RUST_BACKTRACE", "1"); // DEBUG: enables backtrace on program crash
+	let args: Vec<String> = std::env::args().collect();
+	//  ##: If a play script was given, run it without a terminal and then exit
+	if let Some(script_file) = script_from_args(&args) {
+		return run_script(&script_file).map_err(|e| e.into());
+	}
 	//  ##: Set up ratatui
 	let backend = CrosstermBackend::new(io::stdout());
 	let terminal = Terminal::new(backend)?;
@@ -42,24 +49,37 @@ fn main() -> AppResult<()> {
 		// throw a bigtime error and bailout if the terminal is too small
 		return Err(format!("Terminal dimensions are too small: {}x{} (80x40 min)", tsize.width, tsize.height).into());
 	}
+	//  ##: Set up the game engine
+	let mut eng = GameEngine::new(tsize);
 	//  ##: Finish setup of ratatui
-	let events = TuiEventHandler::new(250);
+	let events = TuiEventHandler::new(tick_rate_from_args(&args, eng.config.tick_rate));
+	eng.text_mirror = text_mirror_from_args(&args);
 	let mut tui = Tui::new(terminal, events);
 	tui.init()?;
-	//  ##: Set up the game engine
-	let mut eng = GameEngine::new(tsize);
 	//  ##: Start the game loop
 	eng.running = true;
 	eng.set_menu(MenuType::Main, (30, 15));
+	let mut redraw = true;
 	while eng.running {
-		// Render the game interface and contents
-		tui.draw(&mut eng)?;
-		// Handle input events
-		match tui.events.next()? {
-			TuiEvent::Tick           => eng.tick(),
-			TuiEvent::Key(key_event) => key_parser(key_event, &mut eng)?,
-			TuiEvent::Mouse(_)       => { }
-			TuiEvent::Resize(_, _)   => { }
+		// Render the game interface and contents, but only if something might have changed
+		if redraw {
+			tui.draw(&mut eng)?;
+			redraw = false;
+		}
+		// Wait for something to happen, then handle any keypresses that piled up in the meantime, so that input
+		// gets handled (and redrawn) as soon as it arrives instead of waiting in line behind the tick timer
+		let mut next_event = Some(tui.events.next()?);
+		while let Some(event) = next_event {
+			// Mouse events are ignored, so they don't need a redraw; with mouse capture on, there's a lot of them
+			match event {
+				TuiEvent::Tick           => { eng.tick(); redraw = true; }
+				TuiEvent::Key(key_event) => { key_parser(key_event, &mut eng)?; redraw = true; }
+				TuiEvent::Mouse(_)       => { }
+				TuiEvent::Resize(_, _)   => { eng.layout_changed = true; redraw = true; }
+			}
+			// Always redraw after a tick so that every update gets shown, ie the new-game loading screen
+			if !eng.running || matches!(event, TuiEvent::Tick) { break; }
+			next_event = tui.events.try_next();
 		}
 	}
 	//  ##: The game loop has stopped, so exit the program
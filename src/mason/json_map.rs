@@ -21,10 +21,19 @@ use crate::mason::*;
  *        corner - array of 3 integers (-> 1 Position)
  *        width  - integer, width of room incl walls(?)
  *        height - integer, height of room incl walls(?)
+ *        description - string, optional, shown to the player when they first enter the room
+ *        npcs   - array, optional, of the NPCs who start out in this room:
+ *          name - string
+ *          kind - string, optional, the name of the creature defn to build them from
+ *          role - string, optional, the name of a CrewRole if the NPC is one of the crew
+ *          posn - array of 3 integers (-> 1 Position), optional, else a free spot in the room is picked
  *      portals:
  *        name   - string
  *        points - array of 2 arrays of 3 integers (-> 2 Positions)
  *        twoway - bool, determines if the portal can be used in the reverse direction
+ *    sections    - array of strings, optional, more layout files that each contribute a part of the ship
+ *    first_level - integer, optional, the deck that a section's first map becomes; its rooms and ladders use the
+ *                  ship's deck numbers, not its own
  *  }
  */
 /* The hierarchy of the Model object:
@@ -46,10 +55,13 @@ use crate::mason::*;
 //   ##: JsonBucket
 /// Data structure that maps to the JSON as laid out in the map generator for fast deserialization
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct JsonBucket {
 	pub map_list: Vec<JsonMap>,
 	pub room_list: Vec<JsonRoom>,
 	pub ladder_list: Vec<JsonPortal>,
+	pub sections: Vec<String>, // Other layout files to stitch onto this one, relative to this file's directory
+	pub first_level: usize, // The deck that this file's first map becomes, when it's one section of a larger ship
 }
 //   ##: JsonRoom
 /// A JSON-formatted representation of a room
@@ -61,6 +73,12 @@ pub struct JsonRoom {
 	pub width: usize,
 	pub height: usize,
 	pub contents: Vec<(String, u32)>, // the name of the item and how many to spawn
+	#[serde(default)]
+	pub color: Option<String>, // name of a color to theme the room's floors and walls with
+	#[serde(default)]
+	pub description: Option<String>, // a short blurb about the room for the player's first visit
+	#[serde(default)]
+	pub npcs: Vec<JsonNpc>, // the NPCs who start out in this room
 }
 impl Default for JsonRoom {
 	fn default() -> JsonRoom {
@@ -71,6 +89,9 @@ impl Default for JsonRoom {
 			width: 0,
 			height: 0,
 			contents: Vec::new(),
+			color: None,
+			description: None,
+			npcs: Vec::new(),
 		}
 	}
 }
@@ -99,6 +120,18 @@ impl JsonRoom {
 		self.corner[2]
 	}
 }
+//   ##: JsonNpc
+/// A JSON-formatted request for an NPC to be placed in a room
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct JsonNpc {
+	pub name: String,
+	#[serde(default)]
+	pub kind: Option<String>,
+	#[serde(default)]
+	pub role: Option<String>,
+	#[serde(default)]
+	pub posn: Option<Vec<usize>>,
+}
 //   ##: JsonPortal
 /// A JSON-formatted representation of a door or other room-connecting passageway
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -23,6 +23,7 @@ use bevy_turborand::GlobalRng;
 use bevy_turborand::DelegatedRng;
 
 //  ###: INTERNAL LIBRARIES
+use crate::camera::color_from_name;
 use crate::mason::*;
 
 //  ###: COMPLEX TYPES
@@ -82,18 +83,30 @@ impl SpawnTemplate {
 		}
 	}
 	/// Replaces the IDs in a SpawnTemplate with the item names in a RawItemSet's contents list
-	pub fn assign_names(&mut self, name_list: Vec<(String, String)>) {
+	/// Returns false and logs the mismatch if any placement slot went unnamed or any named item has no slot
+	pub fn assign_names(&mut self, set_name: &str, name_list: Vec<(String, String)>) -> bool {
 		// name_list's values are (id, name) as per defn from furniture_groups_v1.json
 		// NOTE: you can't get mutable refs out of a Rust vector unless it was created that way
 		// so trying to get mutable refs into a tuple binding will always fail unless the vector was
 		// initialized with, eg, vec![&String] and NOT vec![String]
 		// For every occupied tile in the template,
 		//eprintln!("* recvd name_list: {:?}", name_list); // DEBUG: log received name_list
+		let mut unnamed_slots = Vec::new();
 		for item in self.output.iter_mut() {
 			if let Some(new_name) = name_list.iter().find(|x| x.0 == item.0) { // If it matches a tile in the defn,
 				item.1 = new_name.1.clone(); // Assign it a real name
+			} else {
+				unnamed_slots.push(item.0.clone());
 			}
 		}
+		let unplaced_names: Vec<String> = name_list.iter()
+			.filter(|x| !self.output.iter().any(|slot| slot.0 == x.0))
+			.map(|x| format!("{}:{}", x.0, x.1))
+			.collect();
+		if unnamed_slots.is_empty() && unplaced_names.is_empty() { return true; }
+		warn!("* item set '{}' does not match its shape: {} slots for {} items; unnamed slots: {:?}, unplaced items: {:?}",
+			set_name, self.output.len(), name_list.len(), unnamed_slots, unplaced_names);
+		false
 	}
 }
 impl From<Vec<Vec<String>>> for SpawnTemplate {
@@ -162,6 +175,49 @@ impl ShipGraph {
 		let first_outgoing_door = self.rooms[source].first_outgoing_door;
 		Successors { graph: self, current_door_index: first_outgoing_door }
 	}
+	/// Takes the rooms on the given deck out of the layout, along with any doors that lead to or from them
+	/// The rooms and doors on the decks above are moved down by one, to match WorldModel::remove_level
+	pub fn remove_deck(&mut self, z_level: i32) {
+		let old_rooms = std::mem::take(&mut self.rooms);
+		let old_doors = std::mem::take(&mut self.doors);
+		// Work out where each of the remaining rooms will end up first, so that the doors can be pointed at them
+		let mut new_index: Vec<Option<RoomIndex>> = Vec::new();
+		let mut kept = 0;
+		for room in old_rooms.iter() {
+			if room.centerpoint.z == z_level {
+				new_index.push(None);
+			} else {
+				new_index.push(Some(kept));
+				kept += 1;
+			}
+		}
+		let lower = |mut posn: Position| { if posn.z > z_level { posn.z -= 1; } posn };
+		for (index, mut room) in old_rooms.into_iter().enumerate() {
+			if new_index[index].is_none() { continue; }
+			let mut door_list = Vec::new();
+			let mut next_door = room.first_outgoing_door.take();
+			while let Some(door_index) = next_door {
+				door_list.push(door_index);
+				next_door = old_doors[door_index].next_outgoing_door;
+			}
+			if room.centerpoint.z > z_level { room.set_deck(room.centerpoint.z - 1); }
+			let go_from = self.add_room(room);
+			// The doors get pushed onto the front of the list, so go backwards to keep them in their old order
+			for door_index in door_list.into_iter().rev() {
+				let old_door = &old_doors[door_index];
+				let go_to = if let Some(value) = new_index[old_door.target] { value } else { continue; };
+				let door_index = self.doors.len();
+				self.doors.push(GraphDoor {
+					name: old_door.name.clone(),
+					from: lower(old_door.from),
+					to: lower(old_door.to),
+					target: go_to,
+					next_outgoing_door: self.rooms[go_from].first_outgoing_door,
+				});
+				self.rooms[go_from].first_outgoing_door = Some(door_index);
+			}
+		}
+	}
 	/// Tests whether the specified Room is listed in the layout
 	pub fn contains(&self, target: &str) -> Option<usize> {
 		for (index, room) in self.rooms.iter().enumerate() {
@@ -180,6 +236,23 @@ impl ShipGraph {
 		}
 		None
 	}
+	/// Provides a short blurb about the named room for when the player first walks into it
+	/// Rooms that weren't given a description in the ship layout just get their name announced
+	pub fn get_room_blurb(&self, room_name: &str) -> String {
+		match self.rooms.iter().find(|x| x.name == room_name).and_then(|x| x.description.clone()) {
+			Some(blurb) => { blurb }
+			None => { format!("You look around the {}.", room_name) }
+		}
+	}
+	/// Gets the CellType at the given Position from the logical map of whichever Room contains it
+	pub fn get_celltype_at(&self, target: Position) -> Option<CellType> {
+		for room in &self.rooms {
+			if let Some(cell) = room.new_interior.get(&target) {
+				return Some(*cell);
+			}
+		}
+		None
+	}
 	/// Gets the RoomIndex of the named Room
 	pub fn get_room_index(&self, target: &str) -> Option<RoomIndex> {
 		self.rooms.iter().position(|x| x.name == target)
@@ -229,6 +302,15 @@ impl ShipGraph {
 			false
 		}
 	}
+	/// Marks the given Positions as taken in the logical maps of whichever rooms contain them, so that nothing else
+	/// gets placed on top of them later on
+	pub fn reserve_cells(&mut self, targets: &[Position]) {
+		for target in targets.iter() {
+			if let Some(room) = self.rooms.iter_mut().find(|x| x.contains(*target)) {
+				room.new_interior.insert(*target, CellType::Closed);
+			}
+		}
+	}
 	/// Returns the list of all rooms currently listed in the internal graph
 	pub fn get_room_list(&self) -> Vec<String> {
 		self.rooms.iter().map(|x| x.name.clone()).collect()
@@ -251,6 +333,8 @@ pub struct GraphRoom {
 	pub centerpoint: Position, // We prefer centerpoint over corner so that we can discern relative spatial locations
 	pub ul_corner: Position,
 	pub dr_corner: Position,
+	pub tint: Option<u8>, // If set, the room's floors and walls are drawn in this color
+	pub description: Option<String>, // Shown to the player the first time they enter the room
 }
 impl Default for GraphRoom {
 	fn default() -> GraphRoom {
@@ -262,6 +346,8 @@ impl Default for GraphRoom {
 			centerpoint: Position::INVALID,
 			ul_corner: Position::INVALID,
 			dr_corner: Position::INVALID,
+			tint: None,
+			description: None,
 		}
 	}
 }
@@ -310,15 +396,58 @@ impl From<JsonRoom> for GraphRoom {
 			centerpoint: center.into(),
 			ul_corner: (ul_wall.0, ul_wall.1, z_level).into(),
 			dr_corner: (dr_wall.0, dr_wall.1, z_level).into(),
+			tint: new_room.color.as_deref().and_then(color_from_name),
+			description: new_room.description.clone(),
 		}
 	}
 }
 impl GraphRoom {
+	/// Creates a room from an irregular set of floor tiles, ie a hallway that's several tiles wide or has corners
+	/// Every tile next to the floor that isn't part of it is treated as a Wall, so the room's walls follow its shape
+	pub fn from_tiles(new_name: &str, tiles: Vec<Position>) -> GraphRoom {
+		let mut new_map: HashMap<Position, CellType> = HashMap::new();
+		for tile in tiles.iter() {
+			new_map.insert(*tile, CellType::Open);
+		}
+		for tile in tiles.iter() {
+			for y_diff in -1..=1 {
+				for x_diff in -1..=1 {
+					let neighbor = Position::new(tile.x + x_diff, tile.y + y_diff, tile.z);
+					new_map.entry(neighbor).or_insert(CellType::Wall);
+				}
+			}
+		}
+		let z_level = tiles.first().map(|x| x.z).unwrap_or(0);
+		let ul_corner = new_map.keys().fold(Position::new(i32::MAX, i32::MAX, z_level), |acc, x| Position::new(acc.x.min(x.x), acc.y.min(x.y), z_level));
+		let dr_corner = new_map.keys().fold(Position::new(i32::MIN, i32::MIN, z_level), |acc, x| Position::new(acc.x.max(x.x), acc.y.max(x.y), z_level));
+		// The middle of the bounding box might be out in a wall if the room has corners, so use the floor tile nearest to it
+		let middle = Position::new((ul_corner.x + dr_corner.x) / 2, (ul_corner.y + dr_corner.y) / 2, z_level);
+		let centerpoint = tiles.iter().min_by_key(|x| (x.x - middle.x).pow(2) + (x.y - middle.y).pow(2)).copied().unwrap_or(Position::INVALID);
+		GraphRoom {
+			name: new_name.to_string(),
+			interior: tiles,
+			new_interior: new_map,
+			centerpoint,
+			ul_corner,
+			dr_corner,
+			..GraphRoom::default()
+		}
+	}
 	/// Returns True if the specified Position is within the walls of the called Room
 	pub fn contains(&self, target: Position) -> bool {
 		//self.interior.contains(&target) || self.new_interior.contains_key(&target)
 		self.new_interior.contains_key(&target)
 	}
+	/// Moves the whole room onto the given deck, without changing its shape or its x,y position
+	pub fn set_deck(&mut self, z_level: i32) {
+		for posn in self.interior.iter_mut() {
+			posn.z = z_level;
+		}
+		self.new_interior = self.new_interior.drain().map(|(mut posn, cell)| { posn.z = z_level; (posn, cell) }).collect();
+		self.centerpoint.z = z_level;
+		self.ul_corner.z = z_level;
+		self.dr_corner.z = z_level;
+	}
 	pub fn set_interior_to(&mut self, new_interior: Vec<Position>) {
 		self.interior = new_interior;
 	}
@@ -352,7 +481,7 @@ impl GraphRoom {
 	}
 	/// Locates an open space to spawn an item given its associated SpawnTemplate; if successful,
 	/// returns the set of occupied Positions and the SpawnTemplate IDs that correspond to them
-	pub fn find_open_space(&mut self, mut template: SpawnTemplate, rng: &mut GlobalRng) -> Option<Vec<(String, Position)>> {
+	pub fn find_open_space(&mut self, mut template: SpawnTemplate, rng: &mut GlobalRng, mode: PlacementMode) -> Option<Vec<(String, Position)>> {
 		// METHOD
 		// given the template as input, and the destination as the target,
 		// choose a random point in the destination to start at
@@ -360,14 +489,21 @@ impl GraphRoom {
 		// if at any point there is a failure to match, try a new point
 		// repeat until either a valid starting point is found,
 		// or all starting points are exhausted
-		let possible_starts: Vec<Position> = self.new_interior.iter() // All points in the interior of the room...
+		let mut possible_starts: Vec<Position> = self.new_interior.iter() // All points in the interior of the room...
 			.filter(|x| *x.1 == template.shape[0].1 || *x.1 == CellType::Open) // ...which have the same CellType as the template's first point, or are Open...
 			.map(|x| *x.0).collect(); // ...mapped into a Vec<Position> and gathered up
 		if possible_starts.is_empty() { return None; } // Early return if there were no candidate points at all
+		// The interior is a HashMap, so its order can't be relied upon; sorting makes the Deterministic mode repeatable
+		// Position's own ordering is (x, y, z), so the key is given explicitly to fill the room a row at a time
+		possible_starts.sort_by_key(|p| (p.z, p.y, p.x));
+		let mut ordered_starts = possible_starts.iter();
 		// start with a list of all points that match the type of the template's ref_point
 		// choose a point in the list randomly
 		//for s_point in rng.sample_iter(possible_starts.iter()) {
-		while let Some(ref_point) = rng.sample_iter(possible_starts.iter()) {
+		while let Some(ref_point) = match mode {
+			PlacementMode::Random        => { rng.sample_iter(possible_starts.iter()) }
+			PlacementMode::Deterministic => { ordered_starts.next() }
+		} {
 			// TODO: ->> "choose from one of a set of loaded template shapes"
 			for (t_point, t_type, t_success) in template.shape.iter_mut() {
 				// Derive the next Position to examine
@@ -495,9 +631,62 @@ pub enum CellType {
 	Wall, // A Cell that is blocked by something terrain-ish, like a Wall
 	Margin, // A Cell that must remain Open, ie cannot have an occupant
 }
+//   ##: PlacementMode
+/// Decides how items get placed during map generation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum PlacementMode {
+	#[default]
+	Random, // Shapes and spawnpoints are sampled using the RNG
+	Deterministic, // Always uses the first shape, and the first valid spawnpoint in (z, y, x) order; for testing
+}
 //   ##: RoomIndex, DoorIndex
 /// Simple type aliases to provide some type guarantees for these classes
 pub type RoomIndex = usize; // An index to a GraphRoom
 pub type DoorIndex = usize; // An index to a GraphDoor
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn deterministic_placement_fills_the_room_a_row_at_a_time() {
+		// In (x, y, z) order the lower-left tile would come first, but the upper-right one is on an earlier row
+		let mut room = GraphRoom::from_tiles("closet", vec![Position::new(2, 1, 0), Position::new(1, 2, 0)]);
+		let mut rng = GlobalRng::with_seed(1);
+		let spawns = room.find_open_space(vec!["A".to_string()].into(), &mut rng, PlacementMode::Deterministic);
+		assert_eq!(spawns.and_then(|x| x.first().map(|y| y.1)), Some(Position::new(2, 1, 0)));
+	}
+	#[test]
+	fn set_names_are_checked_against_the_shape_slots() {
+		let pair = |id: &str, name: &str| (id.to_string(), name.to_string());
+		let shape = vec!["+++".to_string(), "AB.".to_string()];
+		let mut template: SpawnTemplate = shape.clone().into();
+		assert!(template.assign_names("bunks", vec![pair("A", "bed"), pair("B", "footlocker")]));
+		let names: Vec<String> = template.realize_coordinates(&Position::new(0, 0, 0)).into_iter().map(|x| x.0).collect();
+		assert_eq!(names, vec!["bed", "footlocker"]);
+		// One slot with no item for it, and one item with no slot to go in
+		let mut template: SpawnTemplate = shape.into();
+		assert!(!template.assign_names("bunks", vec![pair("A", "bed"), pair("C", "lamp")]));
+		let names: Vec<String> = template.realize_coordinates(&Position::new(0, 0, 0)).into_iter().map(|x| x.0).collect();
+		assert_eq!(names, vec!["bed", "spawn_template_default_name"]);
+	}
+	#[test]
+	fn removing_a_deck_keeps_the_doors_between_the_other_rooms() {
+		let mut layout = ShipGraph::default();
+		let bottom = layout.add_room(GraphRoom::from_tiles("cargo", vec![Position::new(1, 1, 0)]));
+		let middle = layout.add_room(GraphRoom::from_tiles("galley", vec![Position::new(1, 1, 1)]));
+		let top = layout.add_room(GraphRoom::from_tiles("bridge", vec![Position::new(1, 1, 2)]));
+		layout.connect(bottom, middle);
+		layout.connect(bottom, top);
+		layout.connect(top, bottom);
+		layout.remove_deck(1);
+		assert_eq!(layout.rooms.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), vec!["cargo", "bridge"]);
+		assert_eq!(layout.successors(0).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(layout.successors(1).collect::<Vec<_>>(), vec![0]);
+		assert_eq!(layout.rooms[1].centerpoint, Position::new(1, 1, 1));
+		assert!(layout.rooms[1].contains(Position::new(1, 1, 1)));
+		assert!(!layout.rooms[1].contains(Position::new(1, 1, 2)));
+	}
+}
+
 // EOF
@@ -5,28 +5,48 @@
 use simplelog::*;
 use std::fs::File;
 use std::io::BufReader;
+use std::collections::BTreeMap;
+use std::path::Path;
 //use bevy_turborand::*;
 
 //  ###: INTERNAL LIBRARIES
 use crate::components::Position;
 use crate::worldmap::*;
 pub mod rexpaint_loader;
+pub mod rexpaint_world;
+use rexpaint_world::*;
+pub mod procgen_world;
+use procgen_world::*;
 pub mod json_map;
 use json_map::*;
 pub mod logical_map;
 use logical_map::*;
 
+//  ###: CONSTANTS
+/// The creature defn that's used for any NPC whose kind isn't given in the layout
+pub const DEFAULT_NPC_KIND: &str = "crewmember";
+
 //  ###: TRAITS
 //   ##: WorldBuilder
 pub trait WorldBuilder {
+	/// Chooses which layout file/template the next call to build_world() will use
+	fn set_source(&mut self, source: &str);
 	fn build_world(&mut self);
 	fn get_model(&self) -> WorldModel;
 	fn get_essential_item_requests(&self) -> Vec<(String, Position)>;
 	fn get_additional_item_requests(&self) -> Vec<(String, String)>;
+	fn get_npc_requests(&self) -> Vec<NpcRequest>;
 }
-/// Loads a worldmodel from a pregenerated JSON file and sets it up for gameplay
-pub fn get_world_builder() -> Box<dyn WorldBuilder> {
-	Box::<JsonWorldBuilder>::default()
+/// Picks the WorldBuilder that can read the given layout file: REXPaint files are recognized by their .xp extension,
+/// and a source of "procgen" (with an optional ":<seed>") generates a random layout instead of reading one
+pub fn get_world_builder(source: &str) -> Box<dyn WorldBuilder> {
+	if source.split(':').next() == Some(PROCGEN_SOURCE) {
+		Box::<ProcGenWorldBuilder>::default()
+	} else if source.ends_with(".xp") {
+		Box::<RexWorldBuilder>::default()
+	} else {
+		Box::<JsonWorldBuilder>::default()
+	}
 }
 
 //  ###: COMPLEX TYPES
@@ -35,24 +55,37 @@ pub fn get_world_builder() -> Box<dyn WorldBuilder> {
 pub struct JsonWorldBuilder {
 	model: WorldModel,
 	enty_list: Vec<(String, Position)>,
-	addtl_items: Vec<(String, String)>
+	addtl_items: Vec<(String, String)>,
+	npc_list: Vec<NpcRequest>,
+	hallway_glyph: Option<String>, // If set, hallway floors use this glyph instead of the usual floor glyph
+	source: Option<String>, // The layout file to load, if not the default one
 }
 impl JsonWorldBuilder {
+	/// Sets a distinct glyph for drawing the hallway floors, ie to make them stand out for debugging
+	pub fn hallway_glyph(mut self, new_glyph: &str) -> Self {
+		self.hallway_glyph = Some(new_glyph.to_string());
+		self
+	}
 	/// Extracts, parses, and stores the furniture files in local data storage
+	/// If the layout names any section files, those are read too and stitched onto it before the model is built
 	pub fn load_json_file(&mut self, file_path: &str) {
-		//debug!("* opening input file at {}", file_path);
-		let input_data = if let Ok(file) = File::open(file_path) {
-			let reader = BufReader::new(file);
-			match serde_json::from_reader(reader) {
-				Ok(output) => output,
-				//Ok(output) => {debug!("* output recvd: {:#?}", output); output},
-				Err(msg) => {warn!("! failed to read input data: {}", msg); JsonBucket::default()},
+		let mut input_data = read_json_bucket(file_path);
+		if !input_data.sections.is_empty() {
+			let base_dir = Path::new(file_path).parent().unwrap_or(Path::new("."));
+			let mut sections = vec![(file_path.to_string(), std::mem::take(&mut input_data))];
+			for section_name in sections[0].1.sections.clone().iter() {
+				let section_path = base_dir.join(section_name).to_string_lossy().to_string();
+				let section = read_json_bucket(&section_path);
+				sections.push((section_path, section));
 			}
-		} else {
-			JsonBucket::default()
-		};
+			input_data = assemble_sections(sections);
+		}
+		self.load_json_bucket(input_data);
+	}
+	/// Builds the model from a set of layout data, whether it came from a JSON file or was converted from elsewhere
+	pub fn load_json_bucket(&mut self, input_data: JsonBucket) {
 		// 1: Use the map lists to create the map stack and put it into the model
-		let mut hallway_tiles: Vec<Vec<Position>> = Vec::new();
+		let mut hallway_tiles: Vec<Vec<Position>> = Vec::new(); // All of the hallway floors on each deck
 		let mut logical_door_list: Vec<Position> = Vec::new();
 		let mut _furniture_requests: Vec<(String, String)> = Vec::new();
 		for (z_posn, input_map) in input_data.map_list.iter().enumerate() {
@@ -67,7 +100,7 @@ impl JsonWorldBuilder {
 						'.' => { Tile::new_floor() }
 						',' => {
 							current_hallway.push((x_posn, y_posn, z_posn).into());
-							Tile::new_floor().glyph("x")
+							if let Some(glyph) = &self.hallway_glyph { Tile::new_floor().glyph(glyph) } else { Tile::new_floor() }
 						}
 						'=' => {
 							logical_door_list.push((x_posn, y_posn, z_posn).into());
@@ -82,6 +115,8 @@ impl JsonWorldBuilder {
 			self.model.levels.push(new_map);
 			hallway_tiles.push(current_hallway);
 		}
+		// Each separate stretch of hallway floor on a deck becomes its own room, whatever its width or shape
+		let mut hallway_areas: Vec<Vec<Vec<Position>>> = hallway_tiles.into_iter().map(split_into_areas).collect();
 		// 2: Use the room list to create the topo graph of the layout
 		// Iterate on all the rooms in the input list
 		for cur_room in input_data.room_list.iter() {
@@ -100,10 +135,21 @@ impl JsonWorldBuilder {
 					dest_index = new_index;
 					self.model.layout.connect(room_index, dest_index);
 				} else if destination.contains("hallway") {
-					// If it doesn't exist AND it's a hallway ( FIXME: irregular shape!) then make the hallway now
-					let mut new_room = GraphRoom::default();
-					new_room.name = destination.clone();
-					new_room.set_interior_to(hallway_tiles[cur_room.z_level()].clone());
+					// If it doesn't exist AND it's a hallway, then make the hallway now out of the stretch of hallway
+					// floor that's next to this room; if none of them are, just take the biggest one that's left
+					let areas = if let Some(value) = hallway_areas.get_mut(cur_room.z_level()) { value } else { continue; };
+					let (ul_x, ul_y) = (cur_room.corner[0] as i32, cur_room.corner[1] as i32);
+					let (dr_x, dr_y) = (ul_x + cur_room.width as i32, ul_y + cur_room.height as i32);
+					let is_adjacent = |posn: &Position| {
+						let x_gap = (ul_x - posn.x).max(posn.x - dr_x).max(0);
+						let y_gap = (ul_y - posn.y).max(posn.y - dr_y).max(0);
+						x_gap.max(y_gap) <= 1
+					};
+					let area_index = if let Some(index) = areas.iter().position(|x| x.iter().any(is_adjacent)) { index } else {
+						warn!("* room {} isn't next to any hallway on its deck, guessing which one is {}", cur_room.name, destination); // DEBUG: report detached hallway
+						if let Some(index) = areas.iter().enumerate().max_by_key(|x| x.1.len()).map(|x| x.0) { index } else { continue; }
+					};
+					let new_room = GraphRoom::from_tiles(destination, areas.remove(area_index));
 					dest_index = self.model.layout.add_room(new_room);
 					self.model.layout.connect(room_index, dest_index);
 				} else {
@@ -114,6 +160,24 @@ impl JsonWorldBuilder {
 					}
 				}
 			}
+			// Add the room's NPCs to the list of actors that need to be spawned
+			for npc in cur_room.npcs.iter() {
+				let posn = match npc.posn.as_deref() {
+					Some([x, y, z]) => { Some(Position::new(*x as i32, *y as i32, *z as i32)) }
+					Some(_) => {
+						warn!("* NPC {} in room {} has a malformed posn, it will be placed at random", npc.name, cur_room.name); // DEBUG: report bad NPC posn
+						None
+					}
+					None => { None }
+				};
+				self.npc_list.push(NpcRequest {
+					room: cur_room.name.clone(),
+					name: npc.name.clone(),
+					kind: npc.kind.clone().unwrap_or(DEFAULT_NPC_KIND.to_string()),
+					role: npc.role.clone(),
+					posn,
+				});
+			}
 			// Add the room's contents to the list of items that will need spawnpoints generated
 			if !cur_room.contents.is_empty() {
 				//debug!("* contents of room {}: {:#?}", cur_room.name, cur_room.contents);
@@ -124,6 +188,8 @@ impl JsonWorldBuilder {
 				}
 			}
 		}
+		// Apply any of the rooms' color themes to the tilemaps
+		self.model.apply_room_tints();
 		// 2.5: Use the logical door list to populate those tiles in the logical maps of each room
 		for posn in logical_door_list.iter() {
 			// FIXME: NEED to add Margin tiles around the door
@@ -161,8 +227,12 @@ impl JsonWorldBuilder {
 	}
 }
 impl WorldBuilder for JsonWorldBuilder {
+	fn set_source(&mut self, source: &str) {
+		self.source = Some(source.to_string());
+	}
 	fn build_world(&mut self) {
-		JsonWorldBuilder::load_json_file(self, "resources/test_ship_v3.json");
+		let source = self.source.clone().unwrap_or("resources/test_ship_v3.json".to_string());
+		JsonWorldBuilder::load_json_file(self, &source);
 	}
 	fn get_model(&self) -> WorldModel {
 		self.model.clone()
@@ -173,9 +243,128 @@ impl WorldBuilder for JsonWorldBuilder {
 	fn get_additional_item_requests(&self) -> Vec<(String, String)> {
 		self.addtl_items.clone()
 	}
+	fn get_npc_requests(&self) -> Vec<NpcRequest> {
+		self.npc_list.clone()
+	}
 }
 
 //  ###: SIMPLE TYPES AND HELPERS
+/// Reads a JSON layout file; if it can't be read, the problem is reported and an empty layout is returned
+pub fn read_json_bucket(file_path: &str) -> JsonBucket {
+	//debug!("* opening input file at {}", file_path);
+	if let Ok(file) = File::open(file_path) {
+		let reader = BufReader::new(file);
+		match serde_json::from_reader(reader) {
+			Ok(output) => output,
+			//Ok(output) => {debug!("* output recvd: {:#?}", output); output},
+			Err(msg) => {warn!("! failed to read input data from {}: {}", file_path, msg); JsonBucket::default()},
+		}
+	} else {
+		warn!("! could not open the layout file at {}", file_path);
+		JsonBucket::default()
+	}
+}
+/// Stitches a ship together from its section files, each of which is a (filename, layout) pair
+/// Each section's maps become the decks starting at its first_level; all of the sections use the ship's z-levels
+/// for their rooms and ladders, so that a ladder between two sections can be written down in either one of them
+/// Any deck that's claimed by more than one section is only taken from the first one, and the problem is reported,
+/// as are any gaps in the deck stack and any room exits that don't lead to a room in one of the sections
+pub fn assemble_sections(sections: Vec<(String, JsonBucket)>) -> JsonBucket {
+	let mut output = JsonBucket::default();
+	let mut levels: BTreeMap<usize, (String, JsonMap)> = BTreeMap::new();
+	let mut room_sections: Vec<(String, String)> = Vec::new(); // The name of each room and the section it came from
+	for (filename, section) in sections.into_iter() {
+		for (index, level) in section.map_list.into_iter().enumerate() {
+			let z_level = section.first_level + index;
+			if let Some((owner, _)) = levels.get(&z_level) {
+				error!("! ERR: {} and {} both define deck {}, using the one from {}", owner, filename, z_level, owner); // DEBUG: report deck collision
+				continue;
+			}
+			levels.insert(z_level, (filename.clone(), level));
+		}
+		for room in section.room_list.into_iter() {
+			if let Some((_, owner)) = room_sections.iter().find(|x| x.0 == room.name) {
+				error!("! ERR: room {} is defined in both {} and {}, skipping the second one", room.name, owner, filename); // DEBUG: report room collision
+				continue;
+			}
+			room_sections.push((room.name.clone(), filename.clone()));
+			output.room_list.push(room);
+		}
+		output.ladder_list.extend(section.ladder_list);
+	}
+	let mut deck_sections: Vec<String> = Vec::new(); // The section that each deck came from
+	for (expected, (z_level, (filename, level))) in levels.into_iter().enumerate() {
+		if z_level != expected {
+			error!("! ERR: no section defines deck {}, the decks above it will be out of place", expected); // DEBUG: report deck gap
+		}
+		output.map_list.push(level);
+		deck_sections.push(filename);
+	}
+	// Check that every section's exits lead somewhere, so that a misspelled room name doesn't cut the ship in two
+	for room in output.room_list.iter() {
+		for exit in room.exits.iter() {
+			if !exit.contains("hallway") && !room_sections.iter().any(|x| x.0 == *exit) {
+				error!("! ERR: room {} has an exit to {}, which isn't in any section", room.name, exit); // DEBUG: report dangling exit
+			}
+		}
+		if room.z_level() >= output.map_list.len() {
+			error!("! ERR: room {} is on deck {}, which no section defines", room.name, room.z_level()); // DEBUG: report room without deck
+		}
+	}
+	// Check that each section is joined to some other section, either by a room exit or by a ladder between decks
+	let section_of = |room_name: &str| room_sections.iter().find(|x| x.0 == room_name).map(|x| x.1.clone());
+	let mut links: Vec<(String, String)> = Vec::new();
+	for room in output.room_list.iter() {
+		for exit in room.exits.iter() {
+			if let (Some(left), Some(right)) = (section_of(&room.name), section_of(exit)) { links.push((left, right)); }
+		}
+	}
+	for ladder in output.ladder_list.iter() {
+		let ends: Vec<Option<&String>> = ladder.points.iter().map(|x| x.get(2).and_then(|z| deck_sections.get(*z))).collect();
+		if let [Some(left), Some(right)] = ends.as_slice() { links.push((left.to_string(), right.to_string())); }
+	}
+	let mut section_names: Vec<String> = deck_sections.clone();
+	section_names.extend(room_sections.iter().map(|x| x.1.clone()));
+	section_names.sort();
+	section_names.dedup();
+	if section_names.len() > 1 {
+		for name in section_names.iter() {
+			if !links.iter().any(|x| x.0 != x.1 && (x.0 == *name || x.1 == *name)) {
+				error!("! ERR: section {} isn't connected to any of the other sections", name); // DEBUG: report isolated section
+			}
+		}
+	}
+	output
+}
+/// Splits a set of floor tiles into the separate areas that they make up, where each tile is connected to the rest
+/// of its area by one of its eight neighbors
+pub fn split_into_areas(mut tiles: Vec<Position>) -> Vec<Vec<Position>> {
+	let mut areas = Vec::new();
+	while let Some(first) = tiles.pop() {
+		let mut area = vec![first];
+		let mut index = 0;
+		while index < area.len() {
+			let current = area[index];
+			let (neighbors, rest): (Vec<Position>, Vec<Position>) = tiles.into_iter().partition(|x| x.is_adjacent_to(&current));
+			tiles = rest;
+			area.extend(neighbors);
+			index += 1;
+		}
+		area.sort();
+		areas.push(area);
+	}
+	areas
+}
+//   ##: NpcRequest
+/// Describes an NPC that the ship layout wants spawned, as read from a room's NPC list
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NpcRequest {
+	pub room: String, // The room that the NPC belongs in
+	pub name: String,
+	pub kind: String, // The creature defn to build them from
+	pub role: Option<String>, // The name of their CrewRole, if they're one of the crew
+	pub posn: Option<Position>, // Where to put them; if None, then anywhere in the room will do
+}
 //   ##: Floating-point (for fractional values) vector math functions
 /// Returns a vector of Positions that describe a direct line/path between the two inputs
 fn get_line(first: &Position, second: &Position) -> Vec<Position> {
@@ -0,0 +1,160 @@
+// mason/procgen_world.rs
+// Provides the logic for generating random ship layouts, as an alternative to the authored ones
+
+/* The shape of a generated ship:
+ *  Every deck is the same size, with a hallway running along the middle from one end of the deck to the other
+ *  The rooms are lined up along both sides of the hallway, sharing their walls, and each one has a door onto it
+ *  Each pair of neighboring decks is joined by a ladder somewhere in their hallways
+ * The layout is requested with a source of "procgen" or "procgen:<seed>"; the same seed always makes the same ship,
+ * and if none is given then a random one is picked and written to the log so that the ship can be made again
+ */
+
+//  ###: EXTERNAL LIBRARIES
+use bevy_turborand::prelude::*;
+use simplelog::*;
+
+//  ###: INTERNAL LIBRARIES
+use crate::mason::*;
+use crate::mason::json_map::*;
+
+//  ###: CONSTANTS
+/// The source name that selects the ProcGenWorldBuilder in get_world_builder()
+pub const PROCGEN_SOURCE: &str = "procgen";
+/// The size of every deck on a generated ship
+pub const PROCGEN_DECK_WIDTH: usize = 60;
+pub const PROCGEN_DECK_HEIGHT: usize = 29;
+/// The range of how many decks a generated ship can have
+const PROCGEN_MIN_DECKS: usize = 2;
+const PROCGEN_MAX_DECKS: usize = 4;
+/// The range of the width and depth of each room, counting their walls
+const ROOM_MIN_WIDTH: usize = 6;
+const ROOM_MAX_WIDTH: usize = 12;
+const ROOM_MIN_DEPTH: usize = 5;
+const ROOM_MAX_DEPTH: usize = 10;
+/// The row that the middle of each hallway runs along; the player is always spawned at (4, 14, 1) for now, so
+/// the hallways have to go through that spot and there must always be at least two decks
+const HALLWAY_ROW: usize = 14;
+/// The first and last columns of the hallway walls
+const HALLWAY_WEST_END: usize = 2;
+const HALLWAY_EAST_END: usize = PROCGEN_DECK_WIDTH - 3;
+/// The names to pick from for the generated rooms; each room also gets a number, so the names may repeat
+const ROOM_NAMES: &[&str] = &["crew quarters", "storage", "galley", "laboratory", "medbay", "workshop", "lounge", "office"];
+
+//  ###: COMPLEX TYPES
+//   ##: ProcGenWorldBuilder
+/// Builds a WorldModel from a randomly-generated layout, by writing it out as the same layout data that the JSON
+/// files provide; the tile, door, and ladder handling is left to the JsonWorldBuilder, so that all layouts behave the same
+#[derive(Default)]
+pub struct ProcGenWorldBuilder {
+	layout: JsonWorldBuilder,
+	source: Option<String>,
+	pub seed: Option<u64>, // The seed that the last layout was generated from
+}
+impl ProcGenWorldBuilder {
+	/// Generates a new layout from the given seed and loads it into the model
+	pub fn generate(&mut self, seed: u64) {
+		info!("* generating a ship layout from seed {}", seed); // DEBUG: announce the procgen seed
+		let mut rng = GlobalRng::with_seed(seed);
+		self.seed = Some(seed);
+		self.layout = JsonWorldBuilder::default();
+		self.layout.load_json_bucket(generate_bucket(&mut rng));
+	}
+}
+impl WorldBuilder for ProcGenWorldBuilder {
+	fn set_source(&mut self, source: &str) {
+		self.source = Some(source.to_string());
+	}
+	fn build_world(&mut self) {
+		let source = self.source.clone().unwrap_or(PROCGEN_SOURCE.to_string());
+		let seed = match source.split_once(':').map(|x| x.1.trim().parse::<u64>()) {
+			Some(Ok(value)) => { value }
+			Some(Err(_)) => {
+				warn!("* could not read the seed in '{}', using a random one", source); // DEBUG: report bad procgen seed
+				GlobalRng::new().u64(..)
+			}
+			None => { GlobalRng::new().u64(..) }
+		};
+		self.generate(seed);
+	}
+	fn get_model(&self) -> WorldModel {
+		self.layout.get_model()
+	}
+	fn get_essential_item_requests(&self) -> Vec<(String, Position)> {
+		self.layout.get_essential_item_requests()
+	}
+	fn get_additional_item_requests(&self) -> Vec<(String, String)> {
+		self.layout.get_additional_item_requests()
+	}
+	fn get_npc_requests(&self) -> Vec<NpcRequest> {
+		self.layout.get_npc_requests()
+	}
+}
+
+//  ###: SIMPLE TYPES AND HELPERS
+/// Generates a complete ship layout, in the same form as the JSON layout files
+pub fn generate_bucket(rng: &mut GlobalRng) -> JsonBucket {
+	let mut bucket = JsonBucket::default();
+	let deck_count = rng.usize(PROCGEN_MIN_DECKS..=PROCGEN_MAX_DECKS);
+	let mut room_count = 0;
+	for z_level in 0..deck_count {
+		let mut tiles = vec![vec![' '; PROCGEN_DECK_WIDTH]; PROCGEN_DECK_HEIGHT];
+		let hallway_name = format!("hallway deck {}", z_level);
+		// The rooms are drawn first, and then the hallway is drawn over the walls that it shares with them
+		for north_side in [true, false] {
+			let mut west = HALLWAY_WEST_END;
+			while west < HALLWAY_EAST_END {
+				let mut east = west + rng.usize(ROOM_MIN_WIDTH..=ROOM_MAX_WIDTH);
+				// Stretch the last room to the end of the hallway rather than leaving a sliver that's too small
+				if east + ROOM_MIN_WIDTH > HALLWAY_EAST_END { east = HALLWAY_EAST_END; }
+				let depth = rng.usize(ROOM_MIN_DEPTH..=ROOM_MAX_DEPTH);
+				let (north, south) = if north_side { (HALLWAY_ROW - 2 - depth, HALLWAY_ROW - 2) } else { (HALLWAY_ROW + 2, HALLWAY_ROW + 2 + depth) };
+				draw_box(&mut tiles, (west, north), (east, south), '.');
+				let door_x = rng.usize(west + 1..east);
+				let door_y = if north_side { south } else { north };
+				room_count += 1;
+				let name = ROOM_NAMES[rng.usize(0..ROOM_NAMES.len())];
+				bucket.room_list.push(JsonRoom::new()
+					.name(&format!("{} {}", name, room_count))
+					.exits(vec![hallway_name.clone()])
+					.corner(vec![west, north, z_level])
+					.dims(east - west, south - north));
+				tiles[door_y][door_x] = '=';
+				west = east;
+			}
+		}
+		// Draw the hallway, leaving the doors on its walls in place
+		draw_box(&mut tiles, (HALLWAY_WEST_END, HALLWAY_ROW - 2), (HALLWAY_EAST_END, HALLWAY_ROW + 2), ',');
+		bucket.map_list.push(JsonMap {
+			tilemap: tiles.iter().map(|x| x.iter().collect()).collect(),
+			width: PROCGEN_DECK_WIDTH,
+			height: PROCGEN_DECK_HEIGHT,
+		});
+	}
+	// Join each pair of neighboring decks with a ladder, keeping clear of the player's spawnpoint and the other ladders
+	let mut ladder_columns: Vec<usize> = vec![4];
+	for z_level in 0..deck_count - 1 {
+		let mut x_posn = rng.usize(HALLWAY_WEST_END + 2..HALLWAY_EAST_END - 1);
+		while ladder_columns.iter().any(|x| x.abs_diff(x_posn) < 2) {
+			x_posn = rng.usize(HALLWAY_WEST_END + 2..HALLWAY_EAST_END - 1);
+		}
+		ladder_columns.push(x_posn);
+		bucket.ladder_list.push(JsonPortal {
+			name: format!("ladder {}", z_level),
+			points: vec![vec![x_posn, HALLWAY_ROW, z_level], vec![x_posn, HALLWAY_ROW, z_level + 1]],
+		});
+	}
+	bucket
+}
+/// Draws a box of walls between two corners, inclusive, and fills its inside with the given tile
+/// Any doors that were already drawn on the box's walls are left alone
+fn draw_box(tiles: &mut [Vec<char>], ul_corner: (usize, usize), dr_corner: (usize, usize), fill: char) {
+	for (y_posn, row) in tiles.iter_mut().enumerate().take(dr_corner.1 + 1).skip(ul_corner.1) {
+		for (x_posn, tile) in row.iter_mut().enumerate().take(dr_corner.0 + 1).skip(ul_corner.0) {
+			let is_wall = y_posn == ul_corner.1 || y_posn == dr_corner.1 || x_posn == ul_corner.0 || x_posn == dr_corner.0;
+			if is_wall && *tile == '=' { continue; }
+			*tile = if is_wall { '#' } else { fill };
+		}
+	}
+}
+
+// EOF
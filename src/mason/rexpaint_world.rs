@@ -0,0 +1,128 @@
+// mason/rexpaint_world.rs
+// Provides the logic for importing ship layouts that were drawn in REXPaint
+
+/* The layout of the input .xp file:
+ *  Every deck uses three consecutive layers, so that layers 1-3 are deck 0, layers 4-6 are deck 1, and so on
+ *    tiles   - drawn with the same chars as the JSON tilemaps: '#' wall, '.' floor, ',' hallway, ' ' vacuum
+ *    doors   - any char marks a door
+ *    ladders - any char marks one end of a ladder; the two ends of a ladder are marked with the same char
+ *  The rooms can't be drawn in REXPaint, so they're read from the JSON file next to the .xp file, ie
+ *  'ship.xp' takes its rooms from 'ship.json', using the same format as the room list in the JSON layouts
+ */
+
+//  ###: EXTERNAL LIBRARIES
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use bracket_rex::prelude::*;
+use simplelog::*;
+
+//  ###: INTERNAL LIBRARIES
+use crate::mason::*;
+use crate::mason::json_map::*;
+
+//  ###: CONSTANTS
+/// How many of the .xp file's layers make up a single deck
+pub const REX_LAYERS_PER_DECK: usize = 3;
+
+//  ###: COMPLEX TYPES
+//   ##: RexWorldBuilder
+/// Builds a WorldModel from a REXPaint file, by converting it into the same layout data that the JSON files provide
+/// All of the tile, door, and ladder handling is left to the JsonWorldBuilder, so that both formats behave the same
+#[derive(Default)]
+pub struct RexWorldBuilder {
+	layout: JsonWorldBuilder,
+	source: Option<String>,
+}
+impl RexWorldBuilder {
+	/// Reads the .xp file and its room list, and converts them into a JsonBucket for the JsonWorldBuilder
+	pub fn load_xp_file(&mut self, file_path: &str) {
+		let xp_file = match File::open(file_path).map(BufReader::new).and_then(|mut x| XpFile::read(&mut x)) {
+			Ok(output) => { output }
+			Err(msg) => {
+				warn!("! failed to read REXPaint layout at {}: {}", file_path, msg);
+				return;
+			}
+		};
+		let room_path = Path::new(file_path).with_extension("json");
+		let room_list = match File::open(&room_path).map(BufReader::new) {
+			Ok(reader) => {
+				match serde_json::from_reader::<_, JsonBucket>(reader) {
+					Ok(output) => { output.room_list }
+					Err(msg) => { warn!("! failed to read the room list at {}: {}", room_path.display(), msg); Vec::new() }
+				}
+			}
+			Err(_) => { warn!("! no room list found at {}, the ship will have no rooms", room_path.display()); Vec::new() }
+		};
+		let mut input_data = xp_to_bucket(&xp_file);
+		input_data.room_list = room_list;
+		self.layout.load_json_bucket(input_data);
+	}
+}
+impl WorldBuilder for RexWorldBuilder {
+	fn set_source(&mut self, source: &str) {
+		self.source = Some(source.to_string());
+	}
+	fn build_world(&mut self) {
+		let source = self.source.clone().unwrap_or("resources/test_ship.xp".to_string());
+		RexWorldBuilder::load_xp_file(self, &source);
+	}
+	fn get_model(&self) -> WorldModel {
+		self.layout.get_model()
+	}
+	fn get_essential_item_requests(&self) -> Vec<(String, Position)> {
+		self.layout.get_essential_item_requests()
+	}
+	fn get_additional_item_requests(&self) -> Vec<(String, String)> {
+		self.layout.get_additional_item_requests()
+	}
+	fn get_npc_requests(&self) -> Vec<NpcRequest> {
+		self.layout.get_npc_requests()
+	}
+}
+
+//  ###: SIMPLE TYPES AND HELPERS
+/// Converts the decks in a REXPaint file into tilemaps and ladders; the room list is left empty
+pub fn xp_to_bucket(xp_file: &XpFile) -> JsonBucket {
+	let mut bucket = JsonBucket::default();
+	let mut ladder_ends: BTreeMap<char, Vec<Vec<usize>>> = BTreeMap::new();
+	if xp_file.layers.len() % REX_LAYERS_PER_DECK != 0 {
+		warn!("* REXPaint layout has {} layers, which isn't a multiple of {}", xp_file.layers.len(), REX_LAYERS_PER_DECK); // DEBUG: report bad layer count
+	}
+	for (z_level, deck) in xp_file.layers.chunks(REX_LAYERS_PER_DECK).enumerate() {
+		let tile_layer = &deck[0];
+		let mut tilemap = Vec::new();
+		for y in 0..tile_layer.height {
+			let mut line = String::new();
+			for x in 0..tile_layer.width {
+				let is_door = deck.get(1).and_then(|layer| rex_char_at(layer, x, y)).is_some();
+				if let Some(ladder) = deck.get(2).and_then(|layer| rex_char_at(layer, x, y)) {
+					ladder_ends.entry(ladder).or_default().push(vec![x, y, z_level]);
+				}
+				let tile = if is_door { '=' } else { rex_char_at(tile_layer, x, y).unwrap_or(' ') };
+				line.push(tile);
+			}
+			tilemap.push(line);
+		}
+		bucket.map_list.push(JsonMap { tilemap, width: tile_layer.width, height: tile_layer.height });
+	}
+	for (ladder, points) in ladder_ends.into_iter() {
+		if points.len() != 2 {
+			warn!("* REXPaint ladder '{}' has {} ends instead of 2, skipping it", ladder, points.len()); // DEBUG: report bad ladder
+			continue;
+		}
+		bucket.ladder_list.push(JsonPortal { name: format!("ladder {}", ladder), points });
+	}
+	bucket
+}
+/// Gets the char that was drawn at the given spot on a layer, if any; REXPaint marks empty cells with a 0 or a space
+fn rex_char_at(layer: &XpLayer, x: usize, y: usize) -> Option<char> {
+	let cell = layer.get(x, y)?;
+	match cell.ch {
+		0 | 32 => { None }
+		ch => { char::from_u32(ch) }
+	}
+}
+
+// EOF
@@ -6,7 +6,9 @@
 //  ###: EXTERNAL LIBRARIES
 use bevy::{
 	prelude::*,
+	ecs::entity::*,
 	ecs::query::*,
+	ecs::reflect::ReflectMapEntities,
 	utils::*,
 };
 use ratatui::prelude::*;
@@ -19,6 +21,7 @@ use strum_macros::EnumIter;
 //  ###: INTERNAL LIBRARIES
 use crate::{
 	components::*,
+	engine::config::GameConfig,
 	engine::event::*,
 	engine::messagelog::*,
 	planq::{
@@ -39,6 +42,7 @@ pub fn planq_update_system(mut commands: Commands,
 	                         mut preader:  EventReader<PlanqEvent>,
 	                         mut msglog:   ResMut<MessageLog>,
 	                         time:         Res<Time>,
+	                         config:       Res<GameConfig>,
 	                         mut planq:    ResMut<PlanqData>, // contains the PLANQ's settings and data storage
 	                         p_query:      Query<(Entity, &Body), With<Player>>, // provides interface to player data
 	                         mut q_query:  Query<(Entity, &Device, &Portable), With<Planq>>, // contains the PLANQ's component data
@@ -184,9 +188,11 @@ pub fn planq_update_system(mut commands: Commands,
 						//debug!("¶ running boot stage {}", planq.boot_stage); // DEBUG: announce the current PLANQ boot stage
 						msglog.boot_message(planq.boot_stage);
 						// kick off boot stage 1
+						// The fast_boot option gets rid of the wait between each of the boot stages
+						let stage_time = if config.fast_boot { 0 } else { 3 };
 						planq.proc_table.push(commands.spawn(
 								PlanqProcess::new()
-								.time(3)
+								.time(stage_time)
 								.event(PlanqEvent::new(PlanqEventType::BootStage(1))))
 							.id()
 						);
@@ -312,6 +318,7 @@ pub struct PlanqData {
 	pub stdout: Vec<Message>, // Local copy of the PLANQ's message backlog, as copied from the MessageLog "planq" channel
 	pub proc_table: Vec<Entity>, // The list of PlanqProcesses running in the Planq
 	pub jack_cnxn: Entity, // ID of the object that the PLANQ's access jack is connected to
+	pub prompt: String, // Shown before each command that's echoed to the terminal; "{target}" becomes the jack_cnxn's name
 }
 impl Default for PlanqData {
 	fn default() -> PlanqData {
@@ -329,6 +336,7 @@ impl Default for PlanqData {
 			stdout: Vec::new(), // Contains the PLANQ's message backlog
 			proc_table: Vec::new(), // The list of PlanqProcesses running in the Planq
 			jack_cnxn: Entity::PLACEHOLDER, // ID of the object that the PLANQ's access jack is connected to
+			prompt: "[[fg:green]]>[[end]] ".to_string(),
 		}
 	}
 }
@@ -336,6 +344,11 @@ impl PlanqData {
 	pub fn new() -> PlanqData {
 		PlanqData::default()
 	}
+	/// Produces the CLI prompt, filling in the name of whatever the access jack is connected to
+	/// If the jack isn't connected then the PLANQ's own name is used instead
+	pub fn get_prompt(&self, target_name: Option<&str>) -> String {
+		self.prompt.replace("{target}", target_name.unwrap_or("planq"))
+	}
 	/// Renders the CLI input box
 	pub fn render_cli<B: Backend>(&mut self, frame: &mut Frame<'_, B>, area: Rect, stdin: &mut PlanqInput) {
 		//let mut cli = TextArea::default();
@@ -441,7 +454,23 @@ pub enum PlanqCmd {
 	Shutdown,
 	Reboot,
 	Connect(String),
-	Disconnect
+	Disconnect,
+	Locate(String),
+	Reveal(bool), // DEBUG: if true, reveals every level instead of only the current one
+	Status,
+	Reload, // DEBUG: re-reads the item definitions from disk
+	Export, // DEBUG: writes every level of the map out to a text file
+	Deck(usize), // DEBUG: moves the player straight to the given deck, without using the ladders
+	Scan(String),
+	Note(String),
+	Notes,
+	Repeat, // Runs the last command in the CLI's history again
+}
+impl PlanqCmd {
+	/// Returns false for the commands that are parsed but don't have any logic behind them yet
+	pub fn is_implemented(&self) -> bool {
+		!matches!(self, PlanqCmd::Shutdown | PlanqCmd::Reboot | PlanqCmd::Connect(_) | PlanqCmd::Disconnect)
+	}
 }
 impl std::fmt::Display for PlanqCmd {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -453,6 +482,16 @@ impl std::fmt::Display for PlanqCmd {
 			PlanqCmd::Reboot => { write!(f, "reboot") }
 			PlanqCmd::Connect(_) => { write!(f, "connect") }
 			PlanqCmd::Disconnect => { write!(f, "disconnect") }
+			PlanqCmd::Locate(_) => { write!(f, "locate") }
+			PlanqCmd::Reveal(_) => { write!(f, "reveal") }
+			PlanqCmd::Status => { write!(f, "status") }
+			PlanqCmd::Reload => { write!(f, "reload") }
+			PlanqCmd::Export => { write!(f, "export") }
+			PlanqCmd::Deck(_) => { write!(f, "deck") }
+			PlanqCmd::Scan(_) => { write!(f, "scan") }
+			PlanqCmd::Note(_) => { write!(f, "note") }
+			PlanqCmd::Notes => { write!(f, "notes") }
+			PlanqCmd::Repeat => { write!(f, "!!") }
 		}
 	}
 }
@@ -491,13 +530,31 @@ pub enum PlanqEventType {
 
 //  ###: UTILITIES and COMPONENTS
 /// Defines the PLANQ 'tag' component within Bevy
-#[derive(Component, Copy, Clone, Debug, Default, Reflect)]
-#[reflect(Component)]
-pub struct Planq { }
+/// Also keeps a copy of the access jack's connection, since only Components get their Entity refs remapped by bevy_save
+#[derive(Component, Copy, Clone, Debug, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct Planq {
+	pub jack_cnxn: Entity,
+}
 impl Planq {
 	pub fn new() -> Planq {
 		Planq::default()
 	}
 }
+impl Default for Planq {
+	// Also serves as the FromWorld impl: a PLACEHOLDER is safer than a stale Entity when loading from a save game
+	fn default() -> Planq {
+		Planq {
+			jack_cnxn: Entity::PLACEHOLDER,
+		}
+	}
+}
+impl MapEntities for Planq {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		if self.jack_cnxn != Entity::PLACEHOLDER {
+			self.jack_cnxn = entity_mapper.get_or_reserve(self.jack_cnxn);
+		}
+	}
+}
 
 // EOF
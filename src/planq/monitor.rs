@@ -16,18 +16,19 @@ use crate::sys::DurationFmtExt;
 // ###: BEVY SYSTEMS
 /// Handles the PLANQ's output status bars and other such things
 pub fn planq_monitor_system(time:        Res<Time>,
+	                          turns:       Res<TurnCount>,
 	                          mut rng:     ResMut<GlobalRng>,
 	                          msglog:      ResMut<MessageLog>,
 	                          mut planq:   ResMut<PlanqData>,
 	                          mut monitor: ResMut<PlanqMonitor>,
-	                          p_query:     Query<(Entity, &Body, &Description), With<Player>>,
+	                          p_query:     Query<(Entity, &Body, &Description, Option<&Stamina>), With<Player>>,
 	                          //mut q_query: Query<(Entity, &Device, &mut RngComponent), With<Planq>>,
 	                          mut q_query: Query<(Entity, &Device), With<Planq>>,
 	                          mut s_query: Query<(Entity, &mut DataSampleTimer)>,
 ) {
 	if p_query.is_empty() { return; }
 	if q_query.is_empty() { return; }
-	let (_enty, p_body, p_desc) = if let Ok(value) = p_query.get_single() { value } else { return };
+	let (_enty, p_body, p_desc, p_stamina) = if let Ok(value) = p_query.get_single() { value } else { return };
 	let (_enty, q_device) = if let Ok(value) = q_query.get_single_mut() { value } else { return };
 	// Iterate any active PlanqProcesses
 	// These should be iterated locally here so that they are consistent from frame to frame; this is because
@@ -60,6 +61,13 @@ pub fn planq_monitor_system(time:        Res<Time>,
 				"planq_battery"   => {
 					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Percent(q_device.batt_voltage as u32));
 				}
+				"player_stamina"  => {
+					let stamina = p_stamina.map(|x| x.percent()).unwrap_or(0);
+					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Percent(stamina));
+				}
+				"turn_count"      => {
+					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Text(turns.turns.to_string()));
+				}
 				"test_line"       => {
 					monitor.raw_data.entry(source_name)
 						.and_modify(|x| *x = PlanqDataType::Decimal{numer: rng.i32(0..100), denom: 100});
@@ -148,6 +156,7 @@ impl PlanqMonitor {
 							"planq_mode" => { "MODE: ".to_string() }
 							"player_location" => { "LOCN: ".to_string() }
 							"current_time" => { "TIME: ".to_string() }
+							"turn_count" => { "TURN: ".to_string() }
 							_ => { "".to_string() }
 						};
 						let remainder = area.width as usize - prefix.len() - 2;
@@ -160,8 +169,8 @@ impl PlanqMonitor {
 						                    .block(default_block.clone()), area);
 					}
 					PlanqDataType::Percent(pct) => {
-						if source == "planq_battery" {
-							let prefix = "BATT: ".to_string();
+						if source == "planq_battery" || source == "player_stamina" {
+							let prefix = if source == "planq_battery" { "BATT: ".to_string() } else { "STAM: ".to_string() };
 							let remainder = area.width as usize - prefix.len() - 2;
 							//let line = PlanqMonitor::right_align(pct.to_string() + "%", remainder);
 							let line = PlanqMonitor::right_align(format!("{}{}", pct, "%").as_str(), remainder);
@@ -210,11 +219,13 @@ impl PlanqMonitor {
 impl Default for PlanqMonitor {
 	fn default() -> PlanqMonitor {
 		PlanqMonitor {
-			status_bars: vec!["planq_battery".to_string(), "planq_mode".to_string(), "current_time".to_string(), "player_location".to_string()],
+			status_bars: vec!["planq_battery".to_string(), "player_stamina".to_string(), "planq_mode".to_string(), "current_time".to_string(), "turn_count".to_string(), "player_location".to_string()],
 			raw_data: HashMap::from([("current_time".to_string(), PlanqDataType::Text("Initializing...".to_string())),
 				                       ("planq_battery".to_string(), PlanqDataType::Percent(0)),
+				                       ("player_stamina".to_string(), PlanqDataType::Percent(0)),
 				                       ("planq_mode".to_string(), PlanqDataType::Text("Initializing...".to_string())),
 				                       ("player_location".to_string(), PlanqDataType::Text("Initializing...".to_string())),
+				                       ("turn_count".to_string(), PlanqDataType::Text("0".to_string())),
 			]),
 		}
 	}
@@ -12,17 +12,22 @@ use bevy::ecs::component::{ComponentId, Components};
 use bevy::ecs::entity::Entity;
 use bevy::ecs::event::{EventReader, EventWriter};
 use bevy::ecs::query::{
+	Added,
 	Changed,
+	Or,
 	With,
 	Without,
 };
+use bevy::ecs::removal_detection::RemovedComponents;
 use bevy::ecs::system::{
 	Commands,
+	Local,
 	Query,
 	Res,
 	ResMut
 };
-use bevy::utils::{Duration, HashSet};
+use bevy::time::{Time, Timer, TimerMode};
+use bevy::utils::{Duration, HashMap, HashSet};
 use bevy_turborand::*;
 use bracket_pathfinding::prelude::*;
 use simplelog::*;
@@ -33,6 +38,7 @@ use crate::components::*;
 use crate::components::{
 	Color,
 	Direction,
+	Friendly,
 	Mobile,
 	Player,
 	Position,
@@ -41,10 +47,23 @@ use crate::engine::event::*;
 use crate::engine::event::GameEventType::*;
 use crate::engine::event::ActionType::*;
 use crate::engine::messagelog::*;
+use crate::mason::logical_map::CellType;
 use crate::planq::*;
 use crate::planq::monitor::*;
 use crate::worldmap::*;
 
+// ###: CONSTANTS
+/// How many seconds to wait between status broadcasts on the shipnet
+pub const SHIPNET_INTERVAL: u64 = 30;
+/// How much Stamina is recovered by each turn spent waiting
+pub const STAMINA_REGEN: u32 = 10;
+/// If true, the player will trade places with a Friendly entity that is in their way instead of being blocked by it
+pub const SWAP_WITH_FRIENDLIES: bool = true;
+/// What to call an Entity that doesn't have a Description
+pub const NAMELESS: &str = "something";
+/// DEBUG: How many ticks of game time pass between each check of the WorldModel's contents against the entities
+pub const CONTENTS_CHECK_INTERVAL: u64 = 50;
+
 // ###: CONTINUOUS SYSTEMS
 /// Handles connections between maintenance devices like the PLANQ and access ports on external entities
 pub fn access_port_system(mut ereader:      EventReader<GameEvent>,
@@ -52,6 +71,7 @@ pub fn access_port_system(mut ereader:      EventReader<GameEvent>,
 	                        mut msglog:       ResMut<MessageLog>,
 	                        mut planq:        ResMut<PlanqData>,
 	                        a_query:          Query<(Entity, &Description), With<AccessPort>>,
+	                        mut q_query:      Query<&mut Planq>,
 ) {
 	// For every event in the Game's event queue,
 	//   Assign the planq's jack connection to the target entity,
@@ -60,8 +80,14 @@ pub fn access_port_system(mut ereader:      EventReader<GameEvent>,
 	for event in ereader.iter() {
 		match event.etype {
 			GameEventType::PlanqConnect(Entity::PLACEHOLDER) => {
+				// The event's context still names the old port, so the name has to come from the PlanqData's copy
+				let old_cnxn = planq.jack_cnxn;
 				planq.jack_cnxn = Entity::PLACEHOLDER;
-				if let Ok((_enty, object_name)) = a_query.get(planq.jack_cnxn) {
+				// Keep the Planq component's copy in sync so that the connection survives a save/load
+				if let Ok(mut q_planq) = q_query.get_single_mut() {
+					q_planq.jack_cnxn = Entity::PLACEHOLDER;
+				}
+				if let Ok((_enty, object_name)) = a_query.get(old_cnxn) {
 					msglog.tell_player(format!("The PLANQ's access jack unsnaps from the {}.", object_name).as_str());
 					preader.send(PlanqEvent::new(PlanqEventType::AccessUnlink))
 				}
@@ -69,6 +95,9 @@ pub fn access_port_system(mut ereader:      EventReader<GameEvent>,
 			GameEventType::PlanqConnect(target) => {
 				if let Some(context) = event.context {
 					planq.jack_cnxn = context.object;
+					if let Ok(mut q_planq) = q_query.get_single_mut() {
+						q_planq.jack_cnxn = context.object;
+					}
 					msglog.tell_player(format!("The PLANQ's access jack clicks into place on the {:?}.", target).as_str());
 					preader.send(PlanqEvent::new(PlanqEventType::AccessLink))
 				}
@@ -77,11 +106,26 @@ pub fn access_port_system(mut ereader:      EventReader<GameEvent>,
 		}
 	}
 }
+/// Writes a terse line to the "actions" channel for each thing that the player does, ie "T12 opened door", so that
+/// they can look back over what they did; these are the attempts, so a step into a wall is logged as well
+pub fn action_log_system(mut ereader: EventReader<GameEvent>,
+	                       mut msglog:  ResMut<MessageLog>,
+	                       turns:       Res<TurnCount>,
+	                       d_query:     Query<&Description>,
+) {
+	if ereader.is_empty() { return; }
+	for event in ereader.iter() {
+		let action = if let PlayerAction(action) = event.etype { action } else { continue; };
+		let object = event.context.filter(|x| x.object != x.subject).and_then(|x| d_query.get(x.object).ok()).map(|x| format!(" {}", x.get_name())).unwrap_or_default();
+		msglog.log_action(&format!("T{} {}{}", turns.turns, action.log_verb(), object));
+	}
+}
 /// Maintains accurate ActionSets on Entities, among other future things
 pub fn action_referee_system(_cmd:       Commands, // gonna need this eventually if i want to despawn entys
 	                           archetypes:    &Archetypes,
 	                           components:    &Components,
 	                           mut a_query:   Query<(Entity, &mut ActionSet), Changed<ActionSet>>,
+	                           o_query:       Query<&Openable>,
 ) {
 	// For every actor whose ActionSet has changed, (implying that ActionSet.outdated changed values)
 	//   If that really is what changed (to avoid triggering recursively on subsequent system cycles):
@@ -94,6 +138,8 @@ pub fn action_referee_system(_cmd:       Commands, // gonna need this eventually
 		if a_actionset.outdated {
 			if let Some(component_iter) = get_components_for_entity(a_enty, archetypes) {
 				let mut new_set = HashSet::new();
+				// The player's own set describes what they're able to do, which depends on what they're made of
+				let (mut is_player, mut can_carry, mut can_move) = (false, false, false);
 				for comp_id in component_iter {
 					if let Some(comp_info) = components.get_info(comp_id) {
 						let split_str: Vec<&str> = comp_info.name().split("::").collect();
@@ -104,9 +150,12 @@ pub fn action_referee_system(_cmd:       Commands, // gonna need this eventually
 								new_set.insert(ActionType::MoveItem);
 								new_set.insert(ActionType::DropItem);
 							}
-							"Openable"    => {
-								new_set.insert(ActionType::OpenItem);
-								new_set.insert(ActionType::CloseItem);
+							"Openable"    => { // Only offer whichever of the two would actually change something
+								if o_query.get(a_enty).map(|x| x.is_open).unwrap_or(false) {
+									new_set.insert(ActionType::CloseItem);
+								} else {
+									new_set.insert(ActionType::OpenItem);
+								}
 							}
 							"Lockable"    => {
 								new_set.insert(ActionType::UnlockItem);
@@ -119,26 +168,82 @@ pub fn action_referee_system(_cmd:       Commands, // gonna need this eventually
 							"Device"      => {
 								new_set.insert(ActionType::UseItem);
 							}
+							"Player"      => { is_player = true; }
+							"Container"   => { can_carry = true; }
+							"Mobile"      => { can_move = true; }
 							_ => { }
 						}
 					}
 				}
+				// Only the player's set gets these, since everything else's set describes what can be done *to* it
+				if is_player && can_carry { // Carrying things requires somewhere to put them
+					new_set.insert(ActionType::MoveItem);
+					new_set.insert(ActionType::DropItem);
+				}
+				if is_player && can_move { // Working with the things nearby requires being able to get to them
+					new_set.insert(ActionType::UseItem);
+					new_set.insert(ActionType::OpenItem);
+					new_set.insert(ActionType::CloseItem);
+					new_set.insert(ActionType::LockItem);
+					new_set.insert(ActionType::UnlockItem);
+				}
 				a_actionset.actions = new_set;
 				a_actionset.outdated = false;
 			}
 		}
 	}
 }
+/// Flags an entity's ActionSet as outdated whenever it gains or loses one of the components that the
+/// action_referee_system looks at, so that the available actions always match the entity's capabilities
+/// An Openable also counts as changed when it's opened or closed, since that swaps which of the two actions it offers
+pub fn action_refresh_system(mut a_query:      Query<&mut ActionSet>,
+	                           added_query:      Query<Entity, Or<(Added<Description>, Added<Portable>, Changed<Openable>, Added<Lockable>, Added<Key>, Added<Device>, Added<Player>, Added<Container>, Added<Mobile>)>>,
+	                           mut rm_desc:      RemovedComponents<Description>,
+	                           mut rm_portable:  RemovedComponents<Portable>,
+	                           mut rm_openable:  RemovedComponents<Openable>,
+	                           mut rm_lockable:  RemovedComponents<Lockable>,
+	                           mut rm_key:       RemovedComponents<Key>,
+	                           mut rm_device:    RemovedComponents<Device>,
+	                           mut rm_player:    RemovedComponents<Player>,
+	                           mut rm_container: RemovedComponents<Container>,
+	                           mut rm_mobile:    RemovedComponents<Mobile>,
+) {
+	let mut targets: HashSet<Entity> = added_query.iter().collect();
+	targets.extend(rm_desc.iter());
+	targets.extend(rm_portable.iter());
+	targets.extend(rm_openable.iter());
+	targets.extend(rm_lockable.iter());
+	targets.extend(rm_key.iter());
+	targets.extend(rm_device.iter());
+	targets.extend(rm_player.iter());
+	targets.extend(rm_container.iter());
+	targets.extend(rm_mobile.iter());
+	for enty in targets.iter() {
+		// Entities that were despawned outright will also show up as removals, so just skip them
+		if let Ok(mut actions) = a_query.get_mut(*enty) {
+			if !actions.outdated { actions.outdated = true; }
+		}
+	}
+}
 /// Handles requests for descriptions of entities by the player
 pub fn examination_system(mut ereader:  EventReader<GameEvent>,
 	                        mut msglog:   ResMut<MessageLog>,
-	                        e_query:      Query<(Entity, &Description)>,
+	                        model:        Res<WorldModel>,
+	                        e_query:      Query<(Entity, &Description, Option<&Lockable>, Option<&Openable>, Option<&Device>, Option<&Container>)>,
+	                        i_query:      Query<&Portable, With<IsCarried>>,
+	                        b_query:      Query<&Body>,
+	                        p_query:      Query<(&Body, &Viewshed, Option<&Memory>), With<Player>>,
+	                        c_query:      Query<&Crew>,
+	                        turns:        Res<TurnCount>,
 ) {
 	// Bail out if there's no events in the queue
 	// For every event in the queue,
 	//   Get the target of the EXAMINE action,
 	//   Get the target's description,
-	//   Show the description to the player
+	//   Show the description to the player,
+	//   Then add a line for each of the target's components that the player could notice,
+	//   And finish with a description of the tile that the target is occupying
+	// If the target is out of the player's sight, then only what they remember of it gets reported
 	if ereader.is_empty() { return; }
 	for event in ereader.iter() {
 		if event.etype != PlayerAction(ActionType::Examine) { continue; }
@@ -147,10 +252,65 @@ pub fn examination_system(mut ereader:  EventReader<GameEvent>,
 				warn!("* Attempted to Examine the Entity::PLACEHOLDER"); // DEBUG: warn if this case occurs
 				continue;
 			}
-			if let Ok((_enty, e_desc)) = e_query.get(econtext.object) {
+			if let Ok((e_enty, e_desc, e_lock, e_open, e_device, e_contain)) = e_query.get(econtext.object) {
+				if let (Ok(e_body), Ok((p_body, p_viewshed, p_memory)), Err(_)) = (b_query.get(e_enty), p_query.get_single(), i_query.get(e_enty)) {
+					let in_view = e_body.posns().iter().any(|x| x.z == p_body.ref_posn.z && p_viewshed.visible_points.contains(&posn_to_point(x)));
+					if !in_view {
+						match p_memory.and_then(|x| x.recall(e_enty)) {
+							Some((_, seen_at)) => {
+								msglog.tell_player(&format!("You recall a {} here.", e_desc.name));
+								if let Some(turn) = seen_at {
+									let elapsed = turns.turns.saturating_sub(turn);
+									match elapsed {
+										0 => { msglog.tell_player(" - You saw it just a moment ago."); }
+										1 => { msglog.tell_player(" - You last saw it 1 turn ago."); }
+										_ => { msglog.tell_player(&format!(" - You last saw it {} turns ago.", elapsed)); }
+									}
+								}
+							}
+							None => { msglog.tell_player(&format!("You can't see the {} from here.", e_desc.name)); }
+						}
+						continue;
+					}
+				}
 				//let output = e_desc.desc.clone();
 				let output = &e_desc.desc;
 				msglog.tell_player(output);
+				let mut facts = Vec::new();
+				if let Ok(crew) = c_query.get(e_enty) {
+					facts.push(format!("They're one of the crew: the ship's {}, usually found near the {}.", crew.role, crew.role.duty_station()));
+				}
+				if let Some(lock) = e_lock {
+					facts.push(if lock.is_locked { "It is locked.".to_string() } else { "It is unlocked.".to_string() });
+				}
+				if let Some(open) = e_open {
+					facts.push(if open.is_open { "It is open.".to_string() } else { "It is closed.".to_string() });
+				}
+				if let Some(device) = e_device {
+					let power = if device.pw_switch { "on" } else { "off" };
+					if device.batt_discharge < 0 { // This Device has no battery to report on
+						facts.push(format!("It is powered {}.", power));
+					} else {
+						facts.push(format!("It is powered {}, with {} charge remaining.", power, device.batt_voltage));
+					}
+				}
+				if e_contain.is_some() {
+					let count = i_query.iter().filter(|x| x.carrier == e_enty).count();
+					match count {
+						0 => { facts.push("It holds nothing.".to_string()); }
+						1 => { facts.push("It holds 1 item.".to_string()); }
+						_ => { facts.push(format!("It holds {} items.", count)); }
+					}
+				}
+				if let Ok(e_body) = b_query.get(e_enty) {
+					facts.push(describe_tile(&model, e_body.ref_posn, Some(e_enty), &e_query));
+					if let Some(room_name) = model.layout.get_room_name(e_body.ref_posn) {
+						facts.push(format!("It's in the {}, at {}.", room_name, e_body.ref_posn));
+					}
+				}
+				for fact in facts.iter() {
+					msglog.tell_player(&format!(" - {}", fact));
+				}
 			}
 		}
 	}
@@ -159,13 +319,22 @@ pub fn examination_system(mut ereader:  EventReader<GameEvent>,
 pub fn item_collection_system(mut cmd:      Commands,
 	                            mut ereader:  EventReader<GameEvent>,
 	                            mut msglog:   ResMut<MessageLog>,
+	                            mut model:    ResMut<WorldModel>,
+	                            mut stats:    ResMut<RunStats>,
+	                            mut turns:    ResMut<TurnCount>,
 	                            // The list of Entities that also have Containers
 	                            e_query:      Query<(Entity, &Description, &Body, &Container, Option<&Player>)>,
 	                            // The list of every Item that may or may not be in a container
 	                            mut i_query:      Query<(Entity, &Description, &mut Body, &Portable), Without<Container>>,
+	                            c_query:      Query<&Critical>,
+	                            d_query:      Query<&Openable>,
+	                            n_query:      Query<&Description>,
 ) {
 	// Don't even bother trying if there's no events to worry about
 	if ereader.is_empty() { return; }
+	// The names of everything the player picked up during this run; a "take all" sends its pickups together, and
+	// they get a single summary line at the end instead of one line per item
+	let mut pickups: Vec<String> = Vec::new();
 	for event in ereader.iter() {
 		// Skip any events with the wrong type by filtering on the event's type's action's type
 		let atype: ActionType;
@@ -181,15 +350,20 @@ pub fn item_collection_system(mut cmd:      Commands,
 			_ => { continue; }
 		};
 		// All of the item events require an event context, so if there isn't any then don't try to handle the event
-		if event.context.is_none() { continue; }
-		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
-		// We know that it is safe to unwrap these because calling is_invalid() checked that they are not placeholders
-		//let subject = e_query.get(econtext.subject).expect("econtext.subject should be Some(n)");
-		let (s_enty, s_desc, s_body, _container, s_player) = e_query.get(econtext.subject).expect("econtext.subject should be Some(n)");
-		let subject_name = s_desc.name.clone();
+		let econtext = if let Some(context) = event.context.as_ref() { context } else { continue; };
+		let subject_name = name_of(&n_query, econtext.subject);
+		let item_name = name_of(&n_query, econtext.object);
+		// Either of these could be missing if the event was malformed or the Entity was despawned, so skip the event
+		let (s_enty, _desc, s_body, _container, s_player) = if let Ok(value) = e_query.get(econtext.subject) { value } else {
+			error!("* item_collection_system: subject {:?} of {} not found", econtext.subject, event.etype); // DEBUG: report a bad event
+			continue;
+		};
 		let is_player_action = s_player.is_some();
-		let (o_enty, o_desc, mut o_body, _) = i_query.get_mut(econtext.object).expect("econtext.object should be Some(n)");
-		let item_name = o_desc.name.clone();
+		let (o_enty, _desc, mut o_body, _) = if let Ok(value) = i_query.get_mut(econtext.object) { value } else {
+			debug!("* item_collection_system: object {:?} of {} is not Portable", econtext.object, event.etype); // DEBUG: report an incompatible target
+			if is_player_action { msglog.tell_player(&incompatible_action_msg(atype)); }
+			continue;
+		};
 		// We have all of our context values now, so proceed to actually doing the requested action
 		let mut message: String = "".to_string();
 		match atype {
@@ -198,8 +372,11 @@ pub fn item_collection_system(mut cmd:      Commands,
 				cmd.entity(o_enty)
 				.insert(Portable{carrier: s_enty}) // put the container's ID to the target's Portable component
 				.insert(IsCarried::default()); // add the IsCarried tag to the component
+				model.remove_contents(&o_body.posns(), o_enty); // Carried items are no longer on the map
 				if is_player_action {
-					message = format!("Obtained a {}.", item_name);
+					stats.items_collected += 1;
+					turns.increment();
+					pickups.push(item_name);
 				} else {
 					message = format!("The {} takes a {}.", subject_name, item_name);
 				}
@@ -209,8 +386,19 @@ pub fn item_collection_system(mut cmd:      Commands,
 				cmd.entity(o_enty)
 				.insert(Portable{carrier: Entity::PLACEHOLDER}) // still portable but not carried
 				.remove::<IsCarried>(); // remove the tag from the component
-				o_body.move_to(s_body.ref_posn);
+				// A chosen spot must be next to the subject and have room for the item, else use the usual spot
+				let drop_posn = match econtext.target {
+					Some(target) if target.in_range_of(&s_body.ref_posn, 1) && model.in_bounds(target) && !model.is_blocked_at(target) => { target }
+					Some(_) => {
+						if is_player_action { msglog.tell_player("There's no room to put it there."); }
+						find_drop_posn(&model, &d_query, s_body.ref_posn)
+					}
+					None => { find_drop_posn(&model, &d_query, s_body.ref_posn) }
+				};
+				o_body.move_to(drop_posn);
+				model.add_contents(&o_body.posns(), RenderLayer::ITEM, o_enty);
 				if is_player_action {
+					turns.increment();
 					message = format!("Dropped a {}.", item_name);
 				} else {
 					message = format!("The {} drops a {}.", subject_name, item_name);
@@ -218,7 +406,12 @@ pub fn item_collection_system(mut cmd:      Commands,
 			}
 			ActionType::KillItem => { // DESTROY an Item entirely, ie remove it from the game
 				//debug!("* KILLing item..."); // DEBUG: announce item destruction
+				if c_query.contains(o_enty) {
+					warn!("* item_collection_system: refused to destroy critical item {:?}", o_enty); // DEBUG: report protected item
+					continue;
+				}
 				cmd.entity(o_enty).despawn();
+				model.remove_entity_everywhere(o_enty);
 			}
 			action => {
 				error!("* item_collection_system unhandled action: {}", action); // DEBUG: announce unhandled action for this item
@@ -228,11 +421,207 @@ pub fn item_collection_system(mut cmd:      Commands,
 			msglog.add(&message, "world", 0, 0);
 		}
 	}
+	match pickups.len() {
+		0 => { }
+		1 => { msglog.tell_player(&format!("Obtained a {}.", pickups[0])); }
+		count => { msglog.tell_player(&format!("Picked up {} items.", count)); }
+	}
+}
+/// Deducts Stamina for strenuous actions and restores it when the actor waits
+/// Actions that the actor can't afford are stopped before dispatch, so anything that arrives here gets paid for
+pub fn stamina_system(mut ereader: EventReader<GameEvent>,
+	                    mut s_query: Query<&mut Stamina>,
+) {
+	if ereader.is_empty() { return; }
+	for event in ereader.iter() {
+		let action = match event.etype {
+			PlayerAction(action) | ActorAction(action) => { action }
+			_ => { continue; }
+		};
+		let context = if let Some(context) = event.context { context } else { continue; };
+		let mut stamina = if let Ok(value) = s_query.get_mut(context.subject) { value } else { continue; };
+		if action == ActionType::Wait {
+			stamina.recover(STAMINA_REGEN);
+		} else if !stamina.spend(action.exertion()) {
+			stamina.current = 0;
+		}
+	}
+}
+/// Advances the GameClock by the cost of each action that gets performed, and drains the batteries of any powered
+/// Devices by the same amount; anything else that runs on game time should key off of the GameClock
+/// Once the player has taken a turn, the NPCs are given theirs via the NpcTurn gate
+pub fn turn_scheduler_system(mut ereader:  EventReader<GameEvent>,
+	                           mut clock:    ResMut<GameClock>,
+	                           mut turns:    ResMut<TurnCount>,
+	                           mut npc_turn: ResMut<NpcTurn>,
+	                           s_query:      Query<&Speed>,
+	                           mut d_query:  Query<&mut Device>,
+) {
+	if ereader.is_empty() { return; }
+	let mut elapsed = 0;
+	let mut player_acted = false;
+	for event in ereader.iter() {
+		let action = match event.etype {
+			PlayerAction(action) | ActorAction(action) => { action }
+			_ => { continue; }
+		};
+		let cost = if let Some(context) = event.context {
+			if let Ok(speed) = s_query.get(context.subject) { speed.adjust(action.cost()) } else { action.cost() }
+		} else { action.cost() };
+		elapsed += cost;
+		// Free actions, like looking at things, don't use up a turn
+		// Every other action counts as a turn once its system has carried it out, except for waiting, which can't fail
+		if matches!(event.etype, PlayerAction(_)) && action.cost() > 0 {
+			if action == ActionType::Wait { turns.increment(); }
+			player_acted = true;
+		}
+	}
+	if elapsed == 0 { return; }
+	clock.advance(elapsed);
+	if player_acted { npc_turn.begin(elapsed); }
+	for mut device in d_query.iter_mut() {
+		if !device.pw_switch { continue; }
+		if device.discharge(elapsed as i32) == 0 && device.batt_discharge > 0 {
+			device.power_off();
+		}
+	}
+}
+/// Run condition for the NPC systems: true only while the NPCs are owed a turn by the player
+/// NPC systems should be added to the Update schedule like so, so that they see the results of the player's action:
+///   my_npc_system.run_if(npc_turn_ready).after(turn_scheduler_system).before(end_npc_turn_system)
+/// Any actions that they take should be sent as ActorAction events, which won't reopen the gate
+/// See init_bevy() for the crew_system, which is registered this way
+pub fn npc_turn_ready(npc_turn: Res<NpcTurn>) -> bool {
+	npc_turn.pending
+}
+/// Closes the NpcTurn gate after all of the NPC systems have had their turn
+pub fn end_npc_turn_system(mut npc_turn: ResMut<NpcTurn>) {
+	if npc_turn.pending { npc_turn.finish(); }
+}
+/// Gives each member of the crew their turn: they greet the player when the player first comes up next to them,
+/// and otherwise take a step towards their duty station; this is an NPC system, so it only runs via npc_turn_ready()
+/// NOTE: The step is a greedy one, so a crewmember whose way is blocked waits until it clears; there's no pathfinding
+pub fn crew_system(mut game_events: EventWriter<GameEvent>,
+	                 mut msglog:      ResMut<MessageLog>,
+	                 mut greeted:     Local<HashSet<Entity>>,
+	                 model:           Res<WorldModel>,
+	                 p_query:         Query<&Body, With<Player>>,
+	                 c_query:         Query<(Entity, &Body, &Description, &Crew), With<Mobile>>,
+) {
+	let p_body = if let Ok(value) = p_query.get_single() { value } else { return; };
+	for (c_enty, c_body, c_desc, c_crew) in c_query.iter() {
+		// Only the first turn next to the player gets a greeting, so they don't repeat themselves every turn
+		if c_body.is_adjacent_to(&p_body.ref_posn) {
+			if greeted.insert(c_enty) {
+				msglog.tell_player(&format!("The {} says, {}", c_desc.name, c_crew.role.greeting()));
+			}
+			continue;
+		}
+		greeted.remove(&c_enty);
+		let station = c_crew.role.duty_station().to_lowercase();
+		let room = if let Some(value) = model.layout.rooms.iter().find(|x| x.name.to_lowercase().contains(&station)) { value } else { continue; };
+		let (posn, goal) = (c_body.ref_posn, room.centerpoint);
+		if room.contains(posn) || posn.z != goal.z { continue; } // Already at work, or the station is on another deck
+		let distance = |x: &Position| (x.x - goal.x).pow(2) + (x.y - goal.y).pow(2);
+		let step = Direction::COMPASS.iter()
+			.map(|dir| (*dir, Position::new(posn.x + dir.offset().0, posn.y + dir.offset().1, posn.z)))
+			.filter(|(_, next)| !model.is_blocked_at(*next) && distance(next) < distance(&posn))
+			.min_by_key(|(_, next)| distance(next));
+		if let Some((dir, _)) = step {
+			game_events.send(GameEvent::new(ActorAction(MoveTo(dir)), Some(c_enty), None));
+		}
+	}
+}
+/// Fires off any ScheduledEvents whose time has come, and then removes them from the world
+pub fn scheduler_system(mut commands:     Commands,
+	                      turns:            Res<TurnCount>,
+	                      clock:            Res<GameClock>,
+	                      mut game_events:  EventWriter<GameEvent>,
+	                      mut planq_events: EventWriter<PlanqEvent>,
+	                      s_query:          Query<(Entity, &ScheduledEvent)>,
+) {
+	for (s_enty, s_event) in s_query.iter() {
+		if !s_event.is_due(turns.turns, clock.ticks) { continue; }
+		match s_event.payload {
+			ScheduledPayload::Game(event)  => { game_events.send(event); }
+			ScheduledPayload::Planq(event) => { planq_events.send(event); }
+		}
+		debug!("* fired scheduled event {:?}", s_event.payload); // DEBUG: announce scheduled event
+		commands.entity(s_enty).despawn();
+	}
+}
+/// Lets Networkable devices broadcast their status onto the shipnet channel at regular intervals
+/// If the PLANQ is jacked into one of these devices, the broadcasts will be relayed to the PLANQ's terminal as well
+pub fn shipnet_system(time:          Res<Time>,
+	                    mut timer:     Local<Timer>,
+	                    mut msglog:    ResMut<MessageLog>,
+	                    planq:         Res<PlanqData>,
+	                    n_query:       Query<(Entity, &Description, Option<&Device>), With<Networkable>>,
+) {
+	// Local<Timer> can only be created with its default (zero) duration, so set the real interval on the first run
+	if timer.duration().is_zero() {
+		*timer = Timer::new(Duration::from_secs(SHIPNET_INTERVAL), TimerMode::Repeating);
+	}
+	timer.tick(time.delta());
+	if !timer.just_finished() { return; }
+	let is_connected = n_query.contains(planq.jack_cnxn);
+	for (_enty, n_desc, n_device) in n_query.iter() {
+		let status = match n_device.map(|x| x.state) {
+			Some(DeviceState::Offline)     => { "offline".to_string() }
+			Some(DeviceState::Working)     => { "working".to_string() }
+			Some(DeviceState::Error(code)) => { format!("error {}", code) }
+			_                              => { "nominal".to_string() }
+		};
+		let message = format!("{}: {}", n_desc.name, status);
+		msglog.add(&message, "shipnet", 0, 0);
+		if is_connected {
+			msglog.tell_planq(&format!("[[fg:cyan]]NET[[fg:gray]]│[[end]]{}", message));
+		}
+	}
+}
+/// DEBUG: Makes sure that the WorldModel's tile contents agree with where the entities' Bodies say they are
+/// Any entity that is listed somewhere it isn't, or isn't listed somewhere it is, gets reported in the log
+#[cfg(debug_assertions)]
+pub fn contents_check_system(clock:          Res<GameClock>,
+	                           mut last_check: Local<u64>,
+	                           model:          Res<WorldModel>,
+	                           b_query:        Query<(Entity, &Body, Option<&Portable>)>,
+) {
+	if clock.ticks < *last_check + CONTENTS_CHECK_INTERVAL { return; }
+	*last_check = clock.ticks;
+	let mut listed: HashSet<(Entity, Position)> = HashSet::new();
+	for (z_level, level) in model.levels.iter().enumerate() {
+		for y in 0..level.height as i32 {
+			for x in 0..level.width as i32 {
+				let posn = Position::new(x, y, z_level as i32);
+				for enty in level.get_contents_at(posn) {
+					listed.insert((enty, posn));
+					if let Ok((_, e_body, _)) = b_query.get(enty) {
+						if !e_body.contains(&posn) {
+							warn!("* contents check: {:?} is listed at {} but its Body is at {:?}", enty, posn, e_body.posns()); // DEBUG: report a stale listing
+						}
+					} else {
+						warn!("* contents check: {:?} is listed at {} but has no Body or no longer exists", enty, posn); // DEBUG: report a dangling listing
+					}
+				}
+			}
+		}
+	}
+	for (enty, e_body, e_portable) in b_query.iter() {
+		// Carried items aren't on the map at all
+		if e_portable.is_some_and(|x| x.carrier != Entity::PLACEHOLDER) { continue; }
+		for posn in e_body.posns() {
+			if !listed.contains(&(enty, posn)) {
+				warn!("* contents check: {:?} occupies {} but is not listed there", enty, posn); // DEBUG: report a missing listing
+			}
+		}
+	}
 }
 /// Handles ActorLock/Unlock events
 pub fn lockable_system(mut _commands:    Commands,
 	                     mut ereader:      EventReader<GameEvent>,
 	                     mut msglog:       ResMut<MessageLog>,
+	                     mut turns:        ResMut<TurnCount>,
 	                     mut lock_query:   Query<(Entity, &Body, &Description, &mut Lockable)>,
 	                     mut e_query:      Query<(Entity, &Body, &Description, Option<&Player>)>,
 	                     key_query:        Query<(Entity, &Portable, &Description, &Key), With<IsCarried>>,
@@ -240,19 +629,27 @@ pub fn lockable_system(mut _commands:    Commands,
 	// Bail out if there's no events or the wrong type
 	if ereader.is_empty() { return; }
 	for event in ereader.iter() {
-		let mut atype = ActionType::NoAction;
+		let atype;
 		if let PlayerAction(action) | ActorAction(action) = event.etype {
 			if action != LockItem && action != UnlockItem {
 				continue;
 			} else {
 				atype = action;
 			}
+		} else {
+			continue;
 		}
-		if event.context.is_none() { continue; }
-		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
-		let (e_enty, _body, e_desc, e_player) = e_query.get_mut(econtext.subject).expect("econtext.subject should be found in e_query");
+		let econtext = if let Some(context) = event.context.as_ref() { context } else { continue; };
+		let (e_enty, _body, e_desc, e_player) = if let Ok(value) = e_query.get_mut(econtext.subject) { value } else {
+			error!("* lockable_system: subject {:?} of {} not found", econtext.subject, event.etype); // DEBUG: report a bad event
+			continue;
+		};
 		let player_action = e_player.is_some();
-		let (_enty, _portable, l_desc, mut l_lock) = lock_query.get_mut(econtext.object).expect("econtext.object should be found in lock_query");
+		let (_enty, _portable, l_desc, mut l_lock) = if let Ok(value) = lock_query.get_mut(econtext.object) { value } else {
+			debug!("* lockable_system: object {:?} of {} is not Lockable", econtext.object, event.etype); // DEBUG: report an incompatible target
+			if player_action { msglog.tell_player(&incompatible_action_msg(atype)); }
+			continue;
+		};
 		let mut message: String = "".to_string();
 		// If they have the right key then they can unlock it
 		// Lock attempts always succeed
@@ -260,6 +657,7 @@ pub fn lockable_system(mut _commands:    Commands,
 			ActionType::LockItem => {
 				l_lock.is_locked = true;
 				if player_action {
+					turns.increment();
 					message = format!("You tap the LOCK button on the {}.", l_desc.name.clone());
 				} else {
 					message = format!("The {} locks the {}.", e_desc.name.clone(), l_desc.name.clone());
@@ -282,6 +680,7 @@ pub fn lockable_system(mut _commands:    Commands,
 						} else {
 							message = format!("The {} unlocks the {}.", e_desc.name.clone(), l_desc.name.clone());
 						}
+						break;
 					} else {
 						// none of the keys worked, report a failure
 						if player_action {
@@ -289,6 +688,7 @@ pub fn lockable_system(mut _commands:    Commands,
 						}
 					}
 				}
+				if player_action && !l_lock.is_locked { turns.increment(); }
 			}
 			_ => { }
 		}
@@ -299,24 +699,89 @@ pub fn lockable_system(mut _commands:    Commands,
 }
 /// Handles updates to the 'meta' worldmaps, ie the blocked and opaque tilemaps
 pub fn map_indexing_system(mut model:         ResMut<WorldModel>,
-	                         blocker_query: Query<&Body, With<Obstructive>>,
-	                         opaque_query:  Query<(&Body, &Opaque)>,
+	                         mut indexed:       Local<HashMap<Entity, Vec<Position>>>,
+	                         mut rm_blockers:   RemovedComponents<Obstructive>,
+	                         mut rm_opaques:    RemovedComponents<Opaque>,
+	                         changed_query: Query<(Entity, &Body), (Or<(With<Obstructive>, With<Opaque>)>, Or<(Changed<Body>, Changed<Obstructive>, Changed<Opaque>)>)>,
+	                         blocker_query: Query<(Entity, &Body, Option<&Multilevel>), With<Obstructive>>,
+	                         opaque_query:  Query<(Entity, &Body, &Opaque, Option<&Multilevel>)>,
 ) {
-	// Rebuild each map floor-by-floor
-	for floor in model.levels.iter_mut() {
-		floor.update_tilemaps(); // Update tilemaps based on their tiletypes
+	// Find the levels that need rebuilding: any new maps, plus the old and new locations of anything that changed
+	let mut dirty_levels: HashSet<i32> = HashSet::new();
+	for (z_level, floor) in model.levels.iter().enumerate() {
+		if floor.needs_reindex { dirty_levels.insert(z_level as i32); }
+	}
+	let removed: Vec<Entity> = rm_blockers.iter().chain(rm_opaques.iter()).collect();
+	for enty in removed.iter() {
+		if let Some(old_posns) = indexed.remove(enty) {
+			dirty_levels.extend(old_posns.iter().map(|x| x.z));
+		}
 	}
-	// Then, step through all blocking entities and flag their locations on the map as well
-	for guy in blocker_query.iter() {
-		for posn in &guy.extent {
-			model.set_blocked_state(posn.posn, true);
+	for (enty, guy) in changed_query.iter() {
+		dirty_levels.extend(guy.extent.iter().map(|x| x.posn.z));
+		if let Some(old_posns) = indexed.get(&enty) { // Catches anything that moved off of a level
+			dirty_levels.extend(old_posns.iter().map(|x| x.z));
 		}
 	}
+	if dirty_levels.is_empty() { return; }
+	// Rebuild each dirty level from its tiletypes
+	for z_level in dirty_levels.iter() {
+		if let Some(floor) = model.levels.get_mut(*z_level as usize) {
+			floor.update_tilemaps();
+			floor.needs_reindex = false;
+		}
+	}
+	// Most entities sit on a single deck, so checking their ref_posn is enough; the Multilevel ones are indexed one
+	// deck at a time instead, so that only the parts on the decks that were just rebuilt get flagged again
+	let dirty_parts = |guy: &Body, span: Option<&Multilevel>| -> Vec<Position> {
+		if span.is_some() {
+			guy.posns().into_iter().filter(|x| dirty_levels.contains(&x.z)).collect()
+		} else if dirty_levels.contains(&guy.ref_posn.z) {
+			guy.posns()
+		} else {
+			Vec::new()
+		}
+	};
+	// Then, step through all blocking entities on those levels and flag their locations on the map as well
+	for (enty, guy, span) in blocker_query.iter() {
+		let posns = dirty_parts(guy, span);
+		if posns.is_empty() { continue; }
+		for posn in posns {
+			model.set_blocked_state(posn, true);
+		}
+		indexed.insert(enty, guy.posns());
+	}
 	// Do the same for the opaque entities
-	for guy in opaque_query.iter() {
-		for posn in &guy.0.extent {
-			model.set_opaque_state(posn.posn, guy.1.opaque);
+	for (enty, guy, opaque, span) in opaque_query.iter() {
+		let posns = dirty_parts(guy, span);
+		if posns.is_empty() { continue; }
+		for posn in posns {
+			model.set_opaque_state(posn, opaque.opaque);
 		}
+		indexed.insert(enty, guy.posns());
+	}
+}
+/// Decides whether the game has been won or lost: it's won once the player is carrying all of the Critical items,
+/// and lost if the player ends up standing in vacuum; once decided, the outcome doesn't change again
+pub fn game_outcome_system(mut outcome:  ResMut<GameOutcome>,
+	                         mut msglog:   ResMut<MessageLog>,
+	                         model:        Res<WorldModel>,
+	                         p_query:      Query<(Entity, &Body), With<Player>>,
+	                         c_query:      Query<Option<&Portable>, With<Critical>>,
+) {
+	if *outcome != GameOutcome::Ongoing { return; }
+	let (p_enty, p_body) = if let Ok(value) = p_query.get_single() { value } else { return; };
+	// Nothing is decided until the player is actually on the map
+	if !model.in_bounds(p_body.ref_posn) { return; }
+	if model.get_tiletype_at(p_body.ref_posn) == TileType::Vacuum {
+		// A death goes on the combat channel with everything else that hurts, and is too important not to mirror
+		msglog.tell_combat("You step out of the ship and into the vacuum. It does not go well.", true);
+		*outcome = GameOutcome::Lost;
+		return;
+	}
+	if !c_query.is_empty() && c_query.iter().all(|x| x.is_some_and(|y| y.carrier == p_enty)) {
+		msglog.tell_player("You have everything you need to get off of this ship.");
+		*outcome = GameOutcome::Won;
 	}
 }
 /// Handles updates for entities that can move around
@@ -324,7 +789,11 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 	                     mut msglog:      ResMut<MessageLog>,
 	                     mut p_posn_res:  ResMut<Position>,
 	                     mut model:       ResMut<WorldModel>,
-	                     mut e_query:     Query<(Entity, &mut Description, &mut Body, Option<&mut Viewshed>, Option<&Player>)>
+	                     mut stats:       ResMut<RunStats>,
+	                     mut turns:       ResMut<TurnCount>,
+	                     mut e_query:     Query<(Entity, &mut Description, &mut Body, Option<&mut Viewshed>, Option<&Player>, Option<&RenderLayer>)>,
+	                     f_query:         Query<(), (With<Friendly>, With<Mobile>)>,
+	                     mut face_query:  Query<&mut Facing>,
 ) {
 	if ereader.is_empty() { return; } // Don't even bother trying if there's no events to worry about
 	for event in ereader.iter() {
@@ -337,8 +806,10 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 					continue;
 				}
 				let econtext = event.context.expect("event.context should be Some(n)");
-				let origin = e_query.get_mut(econtext.subject);
-				let (actor_enty, mut actor_desc, mut actor_body, actor_viewshed, _) = origin.expect("econtext.subject should be in e_query");
+				let (actor_enty, mut actor_desc, mut actor_body, actor_viewshed, actor_player, actor_layer) = if let Ok(value) = e_query.get_mut(econtext.subject) { value } else {
+					error!("* movement_system: subject {:?} not found", econtext.subject); // DEBUG: report a bad event
+					continue;
+				};
 				// TODO: this is now overkill, just use the match case to make an implicit PosnOffset applied to the old position
 				let mut xdiff = 0;
 				let mut ydiff = 0;
@@ -397,14 +868,57 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 				//debug!("* target_extent: {:?}", target_extent);
 				if let Some(mut blocked_tiles) = model.get_obstructions_at(target_extent, Some(actor_enty)) {
 					blocked_tiles.retain(|x| x.1 != Obstructor::Actor(actor_enty));
+					// If the only thing in the player's way is a single friendly entity, they can swap places instead
+					// This is restricted to one-tile bodies on the same level so that nobody ends up stuck in a wall
+					let swap_target = match blocked_tiles.first() {
+						Some((_, Obstructor::Actor(enty)))
+						if SWAP_WITH_FRIENDLIES
+						&& is_player_action
+						&& dir != Direction::UP && dir != Direction::DOWN
+						&& actor_body.extent.len() == 1
+						&& f_query.contains(*enty)
+						&& blocked_tiles.iter().all(|x| x.1 == Obstructor::Actor(*enty)) => { Some(*enty) }
+						_ => { None }
+					};
+					if let Some(friend) = swap_target {
+						let old_location = actor_body.ref_posn;
+						let actor_priority = if let Some(layer) = actor_layer { layer.priority }
+							else if actor_player.is_some() { RenderLayer::PLAYER }
+							else { RenderLayer::ACTOR };
+						if let Ok([mut actor, mut other]) = e_query.get_many_mut([actor_enty, friend]) {
+							if other.2.extent.len() == 1 {
+								let other_priority = other.5.map(|x| x.priority).unwrap_or(RenderLayer::ACTOR);
+								model.remove_contents(&actor.2.posns(), actor_enty);
+								model.remove_contents(&other.2.posns(), friend);
+								actor.2.move_to(new_location);
+								other.2.move_to(old_location);
+								model.add_contents(&actor.2.posns(), actor_priority, actor_enty);
+								model.add_contents(&other.2.posns(), other_priority, friend);
+								if let Some(viewshed) = actor.3.as_mut() { viewshed.dirty = true; }
+								if let Some(viewshed) = other.3.as_mut() { viewshed.dirty = true; }
+								if let Some(new_name) = model.layout.get_room_name(new_location) {
+									if new_name != actor.1.locn { actor.1.locn = new_name.clone(); }
+									if stats.visit(&new_name) { msglog.tell_player(&model.layout.get_room_blurb(&new_name)); }
+								}
+								if let Some(new_name) = model.layout.get_room_name(old_location) {
+									if new_name != other.1.locn { other.1.locn = new_name; }
+								}
+								if let Ok(mut facing) = face_query.get_mut(actor_enty) { facing.dir = dir; }
+								*p_posn_res = new_location;
+								turns.increment();
+								msglog.tell_player(format!("You swap places with the {}.", other.1.name).as_str());
+								continue;
+							}
+						}
+					}
 					// We have a list of positions that are definitely blocked, but we don't know why
 					// Get the first one off the list, find out why it's blocked, and report it
 					//debug!("blocked tiles: {:?}, {:?}", dir, blocked_tiles);
 					let reply_msg = match blocked_tiles[0].1 {
 						Obstructor::Actor(enty) => {
 							// build an entity message
-							let actor = e_query.get(enty).expect("Obstructor actor should be listed in e_query");
-							format!("a {}", actor.1.name)
+							// The actor's own query holds the Descriptions mutably, so name_of() can't be given one here
+							format!("a {}", e_query.get(enty).map(|x| x.1.name.clone()).unwrap_or_else(|_| NAMELESS.to_string()))
 						}
 						Obstructor::Object(ttype) => {
 							// build a tile message
@@ -419,20 +933,34 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 				//let old_posns = actor_body.extent;
 				model.remove_contents(&actor_body.posns(), actor_enty);
 				actor_body.move_to(new_location);
-				model.add_contents(&actor_body.posns(), 0, actor_enty);
+				let priority = if let Some(layer) = actor_layer { layer.priority }
+					else if actor_player.is_some() { RenderLayer::PLAYER }
+					else { RenderLayer::ACTOR };
+				model.add_contents(&actor_body.posns(), priority, actor_enty);
+				// Ladders don't change which way the actor is pointed, but every other step does
+				if dir != Direction::UP && dir != Direction::DOWN {
+					if let Ok(mut facing) = face_query.get_mut(actor_enty) { facing.dir = dir; }
+				}
 				// If the actor has a Viewshed, flag it as dirty to be updated
 				if let Some(mut viewshed) = actor_viewshed {
 					viewshed.dirty = true;
 				}
 				// If the entity changed rooms, update their description to reflect that
+				// Only the room's name is kept here, so that the PLANQ's location readout doesn't change with every step;
+				// the exact coordinates can be found by examining something instead
 				if let Some(new_name) = model.layout.get_room_name(new_location) {
+					// The first time the player walks into a room, give them a quick description of it
+					if is_player_action && stats.visit(&new_name) {
+						msglog.tell_player(&model.layout.get_room_blurb(&new_name));
+					}
 					if new_name != actor_desc.locn {
-						actor_desc.locn = format!("{}: {}", new_name, actor_body.ref_posn);
+						actor_desc.locn = new_name;
 					}
 				}
 				// If it was the player specifically moving around, we need to do a few more things
 				if is_player_action {
 					*p_posn_res = new_location; // Update the system-wide resource containing the player's location
+					turns.increment();
 					// Is there anything on the ground at the new location?
 					// If so, tell the player about it, but don't mention the player entity itself
 					let mut contents_list = model.get_contents_at(new_location);
@@ -445,7 +973,7 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 					// 5. > and checks to see if it successfully unwrapped a Player component (the '.4.is_some()' field below)
 					// 6. > and if so, return the index of that element from the position() function to the index variable
 					// 7. which then uses the known-good index variable as an argument to remove the player from the list
-					if let Some(index) = contents_list.iter().position(|x| e_query.get(*x).expect("entry of contents_list should be in e_query").4.is_some()) {
+					if let Some(index) = contents_list.iter().position(|x| e_query.get(*x).is_ok_and(|y| y.4.is_some())) {
 						contents_list.remove(index);
 					}
 					if !contents_list.is_empty() {
@@ -476,25 +1004,35 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 pub fn openable_system(mut commands:    Commands,
 	                     mut ereader:     EventReader<GameEvent>,
 	                     mut msglog:      ResMut<MessageLog>,
+	                     mut turns:       ResMut<TurnCount>,
 	                     mut door_query:  Query<(Entity, &mut Body, &Description, &mut Openable, Option<&mut Opaque>, Option<&Obstructive>)>,
 	                     mut e_query:     Query<(Entity, &Body, &Description, Option<&Player>, Option<&mut Viewshed>), Without<Openable>>,
 ) {
 	// Bail out if no events or wrong type
 	if ereader.is_empty() { return; }
 	for event in ereader.iter() {
-		let mut atype = ActionType::NoAction;
+		let atype;
 		if let PlayerAction(action) | ActorAction(action) = event.etype {
 			if action != OpenItem && action != CloseItem {
 				continue;
 			} else {
 				atype = action;
 			}
+		} else {
+			continue;
 		}
-		if event.context.is_none() { continue; }
-		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
+		let econtext = if let Some(context) = event.context.as_ref() { context } else { continue; };
 		// If they can see it, add it to the list of doors they can choose
-		let (_enty, _body, a_desc, a_player, a_viewshed) = e_query.get_mut(econtext.subject).expect("actor should be listed in e_query");
+		let (_enty, _body, a_desc, a_player, a_viewshed) = if let Ok(value) = e_query.get_mut(econtext.subject) { value } else {
+			error!("* openable_system: subject {:?} of {} not found", econtext.subject, event.etype); // DEBUG: report a bad event
+			continue;
+		};
 		let is_player_action = a_player.is_some();
+		if !door_query.contains(econtext.object) {
+			debug!("* openable_system: object {:?} of {} is not Openable", econtext.object, event.etype); // DEBUG: report an incompatible target
+			if is_player_action { msglog.tell_player(&incompatible_action_msg(atype)); }
+			continue;
+		}
 		let mut message: String = "".to_string();
 		match atype {
 			ActionType::OpenItem => {
@@ -543,6 +1081,7 @@ pub fn openable_system(mut commands:    Commands,
 			}
 			_ => { }
 		}
+		if is_player_action { turns.increment(); }
 		if !message.is_empty() {
 			msglog.tell_player(&message);
 		}
@@ -550,6 +1089,8 @@ pub fn openable_system(mut commands:    Commands,
 }
 /// Handles anything related to the CanOperate component: ActorUse, ToggleSwitch, &c
 pub fn operable_system(mut ereader: EventReader<GameEvent>,
+                       mut msglog:  ResMut<MessageLog>,
+                       mut turns:   ResMut<TurnCount>,
                        //mut o_query: Query<(Entity, &Position, &Name), With<CanOperate>>,
                        mut d_query: Query<(Entity, &Description, &mut Device)>,
 ) {
@@ -559,55 +1100,66 @@ pub fn operable_system(mut ereader: EventReader<GameEvent>,
 			if action != UseItem {
 				continue;
 			}
+		} else {
+			continue;
 		}
-		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
+		let econtext = if let Some(context) = event.context.as_ref() { context } else { continue; };
 		if econtext.is_blank() { continue; }
-		let mut device = d_query.get_mut(econtext.object).expect("econtext.object should be in d_query");
+		let mut device = if let Ok(value) = d_query.get_mut(econtext.object) { value } else {
+			debug!("* operable_system: object {:?} of {} is not a Device", econtext.object, event.etype); // DEBUG: report an incompatible target
+			if let PlayerAction(_) = event.etype { msglog.tell_player(&incompatible_action_msg(UseItem)); }
+			continue;
+		};
 		if !device.2.pw_switch { // If it's not powered on, assume that function first
 			device.2.power_toggle();
 		}
+		if let PlayerAction(_) = event.etype { turns.increment(); }
 		// TODO: there's definitely going to be more stuff to implement here depending on the actual Device
 	}
 }
 /// Handles entities that can see physical light
 pub fn visibility_system(mut model:  ResMut<WorldModel>,
+	                       turns:      Res<TurnCount>,
 	                       mut seers:  Query<(&mut Viewshed, &Body, Option<&Player>, Option<&mut Memory>), Changed<Viewshed>>,
 	                       //observable: Query<(Entity, &Body)>,
 ) {
+	// NOTE: The FOV is only recalculated for viewsheds that were flagged as dirty, ie by movement or by a door changing
+	//       state, and the visible points are walked just once for the bounds check, the revealed_tiles, and the memory
+	//       To measure what that costs on a large ship: cargo test --release visibility_cost -- --ignored --nocapture
 	for (mut s_viewshed, s_body, player, s_memory) in &mut seers {
-		if s_viewshed.dirty {
-			assert!(s_body.ref_posn.z != -1, "! ERROR: Encountered negative z-level index!");
-			let map = &mut model.levels[s_body.ref_posn.z as usize];
+		if !s_viewshed.dirty { continue; }
+		let z_level = s_body.ref_posn.z;
+		// A seer with a corrupt position simply can't see anything, rather than bringing the whole game down
+		if z_level < 0 || z_level as usize >= model.levels.len() {
+			warn!("* visibility_system: seer at {} is not on any level, skipping it", s_body.ref_posn); // DEBUG: report invalid z-level
 			s_viewshed.visible_points.clear();
-			// An interesting thought: should an Entity be able to 'see' from every part of its body?
-			// Right now it is calculated just from the Entity's reference point, the 'head'
-			s_viewshed.visible_points = field_of_view(posn_to_point(&s_body.ref_posn), s_viewshed.range, map);
-			s_viewshed.visible_points.retain(|p| p.x >= 0 && p.x < map.width as i32
-				                             && p.y >= 0 && p.y < map.height as i32
-			);
-			if let Some(_player) = player { // if this is the player...
-				for s_posn in &s_viewshed.visible_points { // For all the player's visible tiles...
-					// ... set the corresponding tile in the map.revealed_tiles to TRUE
-					let map_index = map.to_index(s_posn.x, s_posn.y);
-					map.revealed_tiles[map_index] = true;
-				}
+			s_viewshed.dirty = false;
+			continue;
+		}
+		let (map_width, map_height) = (model.levels[z_level as usize].width as i32, model.levels[z_level as usize].height as i32);
+		// An interesting thought: should an Entity be able to 'see' from every part of its body?
+		// Right now it is calculated just from the Entity's reference point, the 'head'
+		let mut new_points = field_of_view(posn_to_point(&s_body.ref_posn), s_viewshed.range, &model.levels[z_level as usize]);
+		new_points.retain(|p| p.x >= 0 && p.x < map_width && p.y >= 0 && p.y < map_height);
+		let is_player = player.is_some();
+		let mut observations = Vec::with_capacity(if s_memory.is_some() { new_points.len() } else { 0 });
+		for v_posn in new_points.iter() {
+			if is_player { // The player's visible tiles are marked as revealed on the map
+				let map = &mut model.levels[z_level as usize];
+				let map_index = map.to_index(v_posn.x, v_posn.y);
+				map.revealed_tiles[map_index] = true;
 			}
-			if let Some(mut recall) = s_memory { // If the seer entity has a memory...
-				let mut observations = Vec::new();
-				for v_posn in &s_viewshed.visible_points { // Iterate on all points they can see:
-					let observed_posn = Position::new(v_posn.x, v_posn.y, s_body.ref_posn.z);
-					let observation = model.get_contents_at(observed_posn); // Get the list of observed entities
-					let some_observed_entys = if !observation.is_empty() {
-						Some(observation)
-					} else {
-						None
-					};
-					observations.push((observed_posn, some_observed_entys));
-				}
-				recall.update(observations);
+			if s_memory.is_some() { // If the seer entity has a memory, record what's on each visible tile
+				let observed_posn = Position::new(v_posn.x, v_posn.y, z_level);
+				let observation = model.get_contents_at(observed_posn); // Get the list of observed entities
+				observations.push((observed_posn, if observation.is_empty() { None } else { Some(observation) }));
 			}
-			s_viewshed.dirty = false;
 		}
+		if let Some(mut recall) = s_memory {
+			recall.update(observations, turns.turns);
+		}
+		s_viewshed.visible_points = new_points;
+		s_viewshed.dirty = false;
 	}
 }
 
@@ -619,11 +1171,17 @@ pub fn new_player_spawn(mut commands: Commands,
 	                      mut p_query:  Query<(Entity, &Player)>,
 	                      mut msglog:   ResMut<MessageLog>,
 	                      mut global_rng: ResMut<GlobalRng>,
+	                      mut planq_data: ResMut<PlanqData>,
+	                      q_query:      Query<&Planq>,
 ) {
 	if !p_query.is_empty() {
 		info!("* Existing player found, treating as a loaded game"); // DEBUG: announce possible game load
 		let player = p_query.get_single_mut().expect("A loaded game should have a valid player object already");
 		commands.entity(player.0).insert(Viewshed::new(8));
+		// The PlanqData resource isn't remapped on load, so restore the jack connection from the Planq component
+		if let Ok(q_planq) = q_query.get_single() {
+			planq_data.jack_cnxn = q_planq.jack_cnxn;
+		}
 		return;
 	}
 	// DEBUG: testing multitile entities
@@ -637,6 +1195,7 @@ pub fn new_player_spawn(mut commands: Commands,
 	// DEBUG: end testing code
 	let player = commands.spawn((
 		Player { },
+		Facing::new(Direction::N),
 		ActionSet::new(),
 		Description::new().name("Pleyeur").desc("Still your old self."),
 		*spawnpoint,
@@ -646,8 +1205,9 @@ pub fn new_player_spawn(mut commands: Commands,
 		Obstructive::default(),
 		Container::default(),
 		Memory::new(),
+		Stamina::new(100),
 	)).id();
-	model.add_contents(&vec![*spawnpoint], 0, player);
+	model.add_contents(&vec![*spawnpoint], RenderLayer::PLAYER, player);
 	//debug!("* new_player_spawn spawned @{spawnpoint:?}"); // DEBUG: print spawn location of new player
 	let planq = commands.spawn((
 		Planq::new(),
@@ -663,6 +1223,8 @@ pub fn new_player_spawn(mut commands: Commands,
 	commands.spawn(DataSampleTimer::new().source("current_time"));
 	commands.spawn(DataSampleTimer::new().source("planq_battery"));
 	commands.spawn(DataSampleTimer::new().source("planq_mode"));
+	commands.spawn(DataSampleTimer::new().source("player_stamina"));
+	commands.spawn(DataSampleTimer::new().source("turn_count"));
 	msglog.tell_player("[[fg:green]]WELCOME[[end]] TO [[fg:blue,mod:+italic]]SPACEGAME[[end]]");
 }
 /// Spawns a new LMR at the specified Position, using default values
@@ -672,6 +1234,8 @@ pub fn new_lmr_spawn(mut commands:  Commands,
 	let lmr_spawnpoint = (12, 12, 0).into();
 	commands.spawn((
 		LMR         { },
+		Friendly    { },
+		Facing::new(Direction::S),
 		ActionSet::new(),
 		Description::new().name("LMR").desc("The Light Maintenance Robot is awaiting instructions."),
 		lmr_spawnpoint, // TODO: remove magic numbers
@@ -711,6 +1275,67 @@ pub fn test_npc_spawn(mut commands: Commands,
 }
 
 // ###: UTILITIES
+/// Describes the terrain at the given Position, plus the names of everything else located there
+pub fn describe_tile(model:     &WorldModel,
+	                   target:    Position,
+	                   observed:  Option<Entity>,
+	                   e_query:   &Query<(Entity, &Description, Option<&Lockable>, Option<&Openable>, Option<&Device>, Option<&Container>)>,
+) -> String {
+	let mut output = format!("It's on a {} tile", model.get_tiletype_at(target));
+	match (model.is_blocked_at(target), model.is_opaque_at(target)) {
+		(true, true)  => { output.push_str(" that is blocked and opaque"); }
+		(true, false) => { output.push_str(" that is blocked"); }
+		(false, true) => { output.push_str(" that is opaque"); }
+		_ => { }
+	}
+	let mut names = Vec::new();
+	for enty in model.get_contents_at(target) {
+		if Some(enty) == observed { continue; }
+		if let Ok((_enty, e_desc, ..)) = e_query.get(enty) {
+			names.push(format!("a {}", e_desc.name));
+		}
+	}
+	if names.is_empty() {
+		output.push('.');
+	} else {
+		output.push_str(&format!(", along with {}.", names.join(", ")));
+	}
+	output
+}
+/// Returns true if nothing should be left lying on the given Position, ie it's a doorway or a door's margin
+pub fn is_keep_clear(model: &WorldModel, d_query: &Query<&Openable>, target: Position) -> bool {
+	if model.layout.get_celltype_at(target) == Some(CellType::Margin) { return true; }
+	model.get_contents_at(target).iter().any(|x| d_query.contains(*x))
+}
+/// Picks a spot to drop an item near the given Position: underfoot if possible, else the first adjacent open tile
+/// that isn't a doorway or margin; if there's nowhere better, the item just gets dropped underfoot anyway
+pub fn find_drop_posn(model: &WorldModel, d_query: &Query<&Openable>, origin: Position) -> Position {
+	if !is_keep_clear(model, d_query, origin) { return origin; }
+	let neighbors = [(0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)];
+	for (dx, dy) in neighbors.iter() {
+		let target = Position::new(origin.x + dx, origin.y + dy, origin.z);
+		if !model.in_bounds(target) { continue; }
+		if !model.is_blocked_at(target) && !is_keep_clear(model, d_query, target) {
+			return target;
+		}
+	}
+	origin
+}
+/// Provides the feedback for an action that was attempted on an Entity that doesn't support it
+pub fn incompatible_action_msg(action: ActionType) -> String {
+	match action {
+		ActionType::MoveItem => { "You can't pick that up.".to_string() }
+		_ => { format!("You can't {} that.", action.to_string().to_lowercase()) }
+	}
+}
+/// Provides the name from an Entity's Description, or a generic fallback if the Entity had no Description
+pub fn name_of(d_query: &Query<&Description>, enty: Entity) -> String {
+	if let Ok(e_desc) = d_query.get(enty) {
+		e_desc.name.clone()
+	} else {
+		NAMELESS.to_string()
+	}
+}
 /// Converts my Position type into a bracket_pathfinding::Point
 pub fn posn_to_point(input: &Position) -> Point { Point { x: input.x, y: input.y } }
 /// If the Entity exists, will return an Iterator that contains info on all the Components that belong to that Entity
@@ -756,4 +1381,368 @@ impl DurationFmtExt for Duration {
 	}
 }
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bevy::app::{App, Update};
+	use bevy::ecs::event::Events;
+	use bevy::MinimalPlugins;
+	use bevy_save::prelude::*;
+	use crate::mason::logical_map::GraphRoom;
+
+	/// Sets up a bare App with the events and resources that most of the systems in here rely on
+	fn test_app() -> App {
+		let mut app = App::new();
+		app.add_plugins(MinimalPlugins)
+			.add_plugins(SavePlugins)
+			.add_event::<GameEvent>()
+			.add_event::<PlanqEvent>()
+			.insert_resource(MessageLog::new(vec!["world".to_string(), "combat".to_string(), "debug".to_string()]))
+			.insert_resource(PlanqData::new())
+			.insert_resource(TurnCount::default());
+		app
+	}
+
+	#[test]
+	fn jack_disconnect_survives_save_load() {
+		let mut app = test_app();
+		app.register_saveable::<Planq>()
+			.add_systems(Update, access_port_system);
+		app.world.spawn(Planq::new());
+		let port = app.world.spawn((AccessPort::default(), Description::new().name("terminal"))).id();
+		let player = app.world.spawn_empty().id();
+		app.world.send_event(GameEvent::new(PlanqConnect(port), Some(player), Some(port)));
+		app.update();
+		let mut q_query = app.world.query::<&Planq>();
+		assert_eq!(q_query.single(&app.world).jack_cnxn, port);
+		// The handler sends the disconnect with the old port still named as the object
+		app.world.send_event(GameEvent::new(PlanqConnect(Entity::PLACEHOLDER), Some(player), Some(port)));
+		app.update();
+		assert_eq!(q_query.single(&app.world).jack_cnxn, Entity::PLACEHOLDER);
+		assert_eq!(app.world.resource::<PlanqData>().jack_cnxn, Entity::PLACEHOLDER);
+		// Round-trip the world through a snapshot, the same way that a save and a load would
+		let snapshot = app.world.snapshot();
+		snapshot.applier(&mut app.world).despawn(DespawnMode::Unmapped).apply().expect("the snapshot should apply");
+		let mut q_query = app.world.query::<&Planq>();
+		assert_eq!(q_query.single(&app.world).jack_cnxn, Entity::PLACEHOLDER);
+	}
+
+	#[test]
+	fn opening_a_door_swaps_its_actions_from_open_to_close() {
+		let mut app = test_app();
+		app.add_systems(Update, (openable_system, action_refresh_system, action_referee_system).chain());
+		let door_posn = Position::new(2, 2, 0);
+		let door = app.world.spawn((
+			Description::new().name("door"),
+			Body::small(door_posn, ScreenCell::placeholder()),
+			Openable::new(false, "▯", "+"),
+			Obstructive::default(),
+			ActionSet::new(),
+		)).id();
+		let player = app.world.spawn((
+			Player::default(),
+			Description::new().name("player"),
+			Body::small(Position::new(1, 2, 0), ScreenCell::placeholder()),
+		)).id();
+		app.update();
+		let actions = &app.world.get::<ActionSet>(door).expect("door lost its ActionSet").actions;
+		assert!(actions.contains(&ActionType::OpenItem));
+		assert!(!actions.contains(&ActionType::CloseItem));
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::OpenItem), Some(player), Some(door)));
+		app.update();
+		let actions = &app.world.get::<ActionSet>(door).expect("door lost its ActionSet").actions;
+		assert!(actions.contains(&ActionType::CloseItem));
+		assert!(!actions.contains(&ActionType::OpenItem));
+		assert!(app.world.get::<Obstructive>(door).is_none());
+	}
+
+	#[test]
+	fn the_player_is_only_offered_what_they_are_able_to_do() {
+		let mut app = test_app();
+		app.add_systems(Update, (action_refresh_system, action_referee_system).chain());
+		let wrench = app.world.spawn((Description::new().name("wrench"), Portable::empty(), ActionSet::new())).id();
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Mobile::default(), ActionSet::new())).id();
+		app.update();
+		let offered = |app: &App| {
+			let p_actions = app.world.get::<ActionSet>(player).expect("player lost their ActionSet");
+			p_actions.intersect(app.world.get::<ActionSet>(wrench).expect("wrench lost its ActionSet"))
+		};
+		// Without anywhere to put it, the player can look at the wrench but not pick it up
+		assert!(offered(&app).contains(&ActionType::Examine));
+		assert!(!offered(&app).contains(&ActionType::MoveItem));
+		app.world.entity_mut(player).insert(Container::default());
+		app.update();
+		assert!(offered(&app).contains(&ActionType::MoveItem));
+		assert!(!offered(&app).contains(&ActionType::OpenItem));
+	}
+
+	#[test]
+	fn game_is_won_by_carrying_every_critical_item_and_lost_in_vacuum() {
+		let mut app = test_app();
+		let mut level = WorldMap::new(5, 5);
+		let index = level.to_index(4, 4);
+		level.tiles[index] = Tile::new_vacuum();
+		let mut model = WorldModel::default();
+		model.add_level(level);
+		app.insert_resource(model)
+			.insert_resource(GameOutcome::default())
+			.add_systems(Update, game_outcome_system);
+		let player = app.world.spawn((Player::default(), Body::small(Position::new(1, 1, 0), ScreenCell::placeholder()))).id();
+		app.world.spawn((Critical::default(), Portable::new(player)));
+		let second = app.world.spawn((Critical::default(), Portable::empty())).id();
+		app.update();
+		assert_eq!(*app.world.resource::<GameOutcome>(), GameOutcome::Ongoing);
+		// Walking out into the vacuum loses the game, and nothing afterward changes that
+		app.world.get_mut::<Body>(player).expect("player lost their Body").move_to(Position::new(4, 4, 0));
+		app.update();
+		assert_eq!(*app.world.resource::<GameOutcome>(), GameOutcome::Lost);
+		let msglog = app.world.resource::<MessageLog>();
+		assert_eq!(msglog.get_log_as_messages("combat", 0).len(), 1);
+		assert_eq!(msglog.get_log_as_messages("world", 0).len(), 1);
+		app.world.get_mut::<Portable>(second).expect("item lost its Portable").carrier = player;
+		app.update();
+		assert_eq!(*app.world.resource::<GameOutcome>(), GameOutcome::Lost);
+		// Whereas picking up the last of the Critical items wins it
+		app.insert_resource(GameOutcome::default());
+		app.world.get_mut::<Body>(player).expect("player lost their Body").move_to(Position::new(1, 1, 0));
+		app.update();
+		assert_eq!(*app.world.resource::<GameOutcome>(), GameOutcome::Won);
+	}
+
+	#[test]
+	fn drop_onto_a_tile_off_the_map_falls_back_to_underfoot() {
+		let mut app = test_app();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(5, 5));
+		app.insert_resource(model)
+			.insert_resource(RunStats::default())
+			.add_systems(Update, item_collection_system);
+		let origin = Position::new(0, 0, 0);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(origin, ScreenCell::placeholder()), Container::default())).id();
+		let item = app.world.spawn((Description::new().name("wrench"), Body::small(origin, ScreenCell::placeholder()), Portable::new(player), IsCarried::default())).id();
+		for target in [Position::new(-1, 0, 0), Position::new(0, 0, 1)] {
+			let mut event = GameEvent::new(PlayerAction(ActionType::DropItem), Some(player), Some(item));
+			if let Some(context) = event.context.as_mut() { context.target = Some(target); }
+			app.world.send_event(event);
+			app.update();
+			assert_eq!(app.world.get::<Body>(item).map(|x| x.ref_posn), Some(origin));
+		}
+		let log = app.world.resource::<MessageLog>().get_log_as_messages("world", 0);
+		assert!(log.iter().any(|x| x.text == "There's no room to put it there."));
+		assert!(app.world.resource::<WorldModel>().get_contents_at(origin).contains(&item));
+	}
+
+	#[test]
+	fn picking_up_several_items_at_once_gives_one_summary() {
+		let mut app = test_app();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(5, 5));
+		app.insert_resource(model)
+			.insert_resource(RunStats::default())
+			.add_systems(Update, item_collection_system);
+		let posn = Position::new(2, 2, 0);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(posn, ScreenCell::placeholder()), Container::default())).id();
+		for name in ["wrench", "keycard", "flashlight"] {
+			let item = app.world.spawn((Description::new().name(name), Body::small(posn, ScreenCell::placeholder()), Portable::new(Entity::PLACEHOLDER))).id();
+			app.world.send_event(GameEvent::new(PlayerAction(ActionType::MoveItem), Some(player), Some(item)));
+		}
+		// A broken event in the same batch isn't counted, since nothing was picked up
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::MoveItem), Some(player), Some(player)));
+		app.update();
+		let log: Vec<String> = app.world.resource::<MessageLog>().get_log_as_messages("world", 0).into_iter().map(|x| x.text).collect();
+		assert!(log.contains(&"Picked up 3 items.".to_string()), "{:?}", log);
+		assert!(!log.iter().any(|x| x.starts_with("Obtained")), "{:?}", log);
+		assert_eq!(app.world.resource::<RunStats>().items_collected, 3);
+		// Whereas a single pickup still names the item
+		let item = app.world.spawn((Description::new().name("multitool"), Body::small(posn, ScreenCell::placeholder()), Portable::new(Entity::PLACEHOLDER))).id();
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::MoveItem), Some(player), Some(item)));
+		app.update();
+		let log = app.world.resource::<MessageLog>().get_log_as_messages("world", 1);
+		assert_eq!(log[0].text, "Obtained a multitool.");
+	}
+
+	#[test]
+	fn destroyed_items_are_taken_off_the_map() {
+		let mut app = test_app();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(5, 5));
+		app.insert_resource(model)
+			.insert_resource(RunStats::default())
+			.add_systems(Update, item_collection_system);
+		let posn = Position::new(2, 2, 0);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(1, 2, 0), ScreenCell::placeholder()), Container::default())).id();
+		let item = app.world.spawn((Description::new().name("wrench"), Body::small(posn, ScreenCell::placeholder()), Portable::new(Entity::PLACEHOLDER))).id();
+		app.world.resource_mut::<WorldModel>().add_contents(&vec![posn], RenderLayer::ITEM, item);
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::KillItem), Some(player), Some(item)));
+		app.update();
+		assert!(app.world.get_entity(item).is_none());
+		assert!(!app.world.resource::<WorldModel>().get_contents_at(posn).contains(&item));
+	}
+
+	#[test]
+	fn multilevel_blockers_are_indexed_on_every_deck() {
+		let mut app = test_app();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(10, 10));
+		model.add_level(WorldMap::new(10, 10));
+		app.insert_resource(model).add_systems(Update, map_indexing_system);
+		let mut body = Body::small(Position::new(3, 3, 0), ScreenCell::placeholder());
+		body.span_decks(2, 2);
+		app.world.spawn((body, Multilevel::new(2), Obstructive::default()));
+		app.update();
+		let model = app.world.resource::<WorldModel>();
+		assert!(model.is_blocked_at(Position::new(3, 3, 0)));
+		assert!(model.is_blocked_at(Position::new(3, 3, 1)));
+		assert!(!model.is_blocked_at(Position::new(4, 3, 1)));
+		// Rebuilding just the upper deck has to put the upper part of it back as well
+		app.world.resource_mut::<WorldModel>().levels[1].needs_reindex = true;
+		app.update();
+		assert!(app.world.resource::<WorldModel>().is_blocked_at(Position::new(3, 3, 1)));
+	}
+
+	#[test]
+	fn moving_a_blocker_only_changes_its_old_and_new_tiles() {
+		let mut app = test_app();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(10, 10));
+		model.add_level(WorldMap::new(10, 10));
+		app.insert_resource(model).add_systems(Update, map_indexing_system);
+		let (old_posn, new_posn) = (Position::new(3, 3, 0), Position::new(4, 3, 0));
+		let mover = app.world.spawn((Body::small(old_posn, ScreenCell::placeholder()), Obstructive::default())).id();
+		app.world.spawn((Body::small(Position::new(6, 6, 1), ScreenCell::placeholder()), Obstructive::default()));
+		app.update();
+		// A mark that a rebuild of the upper deck would wipe out, to show that it's left alone
+		let marker = Position::new(1, 1, 1);
+		app.world.resource_mut::<WorldModel>().set_blocked_state(marker, true);
+		let before: Vec<Vec<bool>> = app.world.resource::<WorldModel>().levels.iter().map(|x| x.blocked_tiles.clone()).collect();
+		app.world.get_mut::<Body>(mover).unwrap().move_to(new_posn);
+		app.update();
+		let model = app.world.resource::<WorldModel>();
+		let changed: Vec<Position> = model.levels.iter().enumerate().flat_map(|(z, level)| {
+			let before = &before[z];
+			(0..level.blocked_tiles.len()).filter(move |i| level.blocked_tiles[*i] != before[*i])
+				.map(move |i| Position::new((i % level.width) as i32, (i / level.width) as i32, z as i32))
+		}).collect();
+		assert_eq!(changed, vec![old_posn, new_posn]);
+		assert!(model.is_blocked_at(marker));
+		assert!(model.is_blocked_at(Position::new(6, 6, 1)));
+	}
+
+	#[test]
+	fn only_actions_that_succeed_use_up_a_turn() {
+		let mut app = test_app();
+		app.add_systems(Update, (lockable_system, turn_scheduler_system))
+			.insert_resource(GameClock::default())
+			.insert_resource(NpcTurn::default());
+		let origin = Position::new(1, 1, 0);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(origin, ScreenCell::placeholder()))).id();
+		let locker = app.world.spawn((Description::new().name("locker"), Body::small(origin, ScreenCell::placeholder()), Lockable { is_locked: true, key_id: 3 })).id();
+		// Without any keys, the unlock attempt fails, but it still takes time
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::UnlockItem), Some(player), Some(locker)));
+		app.update();
+		assert_eq!(app.world.resource::<TurnCount>().turns, 0);
+		assert!(app.world.resource::<GameClock>().ticks > 0);
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::LockItem), Some(player), Some(locker)));
+		app.update();
+		assert_eq!(app.world.resource::<TurnCount>().turns, 1);
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::Wait), Some(player), None));
+		app.update();
+		assert_eq!(app.world.resource::<TurnCount>().turns, 2);
+	}
+
+	#[test]
+	fn crew_head_for_their_station_and_greet_the_player_once() {
+		let mut app = test_app();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(12, 12));
+		let bridge: Vec<Position> = (6..=8).flat_map(|y| (6..=8).map(move |x| Position::new(x, y, 0))).collect();
+		model.layout.rooms.push(GraphRoom::from_tiles("Bridge", bridge));
+		let mut npc_turn = NpcTurn::default();
+		npc_turn.begin(1);
+		app.insert_resource(model).insert_resource(npc_turn).add_systems(Update, crew_system);
+		let player = app.world.spawn((Player::default(), Body::small(Position::new(1, 10, 0), ScreenCell::placeholder()))).id();
+		app.world.spawn((Crew::new(CrewRole::Captain), Mobile::default(), Description::new().name("captain"),
+			Body::small(Position::new(1, 1, 0), ScreenCell::placeholder())));
+		app.update();
+		let events = app.world.resource::<Events<GameEvent>>();
+		let actions: Vec<GameEventType> = events.get_reader().iter(events).map(|x| x.etype).collect();
+		assert_eq!(actions, vec![ActorAction(MoveTo(Direction::SE))]);
+		// Standing next to the player interrupts the walk, and the greeting only comes the first time
+		app.world.get_mut::<Body>(player).unwrap().move_to(Position::new(2, 2, 0));
+		app.update();
+		app.update();
+		let log = app.world.resource::<MessageLog>().get_log_as_messages("world", 0);
+		assert_eq!(log.iter().filter(|x| x.text.starts_with("The captain says")).count(), 1);
+	}
+
+	#[test]
+	#[ignore] // This is a timing run rather than a pass/fail test; see the note in visibility_system for how to run it
+	fn visibility_cost_on_a_large_ship() {
+		let mut app = test_app();
+		// A 200x200 deck split into a grid of 10x10 rooms, with a doorway in the middle of each wall
+		let mut level = WorldMap::new(200, 200);
+		for y in 0..200 {
+			for x in 0..200 {
+				if (x % 10 == 0 || y % 10 == 0) && x % 10 != 5 && y % 10 != 5 {
+					let index = level.to_index(x, y);
+					level.tiles[index] = Tile::new_wall();
+				}
+			}
+		}
+		let mut model = WorldModel::default();
+		model.add_level(level);
+		app.insert_resource(model).add_systems(Update, visibility_system);
+		let mut seers = Vec::new();
+		for index in 0..100 {
+			let posn = Position::new(3 + (index % 10) * 20, 3 + (index / 10) * 20, 0);
+			seers.push(app.world.spawn((Viewshed::new(40), Memory::default(), Body::small(posn, ScreenCell::placeholder()))).id());
+		}
+		app.world.entity_mut(seers[0]).insert(Player::default());
+		let rounds = 50;
+		let start = std::time::Instant::now();
+		for _ in 0..rounds {
+			for seer in seers.iter() {
+				app.world.get_mut::<Viewshed>(*seer).unwrap().dirty = true;
+			}
+			app.update();
+		}
+		let elapsed = start.elapsed();
+		println!("visibility_system: {} seers on a 200x200 deck, {:?} per update", seers.len(), elapsed / rounds);
+		assert!(app.world.get::<Viewshed>(seers[0]).unwrap().visible_points.len() > 1);
+	}
+
+	#[test]
+	fn the_crew_only_move_after_the_player_has_taken_a_turn() {
+		let mut app = test_app();
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(12, 12));
+		let bridge: Vec<Position> = (6..=8).flat_map(|y| (6..=8).map(move |x| Position::new(x, y, 0))).collect();
+		model.layout.rooms.push(GraphRoom::from_tiles("Bridge", bridge));
+		app.insert_resource(model)
+			.insert_resource(GameClock::default())
+			.insert_resource(NpcTurn::default())
+			.add_systems(Update, turn_scheduler_system)
+			.add_systems(Update, crew_system.run_if(npc_turn_ready).after(turn_scheduler_system).before(end_npc_turn_system))
+			.add_systems(Update, end_npc_turn_system.after(turn_scheduler_system));
+		let player = app.world.spawn((Player::default(), Body::small(Position::new(1, 10, 0), ScreenCell::placeholder()))).id();
+		app.world.spawn((Crew::new(CrewRole::Captain), Mobile::default(), Description::new().name("captain"),
+			Body::small(Position::new(1, 1, 0), ScreenCell::placeholder())));
+		let mut reader = app.world.resource::<Events<GameEvent>>().get_reader();
+		let mut count_moves = |app: &App| {
+			let events = app.world.resource::<Events<GameEvent>>();
+			reader.iter(events).filter(|x| matches!(x.etype, ActorAction(_))).count()
+		};
+		app.update();
+		assert_eq!(count_moves(&app), 0);
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::Wait), Some(player), None));
+		app.update();
+		assert_eq!(count_moves(&app), 1);
+		// The crew's own moves don't reopen the gate, so they wait for the player again
+		app.update();
+		app.update();
+		assert_eq!(count_moves(&app), 0);
+		assert!(!app.world.resource::<NpcTurn>().pending);
+	}
+}
+
 // EOF
@@ -62,6 +62,29 @@ impl WorldModel {
 		self.portals.push(Portal::new().from(left).to(right).twoway(bidir));
 		self.portals.sort(); // Helps prevent duplication and speeds up retrieval
 	}
+	/// Appends a new level to the top of the stack, with its blocked and opaque tilemaps ready to use
+	/// Returns the z-index of the new level
+	pub fn add_level(&mut self, mut new_level: WorldMap) -> usize {
+		new_level.update_tilemaps();
+		self.levels.push(new_level);
+		self.levels.len() - 1
+	}
+	/// Takes a level out of the stack, along with any portals and layout rooms on it; the levels above it each move
+	/// down by one, and their portals and rooms are renumbered to match
+	/// The Bodies of any entities on the higher levels still have their old z-index, so the caller must move them too
+	pub fn remove_level(&mut self, z_level: usize) -> Option<WorldMap> {
+		if z_level >= self.levels.len() { return None; }
+		let old_level = self.levels.remove(z_level);
+		let target = z_level as i32;
+		self.portals.retain(|x| x.left.z != target && x.right.z != target);
+		for portal in self.portals.iter_mut() {
+			if portal.left.z > target { portal.left.z -= 1; }
+			if portal.right.z > target { portal.right.z -= 1; }
+		}
+		self.portals.sort();
+		self.layout.remove_deck(target);
+		Some(old_level)
+	}
 	/// Retrieve the destination of a given Portal, if any
 	pub fn get_exit(&mut self, entry: Position) -> Option<Position> {
 		// if the position belongs to a portal in the list, return its destination
@@ -73,15 +96,27 @@ impl WorldModel {
 			portal
 		}
 	}
-	/// Retrieve the tiletype of the given Position
+	/// Retrieve the tiletype of the given Position; anywhere off the map is Vacuum
 	pub fn get_tiletype_at(&self, target: Position) -> TileType {
+		if !self.in_bounds(target) { return TileType::Vacuum; }
 		let index = self.levels[target.z as usize].to_index(target.x, target.y);
 		self.levels[target.z as usize].tiles[index].ttype
 	}
+	/// Returns True if the Position is on one of the levels, and inside that level's edges
+	pub fn in_bounds(&self, target: Position) -> bool {
+		if target.z < 0 { return false; }
+		let level = if let Some(value) = self.levels.get(target.z as usize) { value } else { return false; };
+		target.x >= 0 && target.y >= 0 && target.x < level.width as i32 && target.y < level.height as i32
+	}
 	/// Adds the given Entity as an occupant at the specified positions, with the given priority
+	/// Any Position that isn't on the map is skipped, since there's no Tile there to hold the Entity
 	pub fn add_contents(&mut self, posns: &Vec<Position>, priority: i32, enty: Entity) {
 		trace!("add_contents: {:?} for enty {:?} at priority {}", posns, enty, priority); // DEBUG: log the call to add_contents
 		for posn in posns {
+			if !self.in_bounds(*posn) {
+				warn!("* add_contents: {} is off the map, skipping it for enty {:?}", posn, enty); // DEBUG: report bad contents posn
+				continue;
+			}
 			self.levels[posn.z as usize].add_occupant(priority, enty, *posn);
 		}
 	}
@@ -89,19 +124,195 @@ impl WorldModel {
 	pub fn remove_contents(&mut self, posns: &Vec<Position>, enty: Entity) {
 		trace!("remove_contents: {:?} for enty {:?}", posns, enty); // DEBUG: log the call to remove_contents
 		for posn in posns {
+			if !self.in_bounds(*posn) { continue; }
 			self.levels[posn.z as usize].remove_occupant(enty, *posn);
 		}
 	}
+	/// Throws out all of the tile contents and rebuilds them from the given list of (Entity, priority, positions)
+	/// This is required after loading a game, since the saved contents still refer to the Entity IDs from before the save
+	pub fn reload_tile_contents(&mut self, occupants: &[(Entity, i32, Vec<Position>)]) {
+		for level in self.levels.iter_mut() {
+			for tile in level.tiles.iter_mut() {
+				tile.contents.clear();
+			}
+			level.needs_reindex = true;
+		}
+		for (enty, priority, posns) in occupants.iter() {
+			self.add_contents(posns, *priority, *enty);
+		}
+	}
+	/// Removes the given Entity from every Tile on every level; use this when an Entity is despawned, since its Body
+	/// may not be available anymore to say where it was
+	pub fn remove_entity_everywhere(&mut self, enty: Entity) {
+		trace!("remove_entity_everywhere: enty {:?}", enty); // DEBUG: log the call to remove_entity_everywhere
+		for level in self.levels.iter_mut() {
+			let mut found = false;
+			for tile in level.tiles.iter_mut() {
+				let count = tile.contents.len();
+				tile.remove_from_contents(enty);
+				found |= tile.contents.len() != count;
+			}
+			// The entity may have been blocking movement or sight, so the level's maps need to be rebuilt
+			if found { level.needs_reindex = true; }
+		}
+	}
 	/// Retrieves a list of all the occupants at the given Position
 	pub fn get_contents_at(&self, target: Position) -> Vec<Entity> {
+		if !self.in_bounds(target) { return Vec::new(); }
 		self.levels[target.z as usize].get_contents_at(target)
 	}
+	/// Retrieves the occupants of every tile in the rectangle between the two corners, inclusive, on the first corner's level
+	/// Only the tiles in the rectangle are visited, so this is much cheaper than checking every Entity's position
+	pub fn get_contents_in_rect(&self, ul_corner: Position, dr_corner: Position) -> Vec<(Position, Entity)> {
+		let mut output = Vec::new();
+		let level = if let Some(level) = self.levels.get(ul_corner.z as usize) { level } else { return output; };
+		let (min_x, max_x) = (ul_corner.x.min(dr_corner.x).max(0), ul_corner.x.max(dr_corner.x).min(level.width as i32 - 1));
+		let (min_y, max_y) = (ul_corner.y.min(dr_corner.y).max(0), ul_corner.y.max(dr_corner.y).min(level.height as i32 - 1));
+		for y in min_y..=max_y {
+			for x in min_x..=max_x {
+				let posn = Position::new(x, y, ul_corner.z);
+				for enty in level.get_contents_at(posn) {
+					output.push((posn, enty));
+				}
+			}
+		}
+		output
+	}
+	/// Retrieves the occupants of every tile within the given range of the center, using the same distance as in_range_of()
+	pub fn get_contents_in_range(&self, center: Position, range: i32) -> Vec<(Position, Entity)> {
+		let ul_corner = Position::new(center.x - range, center.y - range, center.z);
+		let dr_corner = Position::new(center.x + range, center.y + range, center.z);
+		let mut output = self.get_contents_in_rect(ul_corner, dr_corner);
+		output.retain(|(posn, _)| posn.in_range_of(&center, range));
+		output
+	}
+	/// As get_contents_in_range(), but lists each Entity only once, even if it covers more than one of the tiles
+	pub fn get_entities_in_range(&self, center: Position, range: i32) -> Vec<Entity> {
+		let mut output: Vec<Entity> = Vec::new();
+		for (_, enty) in self.get_contents_in_range(center, range) {
+			if !output.contains(&enty) { output.push(enty); }
+		}
+		output
+	}
 	/// Returns True if the Position contains an Entity with Obstructive, or if the Tiletype is a blocking type
+	/// Anywhere off the map counts as blocked
 	pub fn is_blocked_at(&self, target: Position) -> bool {
 		trace!("* is_blocked_at({:?})", target); // DEBUG: log the call to is_blocked_at
+		if !self.in_bounds(target) { return true; }
 		let index = self.levels[target.z as usize].to_index(target.x, target.y);
 		self.levels[target.z as usize].blocked_tiles[index]
 	}
+	/// Copies each themed room's color onto the tiles that it covers, so the camera doesn't need to look up rooms
+	pub fn apply_room_tints(&mut self) {
+		for room in self.layout.rooms.iter() {
+			let tint = if let Some(color) = room.tint { color } else { continue; };
+			for posn in room.new_interior.keys() {
+				if let Some(level) = self.levels.get_mut(posn.z as usize) {
+					let index = level.to_index(posn.x, posn.y);
+					if index < level.tints.len() { level.tints[index] = Some(tint); }
+				}
+			}
+		}
+	}
+	/// DEBUG: Marks every tile on the given level as revealed; does not change what is currently visible
+	pub fn reveal_level(&mut self, z_level: usize) {
+		if let Some(level) = self.levels.get_mut(z_level) {
+			level.revealed_tiles.iter_mut().for_each(|x| *x = true);
+		}
+	}
+	/// Checks that an actor could be placed at the given Position in the named room, returning the reason if not
+	pub fn check_spawnpoint(&self, target: Position, room_name: &str) -> Result<(), String> {
+		let level = if let Some(value) = self.levels.get(target.z as usize) { value } else {
+			return Err(format!("there is no deck {}", target.z));
+		};
+		if target.x < 0 || target.y < 0 || target.x >= level.width as i32 || target.y >= level.height as i32 {
+			return Err(format!("{} is off the edge of the map", target));
+		}
+		if self.layout.get_room_name(target).as_deref() != Some(room_name) {
+			return Err(format!("{} is not inside the {}", target, room_name));
+		}
+		if self.is_blocked_at(target) { return Err(format!("{} is blocked", target)); }
+		if !self.get_contents_at(target).is_empty() { return Err(format!("{} is already occupied", target)); }
+		Ok(())
+	}
+	/// Checks that an item could be put on all of the given Positions: each one has to be open floor that the room
+	/// layouts haven't set aside for anything else, with nothing standing on it yet
+	pub fn can_place_at(&self, posns: &[Position]) -> bool {
+		posns.iter().all(|x| self.in_bounds(*x)
+			&& self.get_tiletype_at(*x) == TileType::Floor
+			&& !self.is_blocked_at(*x)
+			&& self.layout.get_celltype_at(*x) == Some(CellType::Open)
+			&& self.get_contents_at(*x).is_empty())
+	}
+	/// Returns True if the target Position can be reached on foot from the start Position, ie if the start connects
+	/// to the target or one of its neighbors without passing through anything blocked; ladders are followed as well
+	pub fn is_accessible_from(&self, start: Position, target: Position) -> bool {
+		if !self.in_bounds(start) || !self.in_bounds(target) { return false; }
+		let mut reached: Vec<Vec<bool>> = self.levels.iter().map(|x| vec![false; x.blocked_tiles.len()]).collect();
+		reached[start.z as usize][self.levels[start.z as usize].to_index(start.x, start.y)] = true;
+		let mut frontier = vec![start];
+		while let Some(current) = frontier.pop() {
+			if current.z == target.z && (current.x - target.x).abs() <= 1 && (current.y - target.y).abs() <= 1 { return true; }
+			let mut next_steps = Vec::new();
+			for dy in -1..=1 {
+				for dx in -1..=1 {
+					next_steps.push(Position::new(current.x + dx, current.y + dy, current.z));
+				}
+			}
+			if let Some(portal) = self.portals.iter().find(|p| p.has(current)) {
+				next_steps.push(portal.exit_from(current));
+			}
+			for next in next_steps.into_iter() {
+				if self.is_blocked_at(next) { continue; } // Also covers anywhere off the map, ie an INVALID exit
+				let index = self.levels[next.z as usize].to_index(next.x, next.y);
+				if reached[next.z as usize][index] { continue; }
+				reached[next.z as usize][index] = true;
+				frontier.push(next);
+			}
+		}
+		false
+	}
+	/// Lists the rooms that can't be reached from the room containing the start Position, by any combination of room
+	/// exits and ladders; the exits count in both directions, since the layouts only need to write them down once
+	pub fn find_unreachable_rooms(&self, start: Position) -> Vec<String> {
+		let rooms = &self.layout.rooms;
+		let start_index = if let Some(index) = self.layout.get_room_name(start).and_then(|x| self.layout.get_room_index(&x)) { index } else {
+			return rooms.iter().map(|x| x.name.clone()).collect();
+		};
+		let mut links: Vec<Vec<usize>> = vec![Vec::new(); rooms.len()];
+		for index in 0..rooms.len() {
+			for dest in self.layout.successors(index) {
+				links[index].push(dest);
+				links[dest].push(index);
+			}
+		}
+		for portal in self.portals.iter() {
+			let left = self.layout.get_room_name(portal.left).and_then(|x| self.layout.get_room_index(&x));
+			let right = self.layout.get_room_name(portal.right).and_then(|x| self.layout.get_room_index(&x));
+			if let (Some(left), Some(right)) = (left, right) {
+				links[left].push(right);
+				links[right].push(left);
+			}
+		}
+		let mut reached = vec![false; rooms.len()];
+		reached[start_index] = true;
+		let mut frontier = vec![start_index];
+		while let Some(current) = frontier.pop() {
+			for next in links[current].iter() {
+				if !reached[*next] {
+					reached[*next] = true;
+					frontier.push(*next);
+				}
+			}
+		}
+		rooms.iter().zip(reached.iter()).filter(|x| !*x.1).map(|x| x.0.name.clone()).collect()
+	}
+	/// Returns True if the Position contains an Entity or Tiletype that blocks line of sight
+	pub fn is_opaque_at(&self, target: Position) -> bool {
+		if !self.in_bounds(target) { return true; }
+		let index = self.levels[target.z as usize].to_index(target.x, target.y);
+		self.levels[target.z as usize].opaque_tiles[index]
+	}
 	/// Returns a list of all Obstructive Entities at the given Position, optionally with LOS from a given observer
 	pub fn get_obstructions_at(&self, targets: Vec<Position>, observer_enty: Option<Entity>) -> Option<Vec<(Position, Obstructor)>> {
 		let mut block_list = Vec::new();
@@ -131,11 +342,11 @@ impl WorldModel {
 		}
 	}
 	/// Tries to find the specified room in the world model, and if successful, tries to obtain a spawnpoint within
-	pub fn find_spawnpoint_in(&mut self, target_room: &str, template: SpawnTemplate, rng: &mut GlobalRng) -> Option<Vec<(String, Position)>> {
+	pub fn find_spawnpoint_in(&mut self, target_room: &str, template: SpawnTemplate, rng: &mut GlobalRng, mode: PlacementMode) -> Option<Vec<(String, Position)>> {
 		trace!("* find_spawnpoint_in {} for {:?}", target_room, template); // DEBUG: log the call to find_spawnpoint_in
 		if let Some(room_index) = self.layout.get_room_index(target_room) {
 			//self.layout.rooms[room_index].debug_print(); // DEBUG: display the current layout map of the room
-			return self.layout.rooms[room_index].find_open_space(template, rng);
+			return self.layout.rooms[room_index].find_open_space(template, rng, mode);
 		}
 		None
 	}
@@ -145,10 +356,12 @@ impl WorldModel {
 	}
 	/// Sets the state of a specific Position on the blocking map
 	pub fn set_blocked_state(&mut self, target: Position, state: bool) {
+		if !self.in_bounds(target) { return; }
 		self.levels[target.z as usize].set_blocked(target, state);
 	}
 	/// Sets the state of a specific Position on the opaque map
 	pub fn set_opaque_state(&mut self, target: Position, state: bool) {
+		if !self.in_bounds(target) { return; }
 		self.levels[target.z as usize].set_opaque(target, state);
 	}
 }
@@ -164,6 +377,9 @@ pub struct WorldMap {
 	pub visible_tiles: Vec<bool>,
 	pub blocked_tiles: Vec<bool>,
 	pub opaque_tiles: Vec<bool>,
+	pub tints: Vec<Option<u8>>, // The color theme of the room that each tile belongs to, if any
+	#[reflect(ignore)]
+	pub needs_reindex: bool, // If true, the blocked/opaque tilemaps will be completely rebuilt on the next update
 }
 impl WorldMap {
 	/// Generates a map from the default settings
@@ -177,6 +393,8 @@ impl WorldMap {
 			visible_tiles: vec![false; map_size],
 			blocked_tiles: vec![false; map_size],
 			opaque_tiles: vec![false; map_size],
+			tints: vec![None; map_size],
+			needs_reindex: true,
 		}
 	}
 	/// Converts an x, y pair into a tilemap index using the given map's width
@@ -203,6 +421,17 @@ impl WorldMap {
 	pub fn get_display_tile(&self, target: Position) -> Tile {
 		self.tiles[self.to_index(target.x, target.y)].clone()
 	}
+	/// As get_display_tile, but returns only the ScreenCell, with the room's color theme applied to floors and walls
+	pub fn get_tinted_cell(&self, target: Position) -> ScreenCell {
+		let index = self.to_index(target.x, target.y);
+		let mut cell = self.tiles[index].cell.clone();
+		if let Some(Some(tint)) = self.tints.get(index) {
+			if self.tiles[index].ttype == TileType::Floor || self.tiles[index].ttype == TileType::Wall {
+				cell.fg = *tint;
+			}
+		}
+		cell
+	}
 	/// Obtains whatever Entity is visible at the given Position, if any
 	pub fn get_visible_entity_at(&self, target: Position) -> Option<Entity> {
 		self.tiles[self.to_index(target.x, target.y)].get_visible_entity()
@@ -291,19 +520,12 @@ impl Tile {
 		//if self.contents.is_empty() {
 		//	self.contents.push((0, Entity::PLACEHOLDER));
 		//}
-		// Find the point in the stack where we'd like to insert the new Entity:
-		// at the top of the list of Entities with the same priority, *not* the top of the entire stack
-		// In general, if all the visible entities at a given point have the same priority,
-		// then the entity that will be shown will be the one that most-recently entered that tile
-		// If any entities have a higher priority, then those should be shown instead
-		let mut insertion_index = 0;
-		for enty in self.contents.iter() {
-			if new_item.0 < enty.0 {
-				insertion_index += 1;
-			}
-		}
-		// Insert the new entity at the top of the items of the same priority, not the entire stack
-		self.contents.insert(insertion_index, new_item);
+		// The stack is kept sorted by priority, highest first, and then by Entity ID, so that the entity on top doesn't
+		// depend on the order that things were added in: otherwise the shown glyph could change whenever the tile
+		// contents are rebuilt, ie after loading a game, even though nothing on the tile has moved
+		if self.contents.contains(&new_item) { return; }
+		self.contents.push(new_item);
+		self.contents.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
 	}
 	/// Retrieves the Entity ID of the most-visible Entity at this Tile
 	pub fn get_visible_entity(&self) -> Option<Entity> {
@@ -450,4 +672,110 @@ pub enum Obstructor {
 	Actor(Entity),
 	Object(TileType),
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn add_contents_skips_positions_off_the_map() {
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(5, 5));
+		let enty = Entity::from_raw(7);
+		model.add_contents(&vec![Position::new(2, 2, 0), Position::new(2, 2, 1), Position::new(-1, 2, 0)], 0, enty);
+		assert_eq!(model.get_contents_at(Position::new(2, 2, 0)), vec![enty]);
+		assert!(!model.can_place_at(&[Position::new(2, 2, 1)]));
+	}
+	#[test]
+	fn accessibility_is_checked_from_the_start_and_follows_ladders() {
+		let mut model = WorldModel::default();
+		let mut lower = WorldMap::new(7, 5);
+		for y in 0..5 {
+			let index = lower.to_index(3, y);
+			lower.tiles[index] = Tile::new_wall();
+		}
+		model.add_level(lower);
+		let (start, target) = (Position::new(1, 2, 0), Position::new(5, 2, 0));
+		// The target's neighbors are open floor, but the wall cuts them off from the start
+		assert!(!model.is_accessible_from(start, target));
+		// A pair of ladders through the deck above goes around the wall
+		model.add_level(WorldMap::new(7, 5));
+		model.add_portal(Position::new(1, 1, 0), Position::new(1, 1, 1), true);
+		model.add_portal(Position::new(5, 1, 1), Position::new(5, 1, 0), true);
+		assert!(model.is_accessible_from(start, target));
+		assert!(!model.is_accessible_from(Position::INVALID, target));
+	}
+	#[test]
+	fn stacked_items_show_the_same_glyph_whatever_order_they_arrived_in() {
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(5, 5));
+		let posn = Position::new(2, 2, 0);
+		let (wrench, crate_item, player) = (Entity::from_raw(4), Entity::from_raw(9), Entity::from_raw(1));
+		model.add_contents(&vec![posn], 0, crate_item);
+		model.add_contents(&vec![posn], 0, wrench);
+		let shown = model.levels[0].get_visible_entity_at(posn);
+		assert_eq!(shown, Some(wrench));
+		// Rebuilding the tile in the other order, as happens after a load, doesn't change which one is on top
+		model.remove_contents(&vec![posn], wrench);
+		model.remove_contents(&vec![posn], crate_item);
+		model.add_contents(&vec![posn], 0, wrench);
+		model.add_contents(&vec![posn], 0, crate_item);
+		assert_eq!(model.levels[0].get_visible_entity_at(posn), shown);
+		// Something with a higher priority still goes on top of both of them
+		model.add_contents(&vec![posn], 10, player);
+		assert_eq!(model.levels[0].get_visible_entity_at(posn), Some(player));
+	}
+	#[test]
+	fn area_queries_find_the_same_entities_as_checking_every_body() {
+		let mut model = WorldModel::default();
+		model.add_level(WorldMap::new(12, 12));
+		model.add_level(WorldMap::new(12, 12));
+		// A scattering of things, including one on the next deck up and a long one that only pokes into range
+		let bodies = vec![
+			(Entity::from_raw(1), vec![Position::new(5, 5, 0)]),
+			(Entity::from_raw(2), vec![Position::new(6, 7, 0)]),
+			(Entity::from_raw(3), vec![Position::new(0, 0, 0)]),
+			(Entity::from_raw(4), vec![Position::new(5, 6, 1)]),
+			(Entity::from_raw(5), vec![Position::new(8, 4, 0), Position::new(9, 4, 0), Position::new(10, 4, 0)]),
+			(Entity::from_raw(6), vec![Position::new(11, 11, 0)]),
+		];
+		for (enty, posns) in bodies.iter() {
+			model.add_contents(posns, 0, *enty);
+		}
+		for (center, range) in [(Position::new(5, 5, 0), 3), (Position::new(0, 0, 0), 2), (Position::new(11, 11, 0), 1)] {
+			let mut naive: Vec<Entity> = bodies.iter().filter(|(_, posns)| posns.iter().any(|x| x.in_range_of(&center, range)))
+				.map(|(enty, _)| *enty).collect();
+			let mut found = model.get_entities_in_range(center, range);
+			naive.sort();
+			found.sort();
+			assert_eq!(found, naive);
+		}
+		// The rectangle stops at the edges of the map instead of running off of it
+		let corner = model.get_contents_in_rect(Position::new(-3, -3, 0), Position::new(1, 1, 0));
+		assert_eq!(corner, vec![(Position::new(0, 0, 0), Entity::from_raw(3))]);
+		assert!(model.get_contents_in_rect(Position::new(0, 0, 5), Position::new(3, 3, 5)).is_empty());
+	}
+	#[test]
+	fn portal_indices_follow_the_levels_they_connect() {
+		let mut model = WorldModel::default();
+		assert_eq!(model.add_level(WorldMap::new(5, 5)), 0);
+		assert_eq!(model.add_level(WorldMap::new(5, 5)), 1);
+		model.add_portal(Position::new(1, 1, 0), Position::new(1, 1, 1), true);
+		// Adding a level on top doesn't disturb the portals below it
+		assert_eq!(model.add_level(WorldMap::new(5, 5)), 2);
+		model.add_portal(Position::new(3, 3, 1), Position::new(3, 3, 2), true);
+		assert_eq!(model.get_exit(Position::new(1, 1, 0)), Some(Position::new(1, 1, 1)));
+		assert_eq!(model.get_exit(Position::new(3, 3, 2)), Some(Position::new(3, 3, 1)));
+		model.layout.add_room(GraphRoom::from_tiles("bridge", vec![Position::new(2, 2, 2)]));
+		// Taking out the bottom level removes the portal to it, and brings the one above down by one
+		assert!(model.remove_level(0).is_some());
+		assert_eq!(model.levels.len(), 2);
+		assert_eq!(model.get_exit(Position::new(1, 1, 0)), None);
+		assert_eq!(model.get_exit(Position::new(3, 3, 0)), Some(Position::new(3, 3, 1)));
+		assert_eq!(model.get_exit(Position::new(3, 3, 1)), Some(Position::new(3, 3, 0)));
+		assert_eq!(model.layout.get_room_name(Position::new(2, 2, 1)).as_deref(), Some("bridge"));
+		assert!(model.remove_level(5).is_none());
+	}
+}
+
 // EOF